@@ -1,6 +1,12 @@
 // app.rs
 #![allow(dead_code)]
+use crate::cli::{StartWindow, TuiArgs};
+use crate::config::manifest::ProjectManifest;
+use crate::state::{AppState, StateUpdate};
 use crate::utils::centered_rect::centered_rect;
+use crate::windows::config::ConfigWindow;
+use crate::windows::main_window::MainWindow;
+use crate::windows::project_window::validate_project_structure;
 use crate::{
     popup::*,
     window::Window,                         // Removed WindowTransition
@@ -9,20 +15,27 @@ use crate::{
 use crossterm::event::KeyEvent;
 use ratatui::widgets::{Block, BorderType, Borders};
 use ratatui::{prelude::*, widgets::Paragraph, Frame};
-use std::any::Any;
-use std::collections::{HashMap, VecDeque};
+use std::collections::VecDeque;
+#[cfg(feature = "for_fuzzer")]
+use std::path::Path;
+
+#[cfg(feature = "for_fuzzer")]
+use crate::config::profiles::active_config_path;
+#[cfg(feature = "for_fuzzer")]
+use crate::windows::fuzzing_window::FuzzingWindow;
+#[cfg(feature = "for_fuzzer")]
+use flashfuzzemu::opts::EmuOpts;
 
 pub enum Request {
     Popup(Popup),
     PushWindow(Box<dyn Window>), // New request to push a window
     PopWindow,
-    PushProperty(String, Box<dyn Any>), // New request to push a property
-    GetProperty(String),                // New request to get a property
+    Update(StateUpdate), // Change the shared AppState
 }
 
 pub struct App {
     window_stack: VecDeque<Box<dyn Window>>,
-    pub properties: HashMap<String, Box<dyn Any>>,
+    pub state: AppState,
     popup: Option<Popup>,
 }
 
@@ -30,15 +43,76 @@ impl App {
     pub fn new() -> Self {
         let mut stack: VecDeque<Box<dyn Window>> = VecDeque::new();
         stack.push_back(Box::new(ProjectWindow::new()));
-        let mut properties: HashMap<String, Box<dyn Any>> = HashMap::new();
-        properties.insert("port".into(), Box::new(1337u16) as Box<dyn Any>); // Default port
         App {
             window_stack: stack,
-            properties,
+            state: AppState::default(),
             popup: None,
         }
     }
 
+    // Open the project given on the command line, skipping ProjectWindow.
+    // Leaves the app untouched when no project was given.
+    pub fn open_project(&mut self, args: &TuiArgs) -> Result<(), String> {
+        let Some(project) = args.project.as_ref() else {
+            return Ok(());
+        };
+        validate_project_structure(project)
+            .map_err(|e| format!("{} is not a project: {}", project.display(), e))?;
+        // Resolve the binary before moving into the project
+        let binary = match args.binary.as_ref() {
+            Some(b) => Some(
+                b.canonicalize()
+                    .map_err(|e| format!("Binary {}: {}", b.display(), e))?,
+            ),
+            None => None,
+        };
+        let project = project
+            .canonicalize()
+            .map_err(|e| format!("Project {}: {}", project.display(), e))?;
+        std::env::set_current_dir(&project)
+            .map_err(|e| format!("Failed to set current directory: {}", e))?;
+
+        let manifest = ProjectManifest::load(&project)?;
+        self.state.apply(StateUpdate::Project(project));
+        self.state.apply(StateUpdate::Manifest(manifest));
+        // The command line wins over project.json, without changing it
+        if let Some(binary) = binary {
+            self.state.apply(StateUpdate::BinaryPath(binary));
+        }
+        if let Some(addr) = args.load_address {
+            self.state.apply(StateUpdate::LoadAddress(addr));
+        }
+        if let Some(port) = args.port {
+            self.state.apply(StateUpdate::Port(port));
+        }
+
+        self.window_stack.clear();
+        self.window_stack.push_back(Box::new(MainWindow::new()));
+        match args.window {
+            None => {}
+            Some(StartWindow::Config) => {
+                self.window_stack.push_back(Box::new(ConfigWindow::new()));
+            }
+            #[cfg(feature = "for_fuzzer")]
+            Some(StartWindow::Fuzz) => {
+                // Normally pushed by the config window when it is closed
+                let config = std::fs::read_to_string(active_config_path(Path::new(".")))
+                    .map_err(|e| format!("Error reading config file: {}", e))?;
+                let opts = EmuOpts::from_json(config)
+                    .map_err(|e| format!("Invalid EmuOpts configuration: {}", e))?;
+                self.state.apply(StateUpdate::EmuOpts(opts));
+                self.window_stack.push_back(Box::new(FuzzingWindow::new()));
+            }
+            #[cfg(not(feature = "for_fuzzer"))]
+            Some(StartWindow::Fuzz) => {
+                return Err(
+                    "Built without the for_fuzzer feature, fuzzing is not available".into(),
+                );
+            }
+        }
+        Ok(())
+    }
+
     pub fn handle_input(&mut self, key: KeyEvent) -> bool {
         let mut reqs = None;
         if self.popup.is_some() {
@@ -50,7 +124,11 @@ impl App {
             && self.window_stack.back().unwrap().capture_all_input()
         {
             // If there's a current window, check if it captures all input
-            reqs = self.window_stack.back_mut().unwrap().handle_input(key);
+            reqs = self
+                .window_stack
+                .back_mut()
+                .unwrap()
+                .handle_input(key, &self.state);
         } else {
             match key.code {
                 crossterm::event::KeyCode::Char('q') => return false, // Quit
@@ -62,7 +140,7 @@ impl App {
                 }
                 _ => {
                     if let Some(current) = self.window_stack.back_mut() {
-                        reqs = current.handle_input(key);
+                        reqs = current.handle_input(key, &self.state);
                     }
                 }
             }
@@ -102,7 +180,7 @@ impl App {
         self.render_footer(f, chunks[2]);
         // Current window
         if let Some(current) = self.window_stack.back_mut() {
-            let v = current.render(f, chunks[1]);
+            let v = current.render(f, chunks[1], &self.state);
             if let Some(reqs) = v {
                 for r in reqs {
                     self.handle_request(r);
@@ -111,7 +189,7 @@ impl App {
         }
         if let Some(p) = self.popup.as_mut() {
             let area_popup = centered_rect(30, 20, area); // Changed from size
-            p.render(f, area_popup);
+            p.render(f, area_popup, &self.state);
         }
     }
 
@@ -130,26 +208,14 @@ impl App {
                 } else {
                 }
             }
-            Request::PushProperty(s, p) => {
-                self.properties.insert(s, p);
-            }
-            Request::GetProperty(s) => {
-                let p = match self.properties.get(&s) {
-                    None => {
-                        self.window_stack.pop_back();
-                        self.popup = Some(Popup::new(
-                            PopupType::Warning,
-                            format!(
-                                "The requested property was not found in the application state: {}",
-                                s
-                            ),
-                        ));
-                        return;
+            Request::Update(update) => {
+                let persist = update.is_persisted();
+                self.state.apply(update);
+                if persist {
+                    if let Err(e) = self.state.save_manifest() {
+                        self.popup = Some(Popup::new(PopupType::Warning, e));
                     }
-                    Some(v) => v,
-                };
-                let w = self.window_stack.back_mut().unwrap();
-                w.send_property(s.clone(), &**p);
+                }
             }
         }
     }
@@ -157,7 +223,7 @@ impl App {
     fn render_footer(&mut self, f: &mut Frame, area: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Ratio(1, 3); 3])
+            .constraints([Constraint::Ratio(1, 4); 4])
             .split(area); // Changed from size
 
         // Current window
@@ -167,19 +233,14 @@ impl App {
             .map(|w| w.name())
             .unwrap_or("Unknown Window");
 
-        // Attempt to get the current port
-        let port = self
-            .properties
-            .get("port")
-            .and_then(|p| p.downcast_ref::<u16>())
-            .map_or("1337".to_string(), |p| p.to_string());
-
-        // Get current project name
+        let port = match self.state.campaign_status() {
+            Some(status) => format!("{} ({})", self.state.port, status),
+            None => self.state.port.to_string(),
+        };
         let project = self
-            .properties
-            .get("project_name")
-            .and_then(|p| p.downcast_ref::<String>())
-            .map_or("No project".to_string(), |p| p.clone());
+            .state
+            .project_name()
+            .unwrap_or("No project".to_string());
 
         // Make paragraph widgets
         let window_para = Paragraph::new(window_name)
@@ -208,6 +269,19 @@ impl App {
                     ),
             );
 
+        let profile_para = Paragraph::new(format!("Profile: {}", self.state.profile))
+            .alignment(Alignment::Center)
+            .block(
+                Block::new()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .style(
+                        Style::default()
+                            .fg(Color::Indexed(6))
+                            .add_modifier(Modifier::ITALIC),
+                    ),
+            );
+
         let port_para = Paragraph::new(format!("Port: {}", port))
             .alignment(Alignment::Center)
             .block(
@@ -223,7 +297,8 @@ impl App {
 
         // Render
         f.render_widget(project_para, chunks[0]);
-        f.render_widget(window_para, chunks[1]);
-        f.render_widget(port_para, chunks[2]);
+        f.render_widget(profile_para, chunks[1]);
+        f.render_widget(window_para, chunks[2]);
+        f.render_widget(port_para, chunks[3]);
     }
 }
@@ -0,0 +1,72 @@
+// campaign/client.rs
+#![cfg(feature = "for_fuzzer")]
+use super::session::{FuzzerSession, SessionStatus};
+use super::stats::CampaignStats;
+
+// A client that keeps dying is given up on after this many automatic restarts
+pub const MAX_AUTO_RESTARTS: u32 = 5;
+
+// One fuzzer process of a campaign, optionally pinned to a core. All clients
+// of a campaign connect to the same broker port.
+pub struct FuzzerClient {
+    pub id: usize,
+    pub core: Option<usize>,
+    pub session: Option<FuzzerSession>,
+    pub stats: CampaignStats,
+    pub restarts: u32,
+    // Set when the user stopped the client, so it is not brought back automatically
+    pub stopped_by_user: bool,
+    // Bumped on every spawn so output from an earlier process can be ignored
+    pub generation: u64,
+}
+
+impl FuzzerClient {
+    pub fn new(id: usize, core: Option<usize>) -> Self {
+        Self {
+            id,
+            core,
+            session: None,
+            stats: CampaignStats::default(),
+            restarts: 0,
+            stopped_by_user: false,
+            generation: 0,
+        }
+    }
+
+    pub fn attach(&mut self, session: FuzzerSession) {
+        self.session = Some(session);
+        self.stats = CampaignStats::default();
+        self.stopped_by_user = false;
+        self.generation += 1;
+    }
+
+    pub fn status(&self) -> Option<SessionStatus> {
+        self.session.as_ref().map(|s| s.status())
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.session.as_ref().is_some_and(|s| s.is_alive())
+    }
+
+    pub fn poll(&mut self) -> Option<SessionStatus> {
+        self.session.as_mut().map(|s| s.poll())
+    }
+
+    // Died on its own, as opposed to finishing cleanly or being stopped
+    pub fn needs_restart(&self) -> bool {
+        if self.stopped_by_user || self.restarts >= MAX_AUTO_RESTARTS {
+            return false;
+        }
+        matches!(
+            self.status(),
+            Some(SessionStatus::Signaled(_)) | Some(SessionStatus::Exited(1..))
+        )
+    }
+
+    pub fn stop(&mut self) {
+        self.stopped_by_user = true;
+        if let Some(s) = self.session.as_mut() {
+            s.stop();
+        }
+    }
+}
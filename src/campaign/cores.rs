@@ -0,0 +1,125 @@
+// campaign/cores.rs
+
+pub fn available_cores() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+// Parse a core list in the same syntax LibAFL uses on the command line:
+// `all`, `3`, `0-3`, `0,2,4-7`. The result is sorted and deduplicated.
+// `none` gives an empty list, i.e. a single client that is not pinned.
+pub fn parse_core_spec(spec: &str) -> Result<Vec<usize>, String> {
+    let spec = spec.trim();
+    let max = available_cores();
+    if spec.eq_ignore_ascii_case("none") {
+        return Ok(vec![]);
+    }
+    if spec.eq_ignore_ascii_case("all") {
+        return Ok((0..max).collect());
+    }
+    let mut cores = vec![];
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (from, to) = match part.split_once('-') {
+            Some((a, b)) => (parse_core(a)?, parse_core(b)?),
+            None => {
+                let c = parse_core(part)?;
+                (c, c)
+            }
+        };
+        if from > to {
+            return Err(format!("Invalid core range: {}", part));
+        }
+        cores.extend(from..=to);
+    }
+    if cores.is_empty() {
+        return Err("No cores given".to_string());
+    }
+    cores.sort_unstable();
+    cores.dedup();
+    if let Some(c) = cores.iter().find(|&&c| c >= max) {
+        return Err(format!(
+            "Core {} does not exist, this machine has {} cores",
+            c, max
+        ));
+    }
+    Ok(cores)
+}
+
+fn parse_core(s: &str) -> Result<usize, String> {
+    s.trim()
+        .parse::<usize>()
+        .map_err(|_| format!("Invalid core number: {}", s.trim()))
+}
+
+pub fn format_core_spec(cores: &[usize]) -> String {
+    if cores.is_empty() {
+        return "unpinned".to_string();
+    }
+    let mut parts: Vec<String> = vec![];
+    let mut i = 0;
+    while i < cores.len() {
+        let start = cores[i];
+        while i + 1 < cores.len() && cores[i + 1] == cores[i] + 1 {
+            i += 1;
+        }
+        if cores[i] == start {
+            parts.push(start.to_string());
+        } else {
+            parts.push(format!("{}-{}", start, cores[i]));
+        }
+        i += 1;
+    }
+    parts.join(",")
+}
+
+// Pin the calling process to a single core
+#[cfg(all(feature = "for_fuzzer", target_os = "linux"))]
+pub fn pin_to_core(core: usize) -> Result<(), String> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut set);
+        libc::CPU_SET(core, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(format!(
+                "Failed to pin to core {}: {}",
+                core,
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "for_fuzzer", not(target_os = "linux")))]
+pub fn pin_to_core(core: usize) -> Result<(), String> {
+    Err(format!(
+        "CPU pinning is not supported on this platform (core {})",
+        core
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_core_spec() {
+        assert_eq!(parse_core_spec("0").unwrap(), vec![0]);
+        assert_eq!(parse_core_spec("none").unwrap(), Vec::<usize>::new());
+        assert_eq!(
+            parse_core_spec("all").unwrap(),
+            (0..available_cores()).collect::<Vec<_>>()
+        );
+        assert!(parse_core_spec("").is_err());
+        assert!(parse_core_spec("3-1").is_err());
+        assert!(parse_core_spec("x").is_err());
+        assert!(parse_core_spec(&available_cores().to_string()).is_err());
+    }
+
+    #[test]
+    fn test_format_core_spec() {
+        assert_eq!(format_core_spec(&[]), "unpinned");
+        assert_eq!(format_core_spec(&[0, 1, 2, 3, 8, 10, 11]), "0-3,8,10-11");
+    }
+}
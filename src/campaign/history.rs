@@ -0,0 +1,199 @@
+// campaign/history.rs
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[cfg(feature = "for_fuzzer")]
+use super::stats::CampaignStats;
+
+// One sample per second for the last hour
+#[cfg(feature = "for_fuzzer")]
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+pub const RECENT_CAPACITY: usize = 3600;
+// Whole-run buffer gets decimated whenever it fills up
+pub const OVERALL_CAPACITY: usize = 2048;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatsSample {
+    // Seconds since the campaign started
+    pub elapsed: f64,
+    pub executions: u64,
+    pub execs_per_sec: f64,
+    pub corpus_size: u64,
+    pub crashes: u64,
+    pub unique_bbs: Option<u64>,
+}
+
+#[cfg(feature = "for_fuzzer")]
+impl StatsSample {
+    pub fn from_stats(stats: &CampaignStats) -> Self {
+        Self {
+            elapsed: stats.run_time().as_secs_f64(),
+            executions: stats.executions,
+            execs_per_sec: stats.execs_per_sec,
+            corpus_size: stats.corpus_size,
+            crashes: stats.crashes,
+            unique_bbs: stats.unique_bbs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryZoom {
+    Last5Min,
+    LastHour,
+    WholeRun,
+}
+
+impl HistoryZoom {
+    pub fn next(self) -> Self {
+        match self {
+            HistoryZoom::Last5Min => HistoryZoom::LastHour,
+            HistoryZoom::LastHour => HistoryZoom::WholeRun,
+            HistoryZoom::WholeRun => HistoryZoom::Last5Min,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HistoryZoom::Last5Min => "last 5 min",
+            HistoryZoom::LastHour => "last 1 h",
+            HistoryZoom::WholeRun => "whole run",
+        }
+    }
+
+    pub fn window(&self) -> Option<Duration> {
+        match self {
+            HistoryZoom::Last5Min => Some(Duration::from_secs(5 * 60)),
+            HistoryZoom::LastHour => Some(Duration::from_secs(60 * 60)),
+            HistoryZoom::WholeRun => None,
+        }
+    }
+}
+
+// Per-campaign sample history. `recent` is a ring buffer at full resolution,
+// `overall` keeps the whole run at a resolution that halves every time it fills.
+#[derive(Debug, Clone)]
+pub struct StatsHistory {
+    recent: VecDeque<StatsSample>,
+    overall: Vec<StatsSample>,
+    overall_stride: usize,
+    since_overall: usize,
+}
+
+impl Default for StatsHistory {
+    fn default() -> Self {
+        Self {
+            recent: VecDeque::with_capacity(RECENT_CAPACITY),
+            overall: Vec::new(),
+            overall_stride: 1,
+            since_overall: 0,
+        }
+    }
+}
+
+impl StatsHistory {
+    pub fn from_samples(samples: Vec<StatsSample>) -> Self {
+        let mut history = Self::default();
+        for sample in samples {
+            history.push(sample);
+        }
+        history
+    }
+
+    // Record the current stats if at least SAMPLE_INTERVAL passed since the last sample
+    #[cfg(feature = "for_fuzzer")]
+    pub fn record(&mut self, stats: &CampaignStats) {
+        let sample = StatsSample::from_stats(stats);
+        if let Some(last) = self.recent.back() {
+            if sample.elapsed - last.elapsed < SAMPLE_INTERVAL.as_secs_f64() {
+                return;
+            }
+        }
+        self.push(sample);
+    }
+
+    pub fn push(&mut self, sample: StatsSample) {
+        if self.recent.len() == RECENT_CAPACITY {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(sample);
+
+        self.since_overall += 1;
+        if self.since_overall >= self.overall_stride {
+            self.since_overall = 0;
+            self.overall.push(sample);
+            if self.overall.len() >= OVERALL_CAPACITY {
+                self.overall = self.overall.iter().step_by(2).copied().collect();
+                self.overall_stride *= 2;
+            }
+        }
+    }
+
+    pub fn latest(&self) -> Option<&StatsSample> {
+        self.recent.back()
+    }
+
+    pub fn samples(&self, zoom: HistoryZoom) -> Vec<StatsSample> {
+        let latest = match self.latest() {
+            Some(s) => s.elapsed,
+            None => return vec![],
+        };
+        match zoom.window() {
+            Some(window) => {
+                let from = latest - window.as_secs_f64();
+                self.recent
+                    .iter()
+                    .filter(|s| s.elapsed >= from)
+                    .copied()
+                    .collect()
+            }
+            None => {
+                let mut all = self.overall.clone();
+                // Always end on the newest sample even if it was not kept by the stride
+                if all.last().map(|s| s.elapsed) != Some(latest) {
+                    all.push(*self.recent.back().unwrap());
+                }
+                all
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(elapsed: f64) -> StatsSample {
+        StatsSample {
+            elapsed,
+            executions: elapsed as u64 * 100,
+            execs_per_sec: 100.0,
+            corpus_size: elapsed as u64,
+            crashes: 0,
+            unique_bbs: None,
+        }
+    }
+
+    #[test]
+    fn test_history_zoom_windows() {
+        let history = StatsHistory::from_samples((0..1000).map(|i| sample(i as f64)).collect());
+        let last5 = history.samples(HistoryZoom::Last5Min);
+        assert_eq!(last5.first().unwrap().elapsed, 699.0);
+        assert_eq!(last5.last().unwrap().elapsed, 999.0);
+        assert_eq!(history.samples(HistoryZoom::WholeRun).len(), 1000);
+    }
+
+    #[test]
+    fn test_history_keeps_whole_run_bounded() {
+        let n = RECENT_CAPACITY * 3;
+        let history = StatsHistory::from_samples((0..n).map(|i| sample(i as f64)).collect());
+        let all = history.samples(HistoryZoom::WholeRun);
+        assert!(all.len() < OVERALL_CAPACITY);
+        assert_eq!(all.first().unwrap().elapsed, 0.0);
+        assert_eq!(all.last().unwrap().elapsed, (n - 1) as f64);
+        assert_eq!(
+            history.samples(HistoryZoom::LastHour).len(),
+            RECENT_CAPACITY
+        );
+    }
+}
@@ -0,0 +1,311 @@
+// campaign/limits.rs
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "for_fuzzer")]
+use super::stats::CampaignStats;
+use crate::config::manifest::ProjectManifest;
+
+// Conditions that end a campaign. Anything left as None is unbounded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CampaignLimits {
+    pub max_duration_secs: Option<u64>,
+    pub max_execs: Option<u64>,
+    pub max_crashes: Option<u64>,
+    pub max_idle_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    Duration,
+    Execs,
+    Crashes,
+    Idle,
+}
+
+impl LimitKind {
+    pub const ALL: [LimitKind; 4] = [
+        LimitKind::Duration,
+        LimitKind::Execs,
+        LimitKind::Crashes,
+        LimitKind::Idle,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LimitKind::Duration => "Time budget",
+            LimitKind::Execs => "Exec budget",
+            LimitKind::Crashes => "Stop after crashes",
+            LimitKind::Idle => "Stop without new coverage for",
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            LimitKind::Duration | LimitKind::Idle => "e.g. 90s, 30m, 12h, 2d or empty for none",
+            LimitKind::Execs => "e.g. 500000, 250k, 10M, 1G or empty for none",
+            LimitKind::Crashes => "e.g. 1 or empty for none",
+        }
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    pub fn reached_message(&self) -> &'static str {
+        match self {
+            LimitKind::Duration => "time budget reached",
+            LimitKind::Execs => "exec budget reached",
+            LimitKind::Crashes => "crash limit reached",
+            LimitKind::Idle => "no new coverage within the idle limit",
+        }
+    }
+}
+
+// How far a campaign is towards one limit
+#[cfg(feature = "for_fuzzer")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimitProgress {
+    pub kind: LimitKind,
+    pub ratio: f64,
+    pub label: String,
+}
+
+impl CampaignLimits {
+    // From project.json, unbounded until they are first saved
+    pub fn load(project_path: &Path) -> Result<Self, String> {
+        Ok(ProjectManifest::load(project_path)?
+            .limits
+            .unwrap_or_default())
+    }
+
+    pub fn save(&self, project_path: &Path) -> Result<(), String> {
+        ProjectManifest::update(project_path, |m| m.limits = Some(self.clone()))
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.count() == 0
+    }
+
+    // Number of limits that are set
+    pub fn count(&self) -> usize {
+        LimitKind::ALL
+            .iter()
+            .filter(|k| self.get(**k).is_some())
+            .count()
+    }
+
+    pub fn get(&self, kind: LimitKind) -> Option<u64> {
+        match kind {
+            LimitKind::Duration => self.max_duration_secs,
+            LimitKind::Execs => self.max_execs,
+            LimitKind::Crashes => self.max_crashes,
+            LimitKind::Idle => self.max_idle_secs,
+        }
+    }
+
+    pub fn set(&mut self, kind: LimitKind, value: Option<u64>) {
+        match kind {
+            LimitKind::Duration => self.max_duration_secs = value,
+            LimitKind::Execs => self.max_execs = value,
+            LimitKind::Crashes => self.max_crashes = value,
+            LimitKind::Idle => self.max_idle_secs = value,
+        }
+    }
+
+    // Parse user input for a limit, empty input clears it
+    pub fn parse(kind: LimitKind, input: &str) -> Result<Option<u64>, String> {
+        let input = input.trim();
+        if input.is_empty() || input.eq_ignore_ascii_case("none") {
+            return Ok(None);
+        }
+        let value = match kind {
+            LimitKind::Duration | LimitKind::Idle => parse_duration(input)?.as_secs(),
+            LimitKind::Execs => parse_quantity(input)?,
+            LimitKind::Crashes => input
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid crash count: {}", input))?,
+        };
+        if value == 0 {
+            return Err(format!("{} must be greater than zero", kind.label()));
+        }
+        Ok(Some(value))
+    }
+
+    pub fn display(&self, kind: LimitKind) -> String {
+        match (kind, self.get(kind)) {
+            (_, None) => "none".to_string(),
+            (LimitKind::Duration | LimitKind::Idle, Some(v)) => {
+                format_short_duration(Duration::from_secs(v))
+            }
+            (_, Some(v)) => v.to_string(),
+        }
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    pub fn progress(&self, stats: &CampaignStats) -> Vec<LimitProgress> {
+        let mut ret = vec![];
+        for kind in LimitKind::ALL {
+            let Some(max) = self.get(kind) else {
+                continue;
+            };
+            let (current, label) = match kind {
+                LimitKind::Duration => {
+                    let elapsed = stats.run_time().as_secs();
+                    (
+                        elapsed,
+                        format!(
+                            "{} left",
+                            format_short_duration(Duration::from_secs(max.saturating_sub(elapsed)))
+                        ),
+                    )
+                }
+                LimitKind::Execs => (
+                    stats.executions,
+                    format!("{} / {} execs", stats.executions, max),
+                ),
+                LimitKind::Crashes => (
+                    stats.crashes,
+                    format!("{} / {} crashes", stats.crashes, max),
+                ),
+                LimitKind::Idle => {
+                    let idle = stats
+                        .since_last_new_path()
+                        .unwrap_or_else(|| stats.run_time())
+                        .as_secs();
+                    (
+                        idle,
+                        format!(
+                            "idle {} of {}",
+                            format_short_duration(Duration::from_secs(idle)),
+                            format_short_duration(Duration::from_secs(max))
+                        ),
+                    )
+                }
+            };
+            ret.push(LimitProgress {
+                kind,
+                ratio: (current as f64 / max as f64).min(1.0),
+                label,
+            });
+        }
+        ret
+    }
+
+    // First limit the campaign has hit, if any
+    #[cfg(feature = "for_fuzzer")]
+    pub fn reached(&self, stats: &CampaignStats) -> Option<LimitKind> {
+        self.progress(stats)
+            .into_iter()
+            .find(|p| p.ratio >= 1.0)
+            .map(|p| p.kind)
+    }
+}
+
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (num, mult) = match s.chars().last() {
+        Some('s') => (&s[..s.len() - 1], 1),
+        Some('m') => (&s[..s.len() - 1], 60),
+        Some('h') => (&s[..s.len() - 1], 3600),
+        Some('d') => (&s[..s.len() - 1], 86400),
+        _ => (s, 1),
+    };
+    num.trim()
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid duration: {}", s))?
+        .checked_mul(mult)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Duration too large: {}", s))
+}
+
+pub fn parse_quantity(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (num, mult) = match s.chars().last() {
+        Some('k') | Some('K') => (&s[..s.len() - 1], 1_000),
+        Some('M') => (&s[..s.len() - 1], 1_000_000),
+        Some('G') => (&s[..s.len() - 1], 1_000_000_000),
+        _ => (s, 1),
+    };
+    num.trim()
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid number: {}", s))?
+        .checked_mul(mult)
+        .ok_or_else(|| format!("Number too large: {}", s))
+}
+
+pub fn format_short_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 86400 {
+        format!("{}d{}h", secs / 86400, (secs / 3600) % 24)
+    } else if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, (secs / 60) % 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_limits() {
+        assert_eq!(
+            CampaignLimits::parse(LimitKind::Duration, "2h"),
+            Ok(Some(7200))
+        );
+        assert_eq!(CampaignLimits::parse(LimitKind::Idle, "90"), Ok(Some(90)));
+        assert_eq!(
+            CampaignLimits::parse(LimitKind::Execs, "10M"),
+            Ok(Some(10_000_000))
+        );
+        assert_eq!(CampaignLimits::parse(LimitKind::Crashes, ""), Ok(None));
+        assert!(CampaignLimits::parse(LimitKind::Crashes, "0").is_err());
+        assert!(CampaignLimits::parse(LimitKind::Duration, "soon").is_err());
+        // Overflowing the multiplier is an error, not a panic
+        assert!(parse_duration("99999999999999999d").is_err());
+        assert!(parse_duration("9999999999999999999h").is_err());
+        assert!(parse_quantity("99999999999G").is_err());
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    #[test]
+    fn test_limits_reached() {
+        let limits = CampaignLimits {
+            max_execs: Some(1000),
+            max_crashes: Some(1),
+            ..Default::default()
+        };
+        let mut stats = CampaignStats {
+            executions: 500,
+            ..Default::default()
+        };
+        assert_eq!(limits.reached(&stats), None);
+        assert_eq!(limits.progress(&stats)[0].ratio, 0.5);
+        stats.crashes = 1;
+        assert_eq!(limits.reached(&stats), Some(LimitKind::Crashes));
+        assert_eq!(CampaignLimits::default().reached(&stats), None);
+    }
+
+    #[test]
+    fn test_load_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let p = dir.path();
+        assert_eq!(CampaignLimits::load(p), Ok(CampaignLimits::default()));
+
+        // Once saved they live in project.json, next to what it already has
+        ProjectManifest::update(p, |m| m.port = Some(4000)).unwrap();
+        let limits = CampaignLimits {
+            max_crashes: Some(1),
+            ..Default::default()
+        };
+        limits.save(p).unwrap();
+        assert_eq!(CampaignLimits::load(p), Ok(limits.clone()));
+        let manifest = ProjectManifest::load(p).unwrap();
+        assert_eq!(manifest.port, Some(4000));
+        assert_eq!(manifest.limits, Some(limits));
+    }
+}
@@ -0,0 +1,125 @@
+// campaign/log.rs
+#![cfg(feature = "for_fuzzer")]
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::LOGS_DIR;
+
+// Lines kept in memory, the log file always has everything
+pub const LOG_CAPACITY: usize = 10_000;
+
+// Output captured from the fuzzer, optionally teed into a file
+#[derive(Default)]
+pub struct FuzzerLog {
+    lines: VecDeque<String>,
+    file: Option<File>,
+    // Lines ever pushed, including the ones that fell out of memory
+    pushed: u64,
+}
+
+impl FuzzerLog {
+    // Tee into `logs/fuzzer-<timestamp>.log` in the project
+    pub fn tee_to_project(
+        &mut self,
+        project_path: &Path,
+        timestamp: &str,
+    ) -> Result<PathBuf, String> {
+        let dir = project_path.join(LOGS_DIR);
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create logs directory: {}", e))?;
+        let path = dir.join(format!("fuzzer-{}.log", timestamp));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open log file: {}", e))?;
+        self.file = Some(file);
+        Ok(path)
+    }
+
+    pub fn push(&mut self, line: String) {
+        if let Some(file) = self.file.as_mut() {
+            if writeln!(file, "{}", line).is_err() {
+                // Keep the in-memory log going even if the disk is full
+                self.file = None;
+            }
+        }
+        if self.lines.len() == LOG_CAPACITY {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+        self.pushed += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn lines(&self) -> &VecDeque<String> {
+        &self.lines
+    }
+
+    pub fn pushed(&self) -> u64 {
+        self.pushed
+    }
+
+    // Lines pushed after `pushed()` returned `seen`, as far as they are
+    // still in memory
+    pub fn lines_since(&self, seen: u64) -> impl Iterator<Item = &String> {
+        let new = (self.pushed.saturating_sub(seen) as usize).min(self.lines.len());
+        self.lines.iter().skip(self.lines.len() - new)
+    }
+
+    // Index of the next line containing `query` (case-insensitive), starting
+    // after `from` and wrapping around
+    pub fn search(&self, query: &str, from: usize, forward: bool) -> Option<usize> {
+        if self.is_empty() || query.is_empty() {
+            return None;
+        }
+        let len = self.lines.len();
+        let query = query.to_lowercase();
+        (1..=len)
+            .map(|step| {
+                if forward {
+                    (from + step) % len
+                } else {
+                    (from + len * 2 - step) % len
+                }
+            })
+            .find(|&i| self.lines[i].to_lowercase().contains(&query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_log_search_wraps() {
+        let mut log = FuzzerLog::default();
+        for l in ["start", "Crash found", "ok", "crash again"] {
+            log.push(l.to_string());
+        }
+        assert_eq!(log.search("crash", 0, true), Some(1));
+        assert_eq!(log.search("crash", 1, true), Some(3));
+        assert_eq!(log.search("crash", 3, true), Some(1));
+        assert_eq!(log.search("crash", 1, false), Some(3));
+        assert_eq!(log.search("missing", 0, true), None);
+    }
+
+    #[test]
+    fn test_log_tees_to_file() {
+        let dir = tempdir().unwrap();
+        let mut log = FuzzerLog::default();
+        let path = log.tee_to_project(dir.path(), "20240101-000000").unwrap();
+        log.push("hello".into());
+        log.push("world".into());
+        assert_eq!(fs::read_to_string(path).unwrap(), "hello\nworld\n");
+    }
+}
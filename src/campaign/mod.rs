@@ -0,0 +1,16 @@
+// campaign/mod.rs
+pub mod client;
+pub mod cores;
+pub mod history;
+pub mod limits;
+pub mod log;
+pub mod persist;
+pub mod pipe;
+pub mod runner;
+pub mod session;
+pub mod stale;
+pub mod stats;
+pub mod violations;
+
+// Fuzzer output kept in the project, also where violations are read from
+pub const LOGS_DIR: &str = "logs";
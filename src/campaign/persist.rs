@@ -0,0 +1,363 @@
+// campaign/persist.rs
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "for_fuzzer")]
+use std::{fs::OpenOptions, io::Write, time::Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::history::StatsSample;
+#[cfg(feature = "for_fuzzer")]
+use super::stats::CampaignStats;
+
+pub const STATS_DIR: &str = "stats";
+// Same cadence AFL uses for plot_data
+#[cfg(feature = "for_fuzzer")]
+pub const PERSIST_INTERVAL: Duration = Duration::from_secs(5);
+
+#[cfg(feature = "for_fuzzer")]
+const PLOT_DATA_HEADER: &str = "# relative_time, cycles_done, cur_item, corpus_count, pending_total, pending_favs, map_size, saved_crashes, saved_hangs, max_depth, execs_per_sec, total_execs, edges_found";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatsRecord {
+    // Unix time in seconds
+    pub timestamp: u64,
+    // Seconds since the campaign started
+    pub elapsed: f64,
+    pub execs: u64,
+    pub execs_per_sec: f64,
+    pub corpus_count: u64,
+    pub crashes: u64,
+    pub timeouts: u64,
+    pub coverage: Option<u64>,
+}
+
+impl StatsRecord {
+    #[cfg(feature = "for_fuzzer")]
+    pub fn from_stats(stats: &CampaignStats) -> Self {
+        Self {
+            timestamp: unix_time(),
+            elapsed: stats.run_time().as_secs_f64(),
+            execs: stats.executions,
+            execs_per_sec: stats.execs_per_sec,
+            corpus_count: stats.corpus_size,
+            crashes: stats.crashes,
+            timeouts: stats.timeouts,
+            coverage: stats.unique_bbs,
+        }
+    }
+
+    // Zero for an elapsed time a hand-edited or corrupt file made negative
+    // or too large
+    pub fn run_time(&self) -> Duration {
+        Duration::try_from_secs_f64(self.elapsed).unwrap_or_default()
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    pub fn to_plot_data(&self) -> String {
+        format!(
+            "{}, 0, 0, {}, 0, 0, 0.00%, {}, {}, 0, {:.2}, {}, {}",
+            self.elapsed as u64,
+            self.corpus_count,
+            self.crashes,
+            self.timeouts,
+            self.execs_per_sec,
+            self.execs,
+            self.coverage.unwrap_or(0)
+        )
+    }
+}
+
+impl From<&StatsRecord> for StatsSample {
+    fn from(r: &StatsRecord) -> Self {
+        StatsSample {
+            elapsed: r.elapsed,
+            executions: r.execs,
+            execs_per_sec: r.execs_per_sec,
+            corpus_size: r.corpus_count,
+            crashes: r.crashes,
+            unique_bbs: r.coverage,
+        }
+    }
+}
+
+// Appends stats records for one campaign to `stats/<campaign-id>.jsonl` and,
+// in AFL's format for afl-plot, to `stats/<campaign-id>.plot_data`.
+#[cfg(feature = "for_fuzzer")]
+pub struct StatsWriter {
+    campaign_id: String,
+    jsonl: File,
+    plot_data: File,
+    last_write: Option<Instant>,
+}
+
+#[cfg(feature = "for_fuzzer")]
+impl StatsWriter {
+    pub fn create(project_path: &Path, campaign_id: &str) -> Result<Self, String> {
+        let dir = project_path.join(STATS_DIR);
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create stats directory: {}", e))?;
+        let jsonl = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(format!("{}.jsonl", campaign_id)))
+            .map_err(|e| format!("Failed to open stats file: {}", e))?;
+        let mut plot_data = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(format!("{}.plot_data", campaign_id)))
+            .map_err(|e| format!("Failed to open plot_data: {}", e))?;
+        // A resumed campaign keeps the header it already has
+        if plot_data.metadata().map(|m| m.len() == 0).unwrap_or(false) {
+            writeln!(plot_data, "{}", PLOT_DATA_HEADER).map_err(|e| e.to_string())?;
+        }
+        Ok(Self {
+            campaign_id: campaign_id.to_string(),
+            jsonl,
+            plot_data,
+            last_write: None,
+        })
+    }
+
+    pub fn campaign_id(&self) -> &str {
+        &self.campaign_id
+    }
+
+    // Write a record if PERSIST_INTERVAL passed since the previous one
+    pub fn maybe_write(&mut self, stats: &CampaignStats) -> Result<(), String> {
+        if let Some(last) = self.last_write {
+            if last.elapsed() < PERSIST_INTERVAL {
+                return Ok(());
+            }
+        }
+        self.write(stats)
+    }
+
+    pub fn write(&mut self, stats: &CampaignStats) -> Result<(), String> {
+        let record = StatsRecord::from_stats(stats);
+        let line = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+        writeln!(self.jsonl, "{}", line)
+            .map_err(|e| format!("Failed to write stats record: {}", e))?;
+        writeln!(self.plot_data, "{}", record.to_plot_data())
+            .map_err(|e| format!("Failed to write plot_data: {}", e))?;
+        self.last_write = Some(Instant::now());
+        Ok(())
+    }
+}
+
+// Written to `stats/<campaign-id>.summary.json` when a campaign ends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CampaignSummary {
+    pub campaign_id: String,
+    pub reason: String,
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub duration_secs: u64,
+    pub execs: u64,
+    pub corpus_count: u64,
+    pub crashes: u64,
+    pub timeouts: u64,
+    pub coverage: Option<u64>,
+    pub clients: usize,
+}
+
+impl CampaignSummary {
+    #[cfg(feature = "for_fuzzer")]
+    pub fn new(campaign_id: &str, reason: &str, stats: &CampaignStats, clients: usize) -> Self {
+        let ended_at = unix_time();
+        let duration_secs = stats.run_time().as_secs();
+        Self {
+            campaign_id: campaign_id.to_string(),
+            reason: reason.to_string(),
+            started_at: ended_at.saturating_sub(duration_secs),
+            ended_at,
+            duration_secs,
+            execs: stats.executions,
+            corpus_count: stats.corpus_size,
+            crashes: stats.crashes,
+            timeouts: stats.timeouts,
+            coverage: stats.unique_bbs,
+            clients,
+        }
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    pub fn write(&self, project_path: &Path) -> Result<PathBuf, String> {
+        let dir = project_path.join(STATS_DIR);
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create stats directory: {}", e))?;
+        let path = dir.join(format!("{}.summary.json", self.campaign_id));
+        let s = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&path, s).map_err(|e| format!("Failed to write campaign summary: {}", e))?;
+        Ok(path)
+    }
+
+    // None if the campaign never finished, e.g. it is still running
+    pub fn load(project_path: &Path, campaign_id: &str) -> Result<Option<Self>, String> {
+        let path = project_path
+            .join(STATS_DIR)
+            .join(format!("{}.summary.json", campaign_id));
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let s = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read campaign summary: {}", e))?;
+        serde_json::from_str(&s)
+            .map(Some)
+            .map_err(|e| format!("Invalid campaign summary: {}", e))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CampaignInfo {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+// Campaigns recorded in the project, newest first
+pub fn list_campaigns(project_path: &Path) -> Vec<CampaignInfo> {
+    let mut campaigns: Vec<CampaignInfo> = match fs::read_dir(project_path.join(STATS_DIR)) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+            .filter_map(|p| {
+                let id = p.file_stem()?.to_str()?.to_string();
+                Some(CampaignInfo { id, path: p })
+            })
+            .collect(),
+        Err(_) => vec![],
+    };
+    campaigns.sort_by(|a, b| b.id.cmp(&a.id));
+    campaigns
+}
+
+// Load a stats file, skipping lines that do not parse (e.g. a torn last line)
+pub fn load_campaign(path: &Path) -> Result<Vec<StatsRecord>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|l| serde_json::from_str::<StatsRecord>(&l).ok())
+        .collect())
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Sortable id based on the current UTC time, e.g. `20261017-142501`
+#[cfg(feature = "for_fuzzer")]
+pub fn new_campaign_id() -> String {
+    format_timestamp(unix_time())
+}
+
+#[cfg(feature = "for_fuzzer")]
+pub fn format_timestamp(unix_secs: u64) -> String {
+    let days = (unix_secs / 86400) as i64;
+    let secs = unix_secs % 86400;
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+#[cfg(all(test, feature = "for_fuzzer"))]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "19700101-000000");
+        assert_eq!(format_timestamp(1_709_210_096), "20240229-123456");
+    }
+
+    #[test]
+    fn test_stats_writer_round_trip() {
+        let dir = tempdir().unwrap();
+        let mut writer = StatsWriter::create(dir.path(), "20240101-000000").unwrap();
+        let mut stats = CampaignStats {
+            executions: 42,
+            corpus_size: 3,
+            ..Default::default()
+        };
+        writer.write(&stats).unwrap();
+        stats.executions = 84;
+        writer.write(&stats).unwrap();
+
+        let campaigns = list_campaigns(dir.path());
+        assert_eq!(campaigns.len(), 1);
+        assert_eq!(campaigns[0].id, "20240101-000000");
+        let records = load_campaign(&campaigns[0].path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].execs, 84);
+        assert_eq!(records[1].corpus_count, 3);
+
+        let plot_path = dir.path().join(STATS_DIR).join("20240101-000000.plot_data");
+        let plot = fs::read_to_string(&plot_path).unwrap();
+        assert_eq!(plot.lines().count(), 3);
+        assert!(plot.starts_with("# relative_time"));
+
+        // The next campaign leaves this one's plot_data alone
+        let mut next = StatsWriter::create(dir.path(), "20240102-000000").unwrap();
+        next.write(&stats).unwrap();
+        assert_eq!(fs::read_to_string(&plot_path).unwrap(), plot);
+        let ids: Vec<String> = list_campaigns(dir.path())
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, vec!["20240102-000000", "20240101-000000"]);
+    }
+
+    #[test]
+    fn test_bad_elapsed() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("20240101-000000.jsonl");
+        let record = |elapsed: &str| {
+            format!(
+                "{{\"timestamp\": 0, \"elapsed\": {}, \"execs\": 1, \"execs_per_sec\": 0.0, \"corpus_count\": 0, \"crashes\": 0, \"timeouts\": 0, \"coverage\": null}}\n",
+                elapsed
+            )
+        };
+        fs::write(&path, record("-5.0") + &record("1e300") + &record("2.5")).unwrap();
+        let records = load_campaign(&path).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].run_time(), Duration::ZERO);
+        assert_eq!(records[1].run_time(), Duration::ZERO);
+        assert_eq!(records[2].run_time(), Duration::from_millis(2500));
+    }
+
+    #[test]
+    fn test_summary_is_not_listed_as_campaign() {
+        let dir = tempdir().unwrap();
+        let stats = CampaignStats {
+            crashes: 1,
+            ..Default::default()
+        };
+        let summary = CampaignSummary::new("20240101-000000", "crash limit reached", &stats, 2);
+        let path = summary.write(dir.path()).unwrap();
+        let read: CampaignSummary =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(read, summary);
+        assert!(list_campaigns(dir.path()).is_empty());
+    }
+}
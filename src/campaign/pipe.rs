@@ -0,0 +1,22 @@
+// campaign/pipe.rs
+#![cfg(feature = "for_fuzzer")]
+use std::io::{BufRead, BufReader, Read};
+use std::thread::{self, JoinHandle};
+
+// Read `reader` line by line on a background thread until EOF, which happens
+// once every process holding the write end has exited.
+pub fn spawn_line_reader<R, F>(reader: R, mut on_line: F) -> JoinHandle<()>
+where
+    R: Read + Send + 'static,
+    F: FnMut(String) + Send + 'static,
+{
+    thread::spawn(move || {
+        let reader = BufReader::new(reader);
+        for line in reader.split(b'\n') {
+            match line {
+                Ok(bytes) => on_line(String::from_utf8_lossy(&bytes).trim_end().to_string()),
+                Err(_) => break,
+            }
+        }
+    })
+}
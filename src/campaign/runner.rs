@@ -0,0 +1,356 @@
+// campaign/runner.rs
+#![cfg(feature = "for_fuzzer")]
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use flashfuzzemu::opts::EmuOpts;
+use flashfuzzer::fuzz;
+
+use super::client::{FuzzerClient, MAX_AUTO_RESTARTS};
+use super::cores::{format_core_spec, pin_to_core};
+use super::history::StatsHistory;
+use super::limits::CampaignLimits;
+use super::log::FuzzerLog;
+use super::persist::{new_campaign_id, CampaignSummary, StatsWriter};
+use super::pipe::spawn_line_reader;
+use super::session::{FuzzerSession, SessionStatus};
+use super::stats::{parse_monitor_line, CampaignStats};
+
+// What the clients are started with, kept around to restart single clients
+pub struct LaunchParams {
+    pub contents: Vec<u8>,
+    pub opts: EmuOpts,
+    pub port: u16,
+}
+
+// Runs a campaign: one client per core, supervised, with stats, history,
+// logs and limits. Shared by the Fuzzing window and the headless CLI, which
+// only differ in how they present it.
+#[derive(Default)]
+pub struct CampaignRunner {
+    pub context: Arc<RwLock<CampaignCtx>>,
+    launch: Option<LaunchParams>,
+}
+
+#[derive(Default)]
+pub struct CampaignCtx {
+    pub clients: Vec<FuzzerClient>,
+    // Clients stopped on request that are respawned once they have exited
+    pub pending_client_restarts: Vec<usize>,
+    pub restart_requested: bool,
+    // Set while the campaign has not been wrapped up with a summary
+    pub campaign_id: Option<String>,
+    pub limits: CampaignLimits,
+    // Why the campaign was ended by its limits
+    pub finish_reason: Option<String>,
+    // Totals over all clients
+    pub stats: CampaignStats,
+    pub history: StatsHistory,
+    pub stats_writer: Option<StatsWriter>,
+    pub log: FuzzerLog,
+}
+
+impl CampaignRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Start a new campaign in the current project. No cores means a single
+    // client that is not pinned anywhere.
+    pub fn start(
+        &mut self,
+        launch: LaunchParams,
+        cores: &[usize],
+        limits: CampaignLimits,
+    ) -> Result<String, String> {
+        self.note(format!(
+            "Starting {} client(s) on port {}, cores: {}",
+            cores.len().max(1),
+            launch.port,
+            format_core_spec(cores)
+        ));
+        self.launch = Some(launch);
+
+        let campaign_id = new_campaign_id();
+        let mut notes = vec![];
+        {
+            let mut ctx = self.context.write().unwrap();
+            ctx.clients = if cores.is_empty() {
+                vec![FuzzerClient::new(0, None)]
+            } else {
+                cores
+                    .iter()
+                    .enumerate()
+                    .map(|(id, &core)| FuzzerClient::new(id, Some(core)))
+                    .collect()
+            };
+            ctx.pending_client_restarts.clear();
+            ctx.restart_requested = false;
+            ctx.campaign_id = Some(campaign_id.clone());
+            ctx.limits = limits;
+            ctx.finish_reason = None;
+            ctx.stats = CampaignStats::default();
+            ctx.history = StatsHistory::default();
+            ctx.stats_writer = match StatsWriter::create(Path::new("."), &campaign_id) {
+                Ok(w) => {
+                    notes.push(format!("Recording stats for campaign {}", w.campaign_id()));
+                    Some(w)
+                }
+                Err(e) => {
+                    notes.push(format!("Not recording stats: {}", e));
+                    None
+                }
+            };
+            match ctx.log.tee_to_project(Path::new("."), &campaign_id) {
+                Ok(path) => notes.push(format!("Logging fuzzer output to {}", path.display())),
+                Err(e) => notes.push(format!("Not logging fuzzer output to disk: {}", e)),
+            }
+        }
+        for n in notes {
+            self.note(n);
+        }
+        let count = self.context.read().unwrap().clients.len();
+        for id in 0..count {
+            self.spawn_client(id)?;
+        }
+        Ok(campaign_id)
+    }
+
+    // Fork the fuzzer for client `id` and hook its output up to the log and stats
+    pub fn spawn_client(&mut self, id: usize) -> Result<(), String> {
+        let launch = match self.launch.as_ref() {
+            Some(l) => l,
+            None => return Err("Fuzzer was never started".to_string()),
+        };
+        let core = self.context.read().unwrap().clients[id].core;
+        let mut contents = launch.contents.clone();
+        let opts = launch.opts.clone();
+        let port = launch.port;
+        let (session, output) = FuzzerSession::spawn_piped(move || {
+            if let Some(core) = core {
+                if let Err(e) = pin_to_core(core) {
+                    eprintln!("{}", e);
+                }
+            }
+            match fuzz(&mut contents, opts, port as u64) {
+                Ok(_) => 0,
+                Err(_) => 1,
+            }
+        })?;
+        let pid = session.pid();
+        let generation = {
+            let mut ctx = self.context.write().unwrap();
+            let client = &mut ctx.clients[id];
+            client.attach(session);
+            client.generation
+        };
+        self.note(format!("Client {} started with PID {}", id, pid));
+
+        let ctx = self.context.clone();
+        spawn_line_reader(output, move |line| {
+            let mut ctx = ctx.write().unwrap();
+            let multi = ctx.clients.len() > 1;
+            if let Some(client) = ctx.clients.get_mut(id) {
+                if client.generation == generation {
+                    if let Some(record) = parse_monitor_line(&line) {
+                        client.stats.update(&record);
+                    }
+                }
+            }
+            if multi {
+                ctx.log.push(format!("[client {}] {}", id, line));
+            } else {
+                ctx.log.push(line);
+            }
+        });
+        Ok(())
+    }
+
+    // Messages from the tool itself go into the same log as the fuzzer output
+    pub fn note(&self, msg: impl AsRef<str>) {
+        self.context
+            .write()
+            .unwrap()
+            .log
+            .push(format!("[fuzzer_tui] {}", msg.as_ref()));
+    }
+
+    pub fn stop(&mut self) {
+        for client in self.context.write().unwrap().clients.iter_mut() {
+            client.stop();
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        let mut ctx = self.context.write().unwrap();
+        // Pause everything if anything is running, so the clients stay in step
+        let any_running = ctx
+            .clients
+            .iter()
+            .any(|c| c.status() == Some(SessionStatus::Running));
+        for client in ctx.clients.iter_mut() {
+            if let Some(s) = client.session.as_mut() {
+                if any_running {
+                    s.pause();
+                } else {
+                    s.resume();
+                }
+            }
+        }
+    }
+
+    // Stop every client and start over once they are gone. Returns false if
+    // nothing was running, in which case the caller starts a new campaign.
+    pub fn request_restart(&mut self) -> bool {
+        let mut ctx = self.context.write().unwrap();
+        if !ctx.clients.iter().any(|c| c.is_alive()) {
+            return false;
+        }
+        for client in ctx.clients.iter_mut() {
+            client.stop();
+        }
+        ctx.restart_requested = true;
+        true
+    }
+
+    pub fn stop_client(&mut self, id: usize) {
+        if let Some(client) = self.context.write().unwrap().clients.get_mut(id) {
+            client.stop();
+        }
+    }
+
+    pub fn restart_client(&mut self, id: usize) -> Result<(), String> {
+        let alive = match self.context.read().unwrap().clients.get(id) {
+            Some(c) => c.is_alive(),
+            None => return Ok(()),
+        };
+        if alive {
+            let mut ctx = self.context.write().unwrap();
+            ctx.clients[id].stop();
+            ctx.pending_client_restarts.push(id);
+            return Ok(());
+        }
+        self.spawn_client(id)
+    }
+
+    // Once every client is gone after a restart request, clear it and
+    // return true so the caller can start the campaign again
+    pub fn take_restart(&mut self) -> bool {
+        let mut ctx = self.context.write().unwrap();
+        if ctx.restart_requested && ctx.clients.iter().all(|c| !c.is_alive()) {
+            ctx.restart_requested = false;
+            return true;
+        }
+        false
+    }
+
+    // Reap the clients, bring back the ones that died, aggregate and record
+    // the stats and enforce the campaign limits. Returns the summary once,
+    // on the tick the campaign is wrapped up.
+    pub fn poll(&mut self) -> Option<CampaignSummary> {
+        let mut respawn = vec![];
+        let mut notes = vec![];
+        let mut ret = None;
+        {
+            let mut ctx = self.context.write().unwrap();
+            if ctx.clients.is_empty() {
+                return None;
+            }
+            let CampaignCtx {
+                clients,
+                stats,
+                history,
+                stats_writer,
+                log,
+                restart_requested,
+                pending_client_restarts,
+                campaign_id,
+                limits,
+                finish_reason,
+            } = &mut *ctx;
+            for client in clients.iter_mut() {
+                let was_alive = client.is_alive();
+                let status = client.poll();
+                if !was_alive {
+                    continue;
+                }
+                if let Some(status) = status.filter(|s| !s.is_alive()) {
+                    notes.push(format!("Client {}: {}", client.id, status.describe()));
+                    if pending_client_restarts.contains(&client.id) {
+                        respawn.push(client.id);
+                    } else if !*restart_requested && client.needs_restart() {
+                        client.restarts += 1;
+                        notes.push(format!(
+                            "Restarting client {} ({}/{})",
+                            client.id, client.restarts, MAX_AUTO_RESTARTS
+                        ));
+                        respawn.push(client.id);
+                    }
+                }
+            }
+            pending_client_restarts.retain(|id| !respawn.contains(id));
+            let all_dead = clients.iter().all(|c| !c.is_alive()) && respawn.is_empty();
+
+            // Nothing left to do for a campaign that was already wrapped up
+            if let Some(id) = campaign_id.clone() {
+                stats.aggregate(clients.iter().map(|c| &c.stats));
+                if !all_dead {
+                    history.record(stats);
+                    if finish_reason.is_none() && !*restart_requested {
+                        if let Some(kind) = limits.reached(stats) {
+                            *finish_reason = Some(kind.reached_message().to_string());
+                            notes.push(format!("Campaign finished: {}", kind.reached_message()));
+                            for client in clients.iter_mut() {
+                                client.stop();
+                            }
+                            respawn.clear();
+                        }
+                    }
+                }
+                if all_dead {
+                    stats.finish();
+                }
+                if let Some(writer) = stats_writer.as_mut() {
+                    // Always flush a final record once every client is gone
+                    let res = if all_dead {
+                        writer.write(stats)
+                    } else {
+                        writer.maybe_write(stats)
+                    };
+                    if let Err(e) = res {
+                        log.push(format!("[fuzzer_tui] Stopped recording stats: {}", e));
+                        *stats_writer = None;
+                    } else if all_dead {
+                        *stats_writer = None;
+                    }
+                }
+                if all_dead {
+                    let reason = match finish_reason.as_ref() {
+                        Some(r) => r.clone(),
+                        None if *restart_requested => "restarted".to_string(),
+                        None if clients.iter().all(|c| c.stopped_by_user) => {
+                            "stopped by user".to_string()
+                        }
+                        None => "all clients exited".to_string(),
+                    };
+                    let summary = CampaignSummary::new(&id, &reason, stats, clients.len());
+                    match summary.write(Path::new(".")) {
+                        Ok(path) => notes.push(format!("Summary written to {}", path.display())),
+                        Err(e) => notes.push(e),
+                    }
+                    ret = Some(summary);
+                    *campaign_id = None;
+                }
+            }
+        }
+        for n in notes {
+            self.note(n);
+        }
+        for id in respawn {
+            if let Err(e) = self.spawn_client(id) {
+                self.note(format!("Failed to restart client {}: {}", id, e));
+            }
+        }
+        ret
+    }
+}
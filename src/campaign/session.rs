@@ -0,0 +1,357 @@
+// campaign/session.rs
+#![cfg(feature = "for_fuzzer")]
+use std::fs::File;
+use std::os::fd::{FromRawFd, RawFd};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+// Time a child gets to exit after SIGTERM before it is sent SIGKILL
+pub const STOP_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionStatus {
+    Running,
+    Paused,
+    Stopping,
+    Exited(i32),
+    Signaled(i32),
+    // waitpid failed, so the child was reaped by someone else
+    Lost,
+}
+
+impl SessionStatus {
+    pub fn is_alive(&self) -> bool {
+        matches!(
+            self,
+            SessionStatus::Running | SessionStatus::Paused | SessionStatus::Stopping
+        )
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            SessionStatus::Running => "Running".into(),
+            SessionStatus::Paused => "Paused".into(),
+            SessionStatus::Stopping => "Stopping".into(),
+            SessionStatus::Exited(code) => format!("Exited with status {}", code),
+            SessionStatus::Signaled(sig) => {
+                format!("Killed by signal {} ({})", sig, signal_name(*sig))
+            }
+            SessionStatus::Lost => "Lost track of the fuzzer process".into(),
+        }
+    }
+}
+
+pub fn signal_name(sig: i32) -> &'static str {
+    match sig {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        _ => "unknown",
+    }
+}
+
+// Handle to a forked fuzzer process. The child is put in its own process group
+// so that signals also reach any clients the fuzzer forks itself.
+pub struct FuzzerSession {
+    pid: libc::pid_t,
+    status: SessionStatus,
+    started_at: Instant,
+    finished_at: Option<Instant>,
+    kill_deadline: Option<Instant>,
+}
+
+impl FuzzerSession {
+    // Fork and run `child` in the new process. The child never returns into the
+    // caller: it exits with the value returned by `child`, or 101 if it panics.
+    // Its stdout and stderr are redirected into a pipe whose read end is
+    // returned, so nothing the fuzzer prints reaches the TUI's terminal.
+    pub fn spawn_piped<F: FnOnce() -> i32>(child: F) -> Result<(Self, File), String> {
+        let (read_fd, write_fd) = make_pipe()?;
+        let session = Self::fork(child, read_fd, write_fd);
+        unsafe {
+            libc::close(write_fd);
+        }
+        match session {
+            Ok(session) => Ok((session, unsafe { File::from_raw_fd(read_fd) })),
+            Err(e) => {
+                unsafe {
+                    libc::close(read_fd);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn fork<F: FnOnce() -> i32>(child: F, read_fd: RawFd, write_fd: RawFd) -> Result<Self, String> {
+        unsafe {
+            // Handlers the parent installed, e.g. the headless fuzz command's,
+            // must not keep SIGTERM from ending the fuzzer. The signals stay
+            // blocked until the child has reset them, so an early stop() is
+            // not caught by the inherited handler.
+            let mut blocked: libc::sigset_t = std::mem::zeroed();
+            let mut old_mask: libc::sigset_t = std::mem::zeroed();
+            libc::sigemptyset(&mut blocked);
+            libc::sigaddset(&mut blocked, libc::SIGINT);
+            libc::sigaddset(&mut blocked, libc::SIGTERM);
+            libc::pthread_sigmask(libc::SIG_BLOCK, &blocked, &mut old_mask);
+
+            let pid = libc::fork();
+            if pid != 0 {
+                libc::pthread_sigmask(libc::SIG_SETMASK, &old_mask, std::ptr::null_mut());
+            }
+            if pid < 0 {
+                return Err(format!("Fork failed: {}", std::io::Error::last_os_error()));
+            }
+            if pid == 0 {
+                libc::setpgid(0, 0);
+                libc::signal(libc::SIGINT, libc::SIG_DFL);
+                libc::signal(libc::SIGTERM, libc::SIG_DFL);
+                libc::pthread_sigmask(libc::SIG_SETMASK, &old_mask, std::ptr::null_mut());
+                libc::close(read_fd);
+                libc::dup2(write_fd, libc::STDOUT_FILENO);
+                libc::dup2(write_fd, libc::STDERR_FILENO);
+                libc::close(write_fd);
+                let code = panic::catch_unwind(AssertUnwindSafe(child)).unwrap_or(101);
+                libc::_exit(code);
+            }
+            // Set from the parent as well so the group exists before we signal it
+            libc::setpgid(pid, pid);
+            Ok(Self {
+                pid,
+                status: SessionStatus::Running,
+                started_at: Instant::now(),
+                finished_at: None,
+                kill_deadline: None,
+            })
+        }
+    }
+
+    pub fn pid(&self) -> libc::pid_t {
+        self.pid
+    }
+
+    pub fn status(&self) -> SessionStatus {
+        self.status
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.status.is_alive()
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.finished_at
+            .unwrap_or_else(Instant::now)
+            .duration_since(self.started_at)
+    }
+
+    // Reap the child if it changed state and escalate a pending stop to
+    // SIGKILL once the grace period is over. Call this on every tick.
+    pub fn poll(&mut self) -> SessionStatus {
+        if !self.status.is_alive() {
+            return self.status;
+        }
+        let mut wstatus = 0;
+        let ret = unsafe {
+            libc::waitpid(
+                self.pid,
+                &mut wstatus,
+                libc::WNOHANG | libc::WUNTRACED | libc::WCONTINUED,
+            )
+        };
+        if ret == self.pid {
+            if libc::WIFEXITED(wstatus) {
+                self.finish(SessionStatus::Exited(libc::WEXITSTATUS(wstatus)));
+            } else if libc::WIFSIGNALED(wstatus) {
+                self.finish(SessionStatus::Signaled(libc::WTERMSIG(wstatus)));
+            } else if libc::WIFSTOPPED(wstatus) && self.status == SessionStatus::Running {
+                self.status = SessionStatus::Paused;
+            } else if libc::WIFCONTINUED(wstatus) && self.status == SessionStatus::Paused {
+                self.status = SessionStatus::Running;
+            }
+        } else if ret < 0 {
+            self.finish(SessionStatus::Lost);
+        }
+
+        if let Some(deadline) = self.kill_deadline {
+            if self.status == SessionStatus::Stopping && Instant::now() >= deadline {
+                self.signal(libc::SIGKILL);
+                self.kill_deadline = None;
+            }
+        }
+        self.status
+    }
+
+    // Ask the fuzzer to terminate. `poll` sends SIGKILL if it is still around
+    // after STOP_GRACE_PERIOD.
+    pub fn stop(&mut self) {
+        if !self.status.is_alive() || self.status == SessionStatus::Stopping {
+            return;
+        }
+        self.signal(libc::SIGTERM);
+        // A stopped process only acts on SIGTERM once it is continued
+        self.signal(libc::SIGCONT);
+        self.status = SessionStatus::Stopping;
+        self.kill_deadline = Some(Instant::now() + STOP_GRACE_PERIOD);
+    }
+
+    pub fn pause(&mut self) {
+        if self.status == SessionStatus::Running {
+            self.signal(libc::SIGSTOP);
+            self.status = SessionStatus::Paused;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.status == SessionStatus::Paused {
+            self.signal(libc::SIGCONT);
+            self.status = SessionStatus::Running;
+        }
+    }
+
+    fn signal(&self, sig: i32) {
+        unsafe {
+            if libc::kill(-self.pid, sig) != 0 {
+                libc::kill(self.pid, sig);
+            }
+        }
+    }
+
+    fn finish(&mut self, status: SessionStatus) {
+        self.status = status;
+        self.finished_at = Some(Instant::now());
+        self.kill_deadline = None;
+    }
+}
+
+// Pipe with close-on-exec set on both ends, returned as (read, write)
+fn make_pipe() -> Result<(RawFd, RawFd), String> {
+    let mut fds = [0 as RawFd; 2];
+    unsafe {
+        if libc::pipe(fds.as_mut_ptr()) != 0 {
+            return Err(format!(
+                "Failed to create pipe: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        for fd in fds {
+            libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+    }
+    Ok((fds[0], fds[1]))
+}
+
+impl Drop for FuzzerSession {
+    fn drop(&mut self) {
+        // Never leave an orphaned fuzzer behind when the window goes away
+        if self.status.is_alive() {
+            self.signal(libc::SIGKILL);
+            let mut wstatus = 0;
+            unsafe {
+                libc::waitpid(self.pid, &mut wstatus, 0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll_until_dead(session: &mut FuzzerSession) -> SessionStatus {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while session.poll().is_alive() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        session.status()
+    }
+
+    #[test]
+    fn test_session_reports_exit_status() {
+        let (mut session, _out) = FuzzerSession::spawn_piped(|| 3).unwrap();
+        assert!(session.pid() > 0);
+        assert_eq!(poll_until_dead(&mut session), SessionStatus::Exited(3));
+    }
+
+    #[test]
+    fn test_session_pipes_stdout() {
+        use std::io::{Read, Write};
+        // println! is captured by the test harness, so write to the fd directly
+        let (mut session, mut out) = FuzzerSession::spawn_piped(|| {
+            std::io::stdout().write_all(b"corpus: 4\n").unwrap();
+            0
+        })
+        .unwrap();
+        let mut s = String::new();
+        out.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "corpus: 4\n");
+        assert_eq!(poll_until_dead(&mut session), SessionStatus::Exited(0));
+    }
+
+    #[test]
+    fn test_session_stop_terminates_child() {
+        let (mut session, _out) = FuzzerSession::spawn_piped(|| loop {
+            std::thread::sleep(Duration::from_millis(50));
+        })
+        .unwrap();
+        session.stop();
+        assert_eq!(session.status(), SessionStatus::Stopping);
+        assert_eq!(
+            poll_until_dead(&mut session),
+            SessionStatus::Signaled(libc::SIGTERM)
+        );
+    }
+
+    extern "C" fn ignore_signal(_: libc::c_int) {}
+
+    #[test]
+    fn test_session_stop_ignores_parent_handler() {
+        // The child inherits this handler across fork unless it is reset
+        let previous = unsafe {
+            libc::signal(
+                libc::SIGTERM,
+                ignore_signal as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            )
+        };
+        let spawned = FuzzerSession::spawn_piped(|| loop {
+            std::thread::sleep(Duration::from_millis(50));
+        });
+        unsafe {
+            libc::signal(libc::SIGTERM, previous);
+        }
+        let (mut session, _out) = spawned.unwrap();
+        let started = Instant::now();
+        session.stop();
+        assert_eq!(
+            poll_until_dead(&mut session),
+            SessionStatus::Signaled(libc::SIGTERM)
+        );
+        assert!(started.elapsed() < STOP_GRACE_PERIOD);
+    }
+
+    #[test]
+    fn test_session_stop_while_paused() {
+        let (mut session, _out) = FuzzerSession::spawn_piped(|| loop {
+            std::thread::sleep(Duration::from_millis(50));
+        })
+        .unwrap();
+        session.pause();
+        assert_eq!(session.poll(), SessionStatus::Paused);
+        session.resume();
+        assert_eq!(session.status(), SessionStatus::Running);
+        session.pause();
+        session.stop();
+        assert_eq!(
+            poll_until_dead(&mut session),
+            SessionStatus::Signaled(libc::SIGTERM)
+        );
+    }
+}
@@ -0,0 +1,309 @@
+// campaign/stale.rs
+#![cfg(feature = "for_fuzzer")]
+// Which binary each campaign fuzzed, so a rebuilt firmware is noticed before
+// its corpus and crashes are taken for findings against the new build
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::firmware::fingerprint::{sha256, BinaryFingerprint};
+
+use super::persist::{list_campaigns, new_campaign_id, STATS_DIR};
+
+pub const CORPUS_DIR: &str = "corpus";
+pub const CRASHES_DIR: &str = "crashes";
+// Where corpus and crash files found against older binaries are moved
+pub const ARCHIVE_DIR: &str = "archive";
+
+fn record_path(project_path: &Path, campaign_id: &str) -> PathBuf {
+    project_path
+        .join(STATS_DIR)
+        .join(format!("{}.binary.json", campaign_id))
+}
+
+// Written to `stats/<campaign-id>.binary.json` when a campaign starts
+pub fn record_fingerprint(
+    project_path: &Path,
+    campaign_id: &str,
+    fingerprint: &BinaryFingerprint,
+) -> Result<(), String> {
+    let dir = project_path.join(STATS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create stats directory: {}", e))?;
+    let s = serde_json::to_string_pretty(fingerprint).map_err(|e| e.to_string())?;
+    fs::write(record_path(project_path, campaign_id), s)
+        .map_err(|e| format!("Failed to record the binary fingerprint: {}", e))
+}
+
+pub fn load_fingerprint(project_path: &Path, campaign_id: &str) -> Option<BinaryFingerprint> {
+    let s = fs::read_to_string(record_path(project_path, campaign_id)).ok()?;
+    serde_json::from_str(&s).ok()
+}
+
+// The newest campaign that recorded its binary
+pub fn last_fingerprint(project_path: &Path) -> Option<(String, BinaryFingerprint)> {
+    list_campaigns(project_path)
+        .into_iter()
+        .find_map(|c| load_fingerprint(project_path, &c.id).map(|fp| (c.id, fp)))
+}
+
+// Files the fuzzer wrote, without its hidden metadata and lock files
+fn inputs(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file())
+                .filter(|p| {
+                    p.file_name()
+                        .is_some_and(|n| !n.to_string_lossy().starts_with('.'))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleBinary {
+    // The last campaign that recorded a binary
+    pub campaign_id: String,
+    pub previous: BinaryFingerprint,
+    pub current: BinaryFingerprint,
+    pub corpus: usize,
+    pub crashes: usize,
+}
+
+impl StaleBinary {
+    pub fn describe(&self) -> String {
+        format!(
+            "The binary changed since campaign {}: {} inputs in {}/ and {} in {}/ were found against the old build",
+            self.campaign_id, self.corpus, CORPUS_DIR, self.crashes, CRASHES_DIR
+        )
+    }
+
+    // archive/<time>-<old sha>, unique for each binary that is set aside
+    fn archive_dir(&self, project_path: &Path) -> PathBuf {
+        project_path.join(ARCHIVE_DIR).join(format!(
+            "{}-{}",
+            new_campaign_id(),
+            self.previous.short()
+        ))
+    }
+}
+
+// Some when the last campaign fuzzed another binary and left inputs behind.
+// Projects whose campaigns never recorded a binary are not flagged.
+pub fn check_binary(project_path: &Path, current: &BinaryFingerprint) -> Option<StaleBinary> {
+    let (campaign_id, previous) = last_fingerprint(project_path)?;
+    if previous.sha256 == current.sha256 {
+        return None;
+    }
+    let corpus = inputs(&project_path.join(CORPUS_DIR)).len();
+    let crashes = inputs(&project_path.join(CRASHES_DIR)).len();
+    if corpus == 0 && crashes == 0 {
+        return None;
+    }
+    Some(StaleBinary {
+        campaign_id,
+        previous,
+        current: current.clone(),
+        corpus,
+        crashes,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleAction {
+    // Queue the old crashes as seeds so the new campaign replays them
+    Revalidate,
+    // Drop empty and duplicate corpus inputs
+    DropDuplicates,
+    // Archive corpus/ and crashes/ and start from empty directories
+    NewCampaignDir,
+}
+
+impl StaleAction {
+    pub const ALL: [StaleAction; 3] = [
+        StaleAction::Revalidate,
+        StaleAction::DropDuplicates,
+        StaleAction::NewCampaignDir,
+    ];
+
+    pub fn key(&self) -> char {
+        match self {
+            StaleAction::Revalidate => 'v',
+            StaleAction::DropDuplicates => 'd',
+            StaleAction::NewCampaignDir => 'n',
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StaleAction::Revalidate => "Re-validate crashes",
+            StaleAction::DropDuplicates => "Drop empty/duplicate inputs",
+            StaleAction::NewCampaignDir => "New campaign directory",
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            StaleAction::Revalidate => {
+                "seed the corpus with the old crashes, those that still crash are found again"
+            }
+            StaleAction::DropDuplicates => "set aside empty and duplicate corpus inputs",
+            StaleAction::NewCampaignDir => "archive corpus/ and crashes/, start empty",
+        }
+    }
+}
+
+fn move_file(from: &Path, to_dir: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(to_dir)
+        .map_err(|e| format!("Failed to create {}: {}", to_dir.display(), e))?;
+    let to = to_dir.join(from.file_name().unwrap_or_default());
+    fs::rename(from, &to).map_err(|e| format!("Failed to move {}: {}", from.display(), e))?;
+    Ok(to)
+}
+
+// Carry out the choice and say what was done
+pub fn apply_action(
+    project_path: &Path,
+    stale: &StaleBinary,
+    action: StaleAction,
+) -> Result<String, String> {
+    let archive = stale.archive_dir(project_path);
+    let corpus = project_path.join(CORPUS_DIR);
+    let crashes = project_path.join(CRASHES_DIR);
+    match action {
+        StaleAction::Revalidate => {
+            let files = inputs(&crashes);
+            fs::create_dir_all(&corpus)
+                .map_err(|e| format!("Failed to create {}: {}", corpus.display(), e))?;
+            for file in &files {
+                let moved = move_file(file, &archive.join(CRASHES_DIR))?;
+                let name = file.file_name().unwrap_or_default().to_string_lossy();
+                fs::copy(&moved, corpus.join(format!("revalidate-{}", name)))
+                    .map_err(|e| format!("Failed to seed {}: {}", name, e))?;
+            }
+            Ok(format!(
+                "Queued {} old crashes as seeds, the originals are in {}",
+                files.len(),
+                archive.display()
+            ))
+        }
+        StaleAction::DropDuplicates => {
+            // Not a minimization, that needs each input replayed against the
+            // new build. Only inputs that cannot add anything go.
+            let files = inputs(&corpus);
+            let mut seen = std::collections::HashSet::new();
+            let mut dropped = 0;
+            for file in &files {
+                let data = fs::read(file)
+                    .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+                if data.is_empty() || !seen.insert(sha256(&data)) {
+                    move_file(file, &archive.join(CORPUS_DIR))?;
+                    dropped += 1;
+                }
+            }
+            Ok(format!(
+                "Kept {} of {} corpus inputs, {} empty or duplicate ones are in {}",
+                files.len() - dropped,
+                files.len(),
+                dropped,
+                archive.display()
+            ))
+        }
+        StaleAction::NewCampaignDir => {
+            // Everything goes, the fuzzer's metadata included
+            for (dir, name) in [(&corpus, CORPUS_DIR), (&crashes, CRASHES_DIR)] {
+                for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+                    move_file(&entry.path(), &archive.join(name))?;
+                }
+                fs::create_dir_all(dir)
+                    .map_err(|e| format!("Failed to create {}/: {}", name, e))?;
+            }
+            Ok(format!(
+                "Moved {}/ and {}/ to {}",
+                CORPUS_DIR,
+                CRASHES_DIR,
+                archive.display()
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(corpus: &[(&str, &str)], crashes: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let p = dir.path();
+        fs::create_dir_all(p.join(STATS_DIR)).unwrap();
+        fs::create_dir_all(p.join(CORPUS_DIR)).unwrap();
+        fs::create_dir_all(p.join(CRASHES_DIR)).unwrap();
+        for (name, data) in corpus {
+            fs::write(p.join(CORPUS_DIR).join(name), data).unwrap();
+        }
+        for name in crashes {
+            fs::write(p.join(CRASHES_DIR).join(name), "boom").unwrap();
+        }
+        // Lock files of the fuzzer are not inputs
+        fs::write(p.join(CORPUS_DIR).join(".a.lafl_lock"), "").unwrap();
+        // Only campaigns with a stats file are listed
+        fs::write(p.join(STATS_DIR).join("20261001-000000.jsonl"), "").unwrap();
+        fs::write(p.join(STATS_DIR).join("20261002-000000.jsonl"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_check_binary() {
+        let dir = project(&[("a", "1")], &["crash-1"]);
+        let p = dir.path();
+        let old = BinaryFingerprint::of(b"old");
+        let new = BinaryFingerprint::of(b"new");
+        assert_eq!(check_binary(p, &new), None);
+
+        record_fingerprint(p, "20261001-000000", &old).unwrap();
+        assert_eq!(check_binary(p, &old), None);
+        let stale = check_binary(p, &new).unwrap();
+        assert_eq!(stale.campaign_id, "20261001-000000");
+        assert_eq!((stale.corpus, stale.crashes), (1, 1));
+
+        // The newest recorded campaign counts
+        record_fingerprint(p, "20261002-000000", &new).unwrap();
+        assert_eq!(check_binary(p, &new), None);
+        assert!(check_binary(p, &old).is_some());
+
+        let empty = project(&[], &[]);
+        record_fingerprint(empty.path(), "20261001-000000", &old).unwrap();
+        assert_eq!(check_binary(empty.path(), &new), None);
+    }
+
+    #[test]
+    fn test_apply_action() {
+        let dir = project(&[("a", "1"), ("b", "1"), ("c", "")], &["crash-1"]);
+        let p = dir.path();
+        record_fingerprint(p, "20261001-000000", &BinaryFingerprint::of(b"old")).unwrap();
+        let stale = check_binary(p, &BinaryFingerprint::of(b"new")).unwrap();
+
+        apply_action(p, &stale, StaleAction::DropDuplicates).unwrap();
+        let names = |dir: &str| -> Vec<String> {
+            inputs(&p.join(dir))
+                .iter()
+                .map(|f| f.file_name().unwrap().to_string_lossy().to_string())
+                .collect()
+        };
+        assert_eq!(names(CORPUS_DIR), vec!["a"]);
+
+        apply_action(p, &stale, StaleAction::Revalidate).unwrap();
+        assert_eq!(names(CORPUS_DIR), vec!["a", "revalidate-crash-1"]);
+        assert!(names(CRASHES_DIR).is_empty());
+
+        apply_action(p, &stale, StaleAction::NewCampaignDir).unwrap();
+        assert!(names(CORPUS_DIR).is_empty());
+        assert!(p.join(CRASHES_DIR).is_dir());
+        let archived = fs::read_dir(p.join(ARCHIVE_DIR)).unwrap().count();
+        assert!(archived >= 1);
+    }
+}
@@ -0,0 +1,276 @@
+// campaign/stats.rs
+use std::time::Duration;
+#[cfg(feature = "for_fuzzer")]
+use std::time::Instant;
+
+// One status line printed by the fuzzer's monitor, e.g.
+// `[Testcase #0] run time: 0h-0m-1s, clients: 2, corpus: 10, objectives: 1, executions: 1234, exec/sec: 1.2k`
+#[cfg(feature = "for_fuzzer")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MonitorRecord {
+    pub event: Option<String>,
+    pub executions: Option<u64>,
+    pub execs_per_sec: Option<f64>,
+    pub corpus: Option<u64>,
+    pub objectives: Option<u64>,
+    pub timeouts: Option<u64>,
+    pub unique_bbs: Option<u64>,
+    pub clients: Option<u64>,
+}
+
+#[cfg(feature = "for_fuzzer")]
+impl MonitorRecord {
+    fn is_empty(&self) -> bool {
+        self.executions.is_none()
+            && self.execs_per_sec.is_none()
+            && self.corpus.is_none()
+            && self.objectives.is_none()
+            && self.timeouts.is_none()
+            && self.unique_bbs.is_none()
+    }
+}
+
+#[cfg(feature = "for_fuzzer")]
+#[derive(Debug, Clone)]
+pub struct CampaignStats {
+    pub started_at: Instant,
+    pub executions: u64,
+    pub execs_per_sec: f64,
+    pub corpus_size: u64,
+    pub crashes: u64,
+    pub timeouts: u64,
+    pub unique_bbs: Option<u64>,
+    pub clients: u64,
+    pub last_new_path: Option<Instant>,
+    pub last_crash: Option<Instant>,
+    pub last_update: Option<Instant>,
+    pub finished_at: Option<Instant>,
+}
+
+#[cfg(feature = "for_fuzzer")]
+impl Default for CampaignStats {
+    fn default() -> Self {
+        Self {
+            started_at: Instant::now(),
+            executions: 0,
+            execs_per_sec: 0.0,
+            corpus_size: 0,
+            crashes: 0,
+            timeouts: 0,
+            unique_bbs: None,
+            clients: 0,
+            last_new_path: None,
+            last_crash: None,
+            last_update: None,
+            finished_at: None,
+        }
+    }
+}
+
+#[cfg(feature = "for_fuzzer")]
+impl CampaignStats {
+    pub fn update(&mut self, record: &MonitorRecord) {
+        let now = Instant::now();
+        if let Some(corpus) = record.corpus {
+            if corpus > self.corpus_size {
+                self.last_new_path = Some(now);
+            }
+            self.corpus_size = corpus;
+        }
+        if let Some(objectives) = record.objectives {
+            if objectives > self.crashes {
+                self.last_crash = Some(now);
+            }
+            self.crashes = objectives;
+        }
+        if let Some(execs) = record.executions {
+            self.executions = execs;
+        }
+        if let Some(eps) = record.execs_per_sec {
+            self.execs_per_sec = eps;
+        }
+        if let Some(timeouts) = record.timeouts {
+            self.timeouts = timeouts;
+        }
+        if record.unique_bbs.is_some() {
+            self.unique_bbs = record.unique_bbs;
+        }
+        if let Some(clients) = record.clients {
+            self.clients = clients;
+        }
+        self.last_update = Some(now);
+    }
+
+    // Combine per-client stats into campaign totals. Executions, speed and
+    // timeouts add up; corpus, crashes and coverage are shared through the
+    // broker, so the best client is the campaign's value.
+    pub fn aggregate<'a>(&mut self, clients: impl Iterator<Item = &'a CampaignStats>) {
+        let mut total = MonitorRecord::default();
+        let (mut execs, mut eps, mut timeouts, mut count) = (0, 0.0, 0, 0);
+        for c in clients {
+            execs += c.executions;
+            eps += c.execs_per_sec;
+            timeouts += c.timeouts;
+            count += 1;
+            total.corpus = total.corpus.max(Some(c.corpus_size));
+            total.objectives = total.objectives.max(Some(c.crashes));
+            total.unique_bbs = total.unique_bbs.max(c.unique_bbs);
+        }
+        total.executions = Some(execs);
+        total.execs_per_sec = Some(eps);
+        total.timeouts = Some(timeouts);
+        total.clients = Some(count);
+        self.update(&total);
+    }
+
+    // Freeze the run time once the campaign is over
+    pub fn finish(&mut self) {
+        self.finished_at.get_or_insert_with(Instant::now);
+    }
+
+    pub fn run_time(&self) -> Duration {
+        self.finished_at
+            .unwrap_or_else(Instant::now)
+            .duration_since(self.started_at)
+    }
+
+    pub fn since_last_new_path(&self) -> Option<Duration> {
+        self.last_new_path.map(|t| t.elapsed())
+    }
+
+    pub fn since_last_crash(&self) -> Option<Duration> {
+        self.last_crash.map(|t| t.elapsed())
+    }
+}
+
+// Parse a monitor line. Returns None for anything that does not carry stats,
+// so arbitrary fuzzer output can be fed through here.
+#[cfg(feature = "for_fuzzer")]
+pub fn parse_monitor_line(line: &str) -> Option<MonitorRecord> {
+    let mut record = MonitorRecord::default();
+    let mut body = line.trim();
+
+    if body.starts_with('[') {
+        if let Some(end) = body.find(']') {
+            record.event = Some(body[1..end].trim().to_string());
+            body = &body[end + 1..];
+        }
+    }
+    // Newer monitors print `(GLOBAL) ... | (CLIENT) ...`, only the global part is interesting
+    if let Some(start) = body.find("(GLOBAL)") {
+        body = &body[start + "(GLOBAL)".len()..];
+        if let Some(end) = body.find('|') {
+            body = &body[..end];
+        }
+    }
+
+    for field in body.split(',') {
+        let Some((key, value)) = field.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "executions" | "execs" => record.executions = parse_count(value),
+            "exec/sec" | "execs/sec" => record.execs_per_sec = parse_rate(value),
+            "corpus" => record.corpus = parse_count(value),
+            "objectives" | "crashes" => record.objectives = parse_count(value),
+            "timeouts" => record.timeouts = parse_count(value),
+            "unique_bbs" | "unique_bb" | "bbs" => record.unique_bbs = parse_count(value),
+            "clients" => record.clients = parse_count(value),
+            _ => {}
+        }
+    }
+
+    if record.is_empty() {
+        None
+    } else {
+        Some(record)
+    }
+}
+
+// Leading integer of a value, so `123/4096 (3%)` parses as 123
+#[cfg(feature = "for_fuzzer")]
+fn parse_count(value: &str) -> Option<u64> {
+    let digits: String = value.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+// Rates may carry a k/M/G suffix
+#[cfg(feature = "for_fuzzer")]
+fn parse_rate(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (num, mult) = match value.chars().last()? {
+        'k' => (&value[..value.len() - 1], 1e3),
+        'M' => (&value[..value.len() - 1], 1e6),
+        'G' => (&value[..value.len() - 1], 1e9),
+        _ => (value, 1.0),
+    };
+    num.trim().parse::<f64>().ok().map(|n| n * mult)
+}
+
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!(
+        "{} days, {} hrs, {} min, {} sec",
+        secs / 86400,
+        (secs / 3600) % 24,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+pub fn format_count(n: u64) -> String {
+    match n {
+        0..=9_999 => n.to_string(),
+        10_000..=9_999_999 => format!("{:.1}k", n as f64 / 1e3),
+        _ => format!("{:.2}M", n as f64 / 1e6),
+    }
+}
+
+#[cfg(all(test, feature = "for_fuzzer"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_monitor_line() {
+        let rec = parse_monitor_line(
+            "[Testcase #1] run time: 0h-0m-5s, clients: 2, corpus: 12, objectives: 1, executions: 4567, exec/sec: 1.5k",
+        )
+        .unwrap();
+        assert_eq!(rec.event.as_deref(), Some("Testcase #1"));
+        assert_eq!(rec.clients, Some(2));
+        assert_eq!(rec.corpus, Some(12));
+        assert_eq!(rec.objectives, Some(1));
+        assert_eq!(rec.executions, Some(4567));
+        assert_eq!(rec.execs_per_sec, Some(1500.0));
+    }
+
+    #[test]
+    fn test_parse_global_section_only() {
+        let rec = parse_monitor_line(
+            "[Client Heartbeat #0] (GLOBAL) run time: 1s, corpus: 3, executions: 10, unique_bbs: 77/1024 (7%) | (CLIENT) corpus: 1, executions: 4",
+        )
+        .unwrap();
+        assert_eq!(rec.corpus, Some(3));
+        assert_eq!(rec.executions, Some(10));
+        assert_eq!(rec.unique_bbs, Some(77));
+    }
+
+    #[test]
+    fn test_parse_ignores_plain_output() {
+        assert!(parse_monitor_line("Loading binary into flash...").is_none());
+        assert!(parse_monitor_line("").is_none());
+    }
+
+    #[test]
+    fn test_stats_track_new_paths() {
+        let mut stats = CampaignStats::default();
+        assert!(stats.since_last_new_path().is_none());
+        stats.update(&MonitorRecord {
+            corpus: Some(5),
+            ..Default::default()
+        });
+        assert_eq!(stats.corpus_size, 5);
+        assert!(stats.since_last_new_path().is_some());
+    }
+}
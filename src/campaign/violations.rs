@@ -0,0 +1,190 @@
+// campaign/violations.rs
+// Memory access violations in the fuzzer output. Their format is not fixed
+// on our side, so a report is read by keywords: "violation", the PC after
+// "pc", the address after one of ADDR_WORDS, the kind of access and maybe
+// its size. Lines that mention a violation without all of that are counted
+// as not understood rather than dropped.
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use super::LOGS_DIR;
+use crate::config::access_rules::AccessKind;
+use crate::config::schema::parse_int;
+
+const ADDR_WORDS: &[&str] = &["addr", "address", "to", "from", "at", "target"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    pub pc: u64,
+    pub addr: u64,
+    pub kind: AccessKind,
+    // Access width in bytes, 4 when the report does not say
+    pub size: u64,
+}
+
+// What the fuzzer logs hold
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ViolationLog {
+    pub groups: Vec<ViolationGroup>,
+    // Lines that mention a violation but could not be read
+    pub unrecognised: usize,
+}
+
+// Violations at the same PC, address and kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViolationGroup {
+    pub violation: Violation,
+    pub count: u64,
+}
+
+fn mentions_violation(line: &str) -> bool {
+    line.to_ascii_lowercase().contains("violation")
+}
+
+pub fn parse_violation(line: &str) -> Option<Violation> {
+    if !mentions_violation(line) {
+        return None;
+    }
+    let words: Vec<String> = line
+        .split(|c: char| c.is_whitespace() || ",;:=()[]".contains(c))
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_ascii_lowercase())
+        .collect();
+    let number_after = |keys: &[&str]| {
+        words
+            .windows(2)
+            .find(|w| keys.contains(&w[0].as_str()))
+            .and_then(|w| parse_int(&w[1]).ok())
+    };
+    let pc = number_after(&["pc"])?;
+    let addr = number_after(ADDR_WORDS)?;
+    let kind = words
+        .iter()
+        .filter(|w| w.len() > 1)
+        .find_map(|w| AccessKind::parse(w))?;
+    let size = number_after(&["size", "width", "of"])
+        .or_else(|| {
+            words
+                .windows(2)
+                .find(|w| w[1].starts_with("byte"))
+                .and_then(|w| parse_int(&w[0]).ok())
+        })
+        .unwrap_or(4);
+    Some(Violation {
+        pc,
+        addr,
+        kind,
+        size,
+    })
+}
+
+// Most frequent first
+pub fn group_violations(violations: impl IntoIterator<Item = Violation>) -> Vec<ViolationGroup> {
+    let mut counts: HashMap<(u64, u64, AccessKind), ViolationGroup> = HashMap::new();
+    for v in violations {
+        counts
+            .entry((v.pc, v.addr, v.kind))
+            .or_insert(ViolationGroup {
+                violation: v,
+                count: 0,
+            })
+            .count += 1;
+    }
+    let mut ret: Vec<ViolationGroup> = counts.into_values().collect();
+    ret.sort_by_key(|g| {
+        (
+            std::cmp::Reverse(g.count),
+            g.violation.pc,
+            g.violation.addr,
+            g.violation.kind,
+        )
+    });
+    ret
+}
+
+// Every violation in the project's fuzzer logs
+pub fn read_violations(project_path: &Path) -> Result<ViolationLog, String> {
+    let dir = project_path.join(LOGS_DIR);
+    let entries = match fs::read_dir(&dir) {
+        Ok(e) => e,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(ViolationLog::default()),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+    };
+    let mut violations = vec![];
+    let mut unrecognised = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|e| e != "log") {
+            continue;
+        }
+        let file =
+            File::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            match parse_violation(&line) {
+                Some(v) => violations.push(v),
+                None if mentions_violation(&line) => unrecognised += 1,
+                None => {}
+            }
+        }
+    }
+    Ok(ViolationLog {
+        groups: group_violations(violations),
+        unrecognised,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_violation() {
+        assert_eq!(
+            parse_violation(
+                "[mem_access_rules] violation: write of 2 bytes to 0x40021018 at pc 0x08000a3c"
+            ),
+            Some(Violation {
+                pc: 0x0800_0a3c,
+                addr: 0x4002_1018,
+                kind: AccessKind::Write,
+                size: 2,
+            })
+        );
+        assert_eq!(
+            parse_violation("VIOLATION pc=0x8000a3c addr=0x40021018 kind=read"),
+            Some(Violation {
+                pc: 0x0800_0a3c,
+                addr: 0x4002_1018,
+                kind: AccessKind::Read,
+                size: 4,
+            })
+        );
+        assert_eq!(parse_violation("write to 0x40021018 at pc 0x8000a3c"), None);
+        // No address keyword, so the address is not guessed
+        assert_eq!(
+            parse_violation("violation 0x40021018 write pc 0x8000a3c"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_violations() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join(LOGS_DIR)).unwrap();
+        let line = |pc: u32| format!("violation: read from 0x20010000 at pc {:#x}\n", pc);
+        fs::write(
+            dir.path().join(LOGS_DIR).join("fuzzer-1.log"),
+            line(0x100) + &line(0x200) + "corpus: 12\n" + &line(0x200) + "violation!\n",
+        )
+        .unwrap();
+        let log = read_violations(dir.path()).unwrap();
+        assert_eq!(log.groups.len(), 2);
+        assert_eq!(
+            (log.groups[0].violation.pc, log.groups[0].count),
+            (0x200, 2)
+        );
+        assert_eq!(log.unrecognised, 1);
+    }
+}
@@ -0,0 +1,701 @@
+// cli.rs
+// Headless subcommands, so projects can be created, checked and fuzzed from
+// CI or over a dumb terminal. Without a subcommand the TUI starts as before.
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::campaign::cores::parse_core_spec;
+use crate::campaign::limits::{format_short_duration, parse_duration, CampaignLimits, LimitKind};
+use crate::campaign::persist::{list_campaigns, load_campaign, CampaignSummary};
+use crate::config::profiles::{active_config_path, active_profile};
+use crate::config::schema::parse_int;
+use crate::config::validate::{errors, validate, Issue};
+use crate::windows::project_window::{create_project_structure, validate_project_structure};
+
+#[cfg(feature = "for_fuzzer")]
+use flashfuzzemu::opts::EmuOpts;
+#[cfg(feature = "for_fuzzer")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// How often `fuzz` prints a progress line
+#[cfg(feature = "for_fuzzer")]
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+#[cfg(feature = "for_fuzzer")]
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub const USAGE: &str = "Usage: fuzzer_tui [--tui] [--project <dir> [--binary <file>] [--load-address <a>] [--port <p>] [--window config|fuzz]]
+       fuzzer_tui [--json] new <dir>
+       fuzzer_tui [--json] check <dir>
+       fuzzer_tui [--json] fuzz <dir> [--binary <file>] [--load-address <a>] [--port <p>] [--duration <t>] [--cores <spec>]
+       fuzzer_tui [--json] status <dir>
+
+  --tui         start the interactive interface (default without a subcommand)
+  --project     open this project instead of starting at the project picker
+  --window      window to open in the project, next to the main menu
+  --json        print one JSON object per line instead of plain text
+  --binary      firmware as ELF, Intel HEX, Motorola S-record or a raw image
+  --load-address  where a raw image goes, e.g. 0x08000000
+  --duration    stop after this long, e.g. 90s, 30m, 12h; overrides the project's limits
+  --cores       cores to fuzz on, e.g. 0-3,8 / all / none
+
+  fuzz takes the binary, load address, port and cores from the project's
+  project.json unless they are given";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Tui(TuiArgs),
+    Help,
+    New(PathBuf),
+    Check(PathBuf),
+    Fuzz(FuzzArgs),
+    Status(PathBuf),
+}
+
+// Where the interactive interface starts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TuiArgs {
+    pub project: Option<PathBuf>,
+    pub binary: Option<PathBuf>,
+    pub load_address: Option<u64>,
+    pub port: Option<u16>,
+    pub window: Option<StartWindow>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartWindow {
+    Config,
+    Fuzz,
+}
+
+impl StartWindow {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "config" => Ok(StartWindow::Config),
+            "fuzz" => Ok(StartWindow::Fuzz),
+            _ => Err(format!("Unknown window: {}, expected config or fuzz", s)),
+        }
+    }
+}
+
+// None for what project.json decides
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzArgs {
+    pub project: PathBuf,
+    pub binary: Option<PathBuf>,
+    pub load_address: Option<u64>,
+    pub port: Option<u16>,
+    pub duration: Option<Duration>,
+    pub cores: Option<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cli {
+    pub command: Command,
+    pub json: bool,
+}
+
+// `args` without the program name
+pub fn parse_args(args: &[String]) -> Result<Cli, String> {
+    let mut json = false;
+    let mut tui = false;
+    let mut positional = vec![];
+    let mut options: Vec<(String, String)> = vec![];
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--tui" => tui = true,
+            "-h" | "--help" => {
+                return Ok(Cli {
+                    command: Command::Help,
+                    json,
+                })
+            }
+            "--binary" | "--load-address" | "--port" | "--duration" | "--cores" | "--project"
+            | "--window" => {
+                let value = it.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                options.push((arg.clone(), value.clone()));
+            }
+            a if a.starts_with("--") => return Err(format!("Unknown option: {}", a)),
+            a => positional.push(a.to_string()),
+        }
+    }
+
+    let option = |name: &str| {
+        options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    };
+    let port = || {
+        option("--port")
+            .map(|p| {
+                p.parse::<u16>()
+                    .map_err(|_| format!("Invalid port number: {}", p))
+            })
+            .transpose()
+    };
+    let load_address = || {
+        option("--load-address")
+            .map(|a| parse_int(a).map_err(|e| format!("Invalid load address {}: {}", a, e)))
+            .transpose()
+    };
+    let project = |cmd: &str| match positional.get(1) {
+        Some(dir) if positional.len() == 2 => Ok(PathBuf::from(dir)),
+        Some(_) => Err(format!("Too many arguments for {}", cmd)),
+        None => Err(format!("{} needs a project directory", cmd)),
+    };
+
+    let command = match positional.first().map(String::as_str) {
+        None => {
+            let args = TuiArgs {
+                project: option("--project").map(PathBuf::from),
+                binary: option("--binary").map(PathBuf::from),
+                load_address: load_address()?,
+                port: port()?,
+                window: option("--window").map(StartWindow::parse).transpose()?,
+            };
+            if args.project.is_none() && (args.binary.is_some() || args.window.is_some()) {
+                return Err("--binary and --window need --project".to_string());
+            }
+            Command::Tui(args)
+        }
+        Some(_) if tui => return Err("--tui cannot be combined with a subcommand".to_string()),
+        Some("help") => Command::Help,
+        Some("new") => Command::New(project("new")?),
+        Some("check") => Command::Check(project("check")?),
+        Some("status") => Command::Status(project("status")?),
+        Some("fuzz") => {
+            let duration = option("--duration").map(parse_duration).transpose()?;
+            let cores = option("--cores").map(parse_core_spec).transpose()?;
+            Command::Fuzz(FuzzArgs {
+                project: project("fuzz")?,
+                binary: option("--binary").map(PathBuf::from),
+                load_address: load_address()?,
+                port: port()?,
+                duration,
+                cores,
+            })
+        }
+        Some(c) => return Err(format!("Unknown command: {}", c)),
+    };
+    let allowed: &[&str] = match command {
+        Command::Tui(_) => &[
+            "--project",
+            "--binary",
+            "--load-address",
+            "--port",
+            "--window",
+        ],
+        Command::Fuzz(_) => &[
+            "--binary",
+            "--load-address",
+            "--port",
+            "--duration",
+            "--cores",
+        ],
+        _ => &[],
+    };
+    if let Some((name, _)) = options.iter().find(|(n, _)| !allowed.contains(&n.as_str())) {
+        return Err(format!("{} is not valid here", name));
+    }
+    Ok(Cli { command, json })
+}
+
+// Prints either plain lines or one JSON object per line. Every JSON object
+// carries an "event" and a human readable "message".
+pub struct Output {
+    json: bool,
+}
+
+impl Output {
+    pub fn new(json: bool) -> Self {
+        Self { json }
+    }
+
+    pub fn event(&self, event: &str, message: impl AsRef<str>, fields: Value) {
+        if self.json {
+            let mut obj = json!({ "event": event, "message": message.as_ref() });
+            if let (Some(obj), Value::Object(fields)) = (obj.as_object_mut(), fields) {
+                obj.extend(fields);
+            }
+            println!("{}", obj);
+        } else {
+            println!("{}", message.as_ref());
+        }
+    }
+
+    pub fn error(&self, message: impl AsRef<str>) {
+        if self.json {
+            println!(
+                "{}",
+                json!({ "event": "error", "message": message.as_ref() })
+            );
+        } else {
+            eprintln!("error: {}", message.as_ref());
+        }
+    }
+}
+
+// Runs a headless command and returns the process exit code
+pub fn run(cli: Cli) -> i32 {
+    let out = Output::new(cli.json);
+    let res = match cli.command {
+        Command::Tui(_) => Err("The TUI is not started from here".to_string()),
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(0)
+        }
+        Command::New(dir) => run_new(&out, &dir),
+        Command::Check(dir) => run_check(&out, &dir),
+        Command::Status(dir) => run_status(&out, &dir),
+        Command::Fuzz(args) => run_fuzz(&out, args),
+    };
+    match res {
+        Ok(code) => code,
+        Err(e) => {
+            out.error(e);
+            1
+        }
+    }
+}
+
+fn run_new(out: &Output, dir: &Path) -> Result<i32, String> {
+    // create_project_structure truncates config.json, never do that to a project
+    if validate_project_structure(dir).is_ok() {
+        return Err(format!("{} already is a project", dir.display()));
+    }
+    create_project_structure(dir)?;
+    out.event(
+        "created",
+        format!("Created project at {}", dir.display()),
+        json!({ "project": dir }),
+    );
+    Ok(0)
+}
+
+fn run_check(out: &Output, dir: &Path) -> Result<i32, String> {
+    let mut failed = 0;
+    let mut report = |name: &str, res: Result<String, String>| {
+        let (ok, detail) = match res {
+            Ok(d) => (true, d),
+            Err(e) => {
+                failed += 1;
+                (false, e)
+            }
+        };
+        out.event(
+            "check",
+            format!(
+                "{:<10} {:<4} {}",
+                name,
+                if ok { "ok" } else { "FAIL" },
+                detail
+            ),
+            json!({ "check": name, "ok": ok, "detail": detail }),
+        );
+    };
+
+    report(
+        "structure",
+        validate_project_structure(dir)
+            .map(|_| "corpus/, crashes/, config.json, grammar.json".into()),
+    );
+    report("config", check_config(dir));
+    report(
+        "limits",
+        CampaignLimits::load(dir).map(|l| describe_limits(&l)),
+    );
+
+    if failed > 0 {
+        out.event(
+            "result",
+            format!("{} check(s) failed", failed),
+            json!({ "ok": false, "failed": failed }),
+        );
+        return Ok(1);
+    }
+    out.event("result", "Project is ready to fuzz", json!({ "ok": true }));
+    Ok(0)
+}
+
+// The active profile, config.json unless another one was picked
+fn check_config(dir: &Path) -> Result<String, String> {
+    let s = std::fs::read_to_string(active_config_path(dir))
+        .map_err(|e| format!("Error reading config file: {}", e))?;
+    let value = serde_json::from_str::<Value>(&s)
+        .map_err(|e| format!("Invalid JSON configuration: {}", e))?;
+    let issues = validate(&value, None);
+    let describe = |issues: Vec<&Issue>| {
+        issues
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+    let problems = errors(&issues);
+    if !problems.is_empty() {
+        return Err(describe(problems));
+    }
+    let warnings: Vec<&Issue> = issues.iter().filter(|i| !i.is_error()).collect();
+    if !warnings.is_empty() {
+        return Ok(format!(
+            "profile {} is valid, with warnings: {}",
+            active_profile(dir),
+            describe(warnings)
+        ));
+    }
+    Ok(format!("profile {} is valid", active_profile(dir)))
+}
+
+fn describe_limits(limits: &CampaignLimits) -> String {
+    if limits.is_unbounded() {
+        return "unbounded".to_string();
+    }
+    LimitKind::ALL
+        .iter()
+        .filter(|k| limits.get(**k).is_some())
+        .map(|k| format!("{}: {}", k.label(), limits.display(*k)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn run_status(out: &Output, dir: &Path) -> Result<i32, String> {
+    validate_project_structure(dir)?;
+    let limits = CampaignLimits::load(dir)?;
+    let campaigns = list_campaigns(dir);
+    out.event(
+        "project",
+        format!(
+            "Project {}: {} campaign(s), limits: {}",
+            dir.display(),
+            campaigns.len(),
+            describe_limits(&limits)
+        ),
+        json!({
+            "project": dir,
+            "campaigns": campaigns.len(),
+            "limits": limits,
+        }),
+    );
+    let Some(latest) = campaigns.first() else {
+        return Ok(0);
+    };
+    let last = load_campaign(&latest.path)?.pop();
+    let summary = CampaignSummary::load(dir, &latest.id)?;
+    let state = match summary.as_ref() {
+        Some(s) => format!("finished, {}", s.reason),
+        None => "running or interrupted".to_string(),
+    };
+    let mut message = format!("Latest campaign {}: {}", latest.id, state);
+    if let Some(r) = last.as_ref() {
+        message.push_str(&format!(
+            "\n  run time {}, {} execs ({:.1}/sec), corpus {}, crashes {}, timeouts {}",
+            format_short_duration(r.run_time()),
+            r.execs,
+            r.execs_per_sec,
+            r.corpus_count,
+            r.crashes,
+            r.timeouts
+        ));
+    }
+    out.event(
+        "campaign",
+        message,
+        json!({
+            "campaign_id": latest.id,
+            "finished": summary.is_some(),
+            "summary": summary,
+            "last_record": last,
+        }),
+    );
+    Ok(0)
+}
+
+// Set from the signal handler, the fuzz loop stops the campaign when it sees it
+#[cfg(feature = "for_fuzzer")]
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "for_fuzzer")]
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+#[cfg(not(feature = "for_fuzzer"))]
+fn run_fuzz(_out: &Output, _args: FuzzArgs) -> Result<i32, String> {
+    Err("Built without the for_fuzzer feature, fuzzing is not available".to_string())
+}
+
+#[cfg(feature = "for_fuzzer")]
+fn run_fuzz(out: &Output, args: FuzzArgs) -> Result<i32, String> {
+    use crate::campaign::cores::format_core_spec;
+    use crate::campaign::runner::{CampaignRunner, LaunchParams};
+    use crate::campaign::stale::{check_binary, record_fingerprint};
+    use crate::campaign::stats::CampaignStats;
+    use crate::config::manifest::ProjectManifest;
+    use crate::firmware::fingerprint::BinaryFingerprint;
+    use crate::firmware::loader::emulator_image;
+    use crate::state::DEFAULT_PORT;
+    use std::time::Instant;
+
+    validate_project_structure(&args.project)?;
+    // The command line wins over project.json, like in the TUI. The load
+    // address in project.json belongs to the binary there.
+    let manifest = ProjectManifest::load(&args.project)?;
+    let (binary, load_address) = match args.binary {
+        Some(b) => (b, args.load_address),
+        None => (
+            manifest.binary_path(&args.project).ok_or(
+                "No binary to fuzz, pass --binary or pick one in the TUI to keep it in project.json",
+            )?,
+            args.load_address.or(manifest.load_address),
+        ),
+    };
+    let port = args.port.or(manifest.port).unwrap_or(DEFAULT_PORT);
+    let cores = args.cores.unwrap_or(manifest.cores);
+    let config = std::fs::read_to_string(active_config_path(&args.project))
+        .map_err(|e| format!("Error reading config file: {}", e))?;
+    let value = serde_json::from_str::<Value>(&config)
+        .map_err(|e| format!("Invalid JSON configuration: {}", e))?;
+    // Read the binary before moving into the project, relative paths are
+    // meant relative to where we were started
+    let data =
+        std::fs::read(&binary).map_err(|e| format!("Cannot read {}: {}", binary.display(), e))?;
+    let fingerprint = BinaryFingerprint::of(&data);
+    let contents = emulator_image(&binary, &data, load_address, &value)?;
+    let issues = validate(&value, Some(contents.len() as u64));
+    let problems = errors(&issues);
+    if let Some(first) = problems.first() {
+        return Err(format!(
+            "The configuration has {} problem(s), run `check` for all of them. First: {}",
+            problems.len(),
+            first
+        ));
+    }
+    for warning in issues.iter().filter(|i| !i.is_error()) {
+        out.event(
+            "warning",
+            warning.to_string(),
+            json!({ "path": warning.path }),
+        );
+    }
+    let opts =
+        EmuOpts::from_json(config).map_err(|e| format!("Invalid EmuOpts configuration: {}", e))?;
+    let mut limits = CampaignLimits::load(&args.project)?;
+    if let Some(d) = args.duration {
+        limits.max_duration_secs = Some(d.as_secs().max(1));
+    }
+    // The campaign writes its stats and logs relative to the project, like the TUI
+    std::env::set_current_dir(&args.project)
+        .map_err(|e| format!("Failed to set current directory: {}", e))?;
+    // Nothing to ask without a terminal, the TUI offers what to do about it
+    if let Some(stale) = check_binary(Path::new("."), &fingerprint) {
+        out.event(
+            "binary_changed",
+            stale.describe(),
+            json!({
+                "campaign_id": stale.campaign_id,
+                "previous": stale.previous,
+                "current": stale.current,
+                "corpus": stale.corpus,
+                "crashes": stale.crashes,
+            }),
+        );
+    }
+
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as *const () as libc::sighandler_t,
+        );
+        libc::signal(
+            libc::SIGTERM,
+            on_interrupt as *const () as libc::sighandler_t,
+        );
+    }
+
+    let mut runner = CampaignRunner::new();
+    let launch = LaunchParams {
+        contents,
+        opts,
+        port,
+    };
+    let campaign_id = runner.start(launch, &cores, limits.clone())?;
+    if let Err(e) = record_fingerprint(Path::new("."), &campaign_id, &fingerprint) {
+        out.event("note", e, json!({}));
+    }
+    out.event(
+        "started",
+        format!(
+            "Campaign {} started: {} on port {}, cores: {}, limits: {}",
+            campaign_id,
+            binary.display(),
+            port,
+            format_core_spec(&cores),
+            describe_limits(&limits)
+        ),
+        json!({
+            "campaign_id": campaign_id,
+            "binary": binary,
+            "fingerprint": fingerprint,
+            "port": port,
+            "cores": cores,
+            "limits": limits,
+        }),
+    );
+
+    let mut seen = 0;
+    let mut last_report = Instant::now();
+    let mut stopping = false;
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        if INTERRUPTED.swap(false, Ordering::SeqCst) && !stopping {
+            stopping = true;
+            out.event("stopping", "Interrupted, stopping the campaign", json!({}));
+            runner.stop();
+        }
+        let summary = runner.poll();
+        // Our own notes are worth seeing, the fuzzer output is in logs/
+        {
+            let ctx = runner.context.read().unwrap();
+            for line in ctx.log.lines_since(seen) {
+                if let Some(note) = line.strip_prefix("[fuzzer_tui] ") {
+                    out.event("note", note, json!({}));
+                }
+            }
+            seen = ctx.log.pushed();
+        }
+        if let Some(summary) = summary {
+            out.event(
+                "finished",
+                format!(
+                    "Campaign {} finished: {}, {} execs, corpus {}, crashes {}, run time {}",
+                    summary.campaign_id,
+                    summary.reason,
+                    summary.execs,
+                    summary.corpus_count,
+                    summary.crashes,
+                    format_short_duration(Duration::from_secs(summary.duration_secs))
+                ),
+                json!({ "summary": summary }),
+            );
+            return Ok(0);
+        }
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            last_report = Instant::now();
+            let ctx = runner.context.read().unwrap();
+            let stats: &CampaignStats = &ctx.stats;
+            let alive = ctx.clients.iter().filter(|c| c.is_alive()).count();
+            out.event(
+                "progress",
+                format!(
+                    "[{}] {} execs ({:.1}/sec), corpus {}, crashes {}, timeouts {}, clients {}/{}",
+                    format_short_duration(stats.run_time()),
+                    stats.executions,
+                    stats.execs_per_sec,
+                    stats.corpus_size,
+                    stats.crashes,
+                    stats.timeouts,
+                    alive,
+                    ctx.clients.len()
+                ),
+                json!({
+                    "elapsed": stats.run_time().as_secs_f64(),
+                    "execs": stats.executions,
+                    "execs_per_sec": stats.execs_per_sec,
+                    "corpus_count": stats.corpus_size,
+                    "crashes": stats.crashes,
+                    "timeouts": stats.timeouts,
+                    "coverage": stats.unique_bbs,
+                    "clients_alive": alive,
+                    "clients": ctx.clients.len(),
+                }),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(&[]).unwrap().command,
+            Command::Tui(TuiArgs::default())
+        );
+        assert_eq!(
+            parse_args(&args("--tui")).unwrap().command,
+            Command::Tui(TuiArgs::default())
+        );
+        let cli = parse_args(&args("--json check proj")).unwrap();
+        assert!(cli.json);
+        assert_eq!(cli.command, Command::Check(PathBuf::from("proj")));
+        let cli = parse_args(&args(
+            "fuzz proj --binary fw.bin --load-address 0x08000000 --duration 2m --port 4000",
+        ))
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Command::Fuzz(FuzzArgs {
+                project: PathBuf::from("proj"),
+                binary: Some(PathBuf::from("fw.bin")),
+                load_address: Some(0x0800_0000),
+                port: Some(4000),
+                duration: Some(Duration::from_secs(120)),
+                cores: None,
+            })
+        );
+        // Everything else comes from project.json
+        assert_eq!(
+            parse_args(&args("fuzz proj --cores none")).unwrap().command,
+            Command::Fuzz(FuzzArgs {
+                project: PathBuf::from("proj"),
+                binary: None,
+                load_address: None,
+                port: None,
+                duration: None,
+                cores: Some(vec![]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(parse_args(&args("fuzz")).is_err());
+        assert!(parse_args(&args("check")).is_err());
+        assert!(parse_args(&args("check a b")).is_err());
+        assert!(parse_args(&args("status proj --port 1")).is_err());
+        assert!(parse_args(&args("--tui new proj")).is_err());
+        assert!(parse_args(&args("frobnicate")).is_err());
+        assert!(parse_args(&args("check proj --window fuzz")).is_err());
+        assert!(parse_args(&args("fuzz proj --binary a --project b")).is_err());
+    }
+
+    #[test]
+    fn test_parse_tui_args() {
+        let cli = parse_args(&args("--project proj --binary fw.elf --window fuzz")).unwrap();
+        assert_eq!(
+            cli.command,
+            Command::Tui(TuiArgs {
+                project: Some(PathBuf::from("proj")),
+                binary: Some(PathBuf::from("fw.elf")),
+                load_address: None,
+                port: None,
+                window: Some(StartWindow::Fuzz),
+            })
+        );
+        assert!(parse_args(&args("--window config")).is_err());
+        assert!(parse_args(&args("--project proj --window stats")).is_err());
+        assert!(parse_args(&args("--port 99999")).is_err());
+        assert!(parse_args(&args("--project proj --load-address flash")).is_err());
+    }
+}
@@ -0,0 +1,352 @@
+// config/access_rules.rs
+// Memory access rules for the emulator's `mem_access_rules` feature: which
+// kinds of access a range allows and what happens on any other access.
+use serde_json::{Map, Value};
+
+use super::memory_map::{find_list, get_list, put_list, Perms};
+use super::schema::{parse_int, parse_size, Schema};
+use super::validate::as_u64;
+
+const START_KEYS: &[&str] = &["start", "base", "address", "addr"];
+const SIZE_KEYS: &[&str] = &["size", "length", "len"];
+const ALLOW_KEYS: &[&str] = &["allow", "access", "perms", "kinds"];
+const ACTION_KEYS: &[&str] = &["action", "on_violation"];
+// Where the list goes when the config has none yet
+const DEFAULT_LIST: &str = "mem_access_rules";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    // Report the access as a crash
+    Crash,
+    // Record the violation and carry on
+    Log,
+    Ignore,
+}
+
+impl Action {
+    pub const ALL: [Action; 3] = [Action::Crash, Action::Log, Action::Ignore];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Crash => "crash",
+            Action::Log => "log",
+            Action::Ignore => "ignore",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        Self::ALL
+            .iter()
+            .find(|a| a.name() == s.trim().to_ascii_lowercase())
+            .copied()
+            .ok_or_else(|| format!("Unknown action {}, expected crash, log or ignore", s))
+    }
+
+    pub fn next(&self) -> Self {
+        let i = Self::ALL.iter().position(|a| a == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AccessKind {
+    Read,
+    Write,
+    Exec,
+}
+
+impl AccessKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccessKind::Read => "read",
+            AccessKind::Write => "write",
+            AccessKind::Exec => "exec",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "r" | "read" | "load" => Some(AccessKind::Read),
+            "w" | "write" | "store" => Some(AccessKind::Write),
+            "x" | "exec" | "execute" | "fetch" => Some(AccessKind::Exec),
+            _ => None,
+        }
+    }
+
+    pub fn allowed_by(&self, perms: &Perms) -> bool {
+        match self {
+            AccessKind::Read => perms.read,
+            AccessKind::Write => perms.write,
+            AccessKind::Exec => perms.exec,
+        }
+    }
+
+    pub fn add_to(&self, perms: &mut Perms) {
+        match self {
+            AccessKind::Read => perms.read = true,
+            AccessKind::Write => perms.write = true,
+            AccessKind::Exec => perms.exec = true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessRule {
+    pub start: u64,
+    pub size: u64,
+    pub allow: Perms,
+    pub action: Action,
+    // The entry with any keys we do not know about
+    entry: Map<String, Value>,
+}
+
+impl AccessRule {
+    pub fn new(start: u64, size: u64, allow: Perms, action: Action) -> Self {
+        Self {
+            start,
+            size,
+            allow,
+            action,
+            entry: Map::new(),
+        }
+    }
+
+    pub fn end(&self) -> u64 {
+        self.start.saturating_add(self.size)
+    }
+
+    pub fn contains(&self, addr: u64) -> bool {
+        self.start <= addr && addr < self.end()
+    }
+}
+
+// How the list entries spell their keys, taken from the first one
+#[derive(Debug, Clone, PartialEq)]
+struct Layout {
+    start_key: String,
+    size_key: String,
+    size_is_end: bool,
+    allow_key: String,
+    action_key: String,
+    hex_strings: bool,
+}
+
+impl Layout {
+    fn of(items: &[Value]) -> Self {
+        let first = items.iter().find_map(|v| v.as_object());
+        let pick = |keys: &[&str]| {
+            first
+                .and_then(|o| keys.iter().find(|k| o.contains_key(**k)))
+                .unwrap_or(&keys[0])
+                .to_string()
+        };
+        let start_key = pick(START_KEYS);
+        let size_is_end = first.is_some_and(|o| {
+            o.contains_key("end") && !SIZE_KEYS.iter().any(|k| o.contains_key(*k))
+        });
+        Self {
+            hex_strings: first
+                .and_then(|o| o.get(&start_key))
+                .is_some_and(|v| v.is_string()),
+            start_key,
+            size_key: if size_is_end {
+                "end".to_string()
+            } else {
+                pick(SIZE_KEYS)
+            },
+            size_is_end,
+            allow_key: pick(ALLOW_KEYS),
+            action_key: pick(ACTION_KEYS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessRules {
+    // Dotted path of the list, e.g. `mem_access_rules`
+    pub list_path: String,
+    pub rules: Vec<AccessRule>,
+    layout: Layout,
+}
+
+impl AccessRules {
+    pub fn from_config(value: &Value, default_list: Option<&str>) -> Self {
+        let list_path = find_list(value, &is_rules_key)
+            .or(default_list.map(|s| s.to_string()))
+            .unwrap_or_else(|| DEFAULT_LIST.to_string());
+        let items = get_list(value, &list_path);
+        let layout = Layout::of(&items);
+        let rules = items
+            .iter()
+            .filter_map(|item| {
+                let obj = item.as_object()?;
+                let start = obj.get(&layout.start_key).and_then(as_u64)?;
+                let size = match obj.get(&layout.size_key).and_then(as_u64)? {
+                    end if layout.size_is_end => end.saturating_sub(start),
+                    size => size,
+                };
+                Some(AccessRule {
+                    start,
+                    size,
+                    allow: obj
+                        .get(&layout.allow_key)
+                        .and_then(Perms::from_value)
+                        .unwrap_or_default(),
+                    action: obj
+                        .get(&layout.action_key)
+                        .and_then(|v| v.as_str())
+                        .and_then(|s| Action::parse(s).ok())
+                        .unwrap_or(Action::Crash),
+                    entry: obj.clone(),
+                })
+            })
+            .collect();
+        Self {
+            list_path,
+            rules,
+            layout,
+        }
+    }
+
+    pub fn set(&mut self, idx: Option<usize>, rule: AccessRule) -> Result<usize, String> {
+        if rule.size == 0 {
+            return Err("A rule needs a size".to_string());
+        }
+        match idx {
+            Some(i) => {
+                let entry = std::mem::take(&mut self.rules[i].entry);
+                self.rules[i] = AccessRule { entry, ..rule };
+                Ok(i)
+            }
+            None => {
+                self.rules.push(rule);
+                Ok(self.rules.len() - 1)
+            }
+        }
+    }
+
+    pub fn delete(&mut self, idx: usize) {
+        if idx < self.rules.len() {
+            self.rules.remove(idx);
+        }
+    }
+
+    // Whether some rule allows this access
+    pub fn allows(&self, addr: u64, kind: AccessKind) -> bool {
+        self.rules
+            .iter()
+            .any(|r| r.contains(addr) && kind.allowed_by(&r.allow))
+    }
+
+    // Allow an access that was reported as a violation. A rule for exactly
+    // that range is widened, otherwise a new one is added. Returns its index.
+    pub fn allow(&mut self, addr: u64, size: u64, kind: AccessKind) -> usize {
+        let size = size.max(1);
+        if let Some(i) = self
+            .rules
+            .iter()
+            .position(|r| r.start == addr && r.size == size)
+        {
+            kind.add_to(&mut self.rules[i].allow);
+            return i;
+        }
+        let mut allow = Perms::default();
+        kind.add_to(&mut allow);
+        self.rules
+            .push(AccessRule::new(addr, size, allow, Action::Crash));
+        self.rules.len() - 1
+    }
+
+    pub fn apply(&self, value: &mut Value) {
+        let layout = &self.layout;
+        let number = |n: u64| {
+            if layout.hex_strings {
+                Value::from(format!("{:#x}", n))
+            } else {
+                Value::from(n)
+            }
+        };
+        let list: Vec<Value> = self
+            .rules
+            .iter()
+            .map(|r| {
+                let mut obj = r.entry.clone();
+                obj.insert(layout.start_key.clone(), number(r.start));
+                let size = if layout.size_is_end { r.end() } else { r.size };
+                obj.insert(layout.size_key.clone(), number(size));
+                obj.insert(layout.allow_key.clone(), Value::from(r.allow.to_string()));
+                obj.insert(layout.action_key.clone(), Value::from(r.action.name()));
+                Value::Object(obj)
+            })
+            .collect();
+        put_list(value, &self.list_path, list);
+    }
+}
+
+fn is_rules_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.contains("access_rule") || key == "rules"
+}
+
+// The array field of a schema that holds the access rules
+pub fn rules_list_field(schema: &Schema) -> Option<String> {
+    schema
+        .fields
+        .iter()
+        .find(|f| f.default.is_array() && is_rules_key(f.key()))
+        .map(|f| f.dotted())
+}
+
+// "start size allow [action]", e.g. "0x40000000 4k rw- log"
+pub fn parse_rule_spec(input: &str) -> Result<AccessRule, String> {
+    let parts: Vec<&str> = input.split_whitespace().collect();
+    if parts.len() < 3 || parts.len() > 4 {
+        return Err("Expected: start size allow [action], e.g. 0x40000000 4k rw- log".to_string());
+    }
+    Ok(AccessRule::new(
+        parse_int(parts[0])?,
+        parse_size(parts[1])?,
+        Perms::parse(parts[2])?,
+        match parts.get(3) {
+            Some(a) => Action::parse(a)?,
+            None => Action::Crash,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rules_roundtrip() {
+        let config = json!({"mem_access_rules": [
+            {"start": "0x40000000", "end": "0x40001000", "allow": "r--", "action": "log", "note": "rcc"},
+        ]});
+        let mut rules = AccessRules::from_config(&config, None);
+        assert_eq!(rules.rules[0].size, 0x1000);
+        assert!(rules.allows(0x4000_0ffc, AccessKind::Read));
+        assert!(!rules.allows(0x4000_0ffc, AccessKind::Write));
+
+        assert_eq!(rules.allow(0x4000_2000, 4, AccessKind::Write), 1);
+        assert_eq!(rules.allow(0x4000_2000, 4, AccessKind::Read), 1);
+        assert_eq!(
+            rules.set(Some(0), parse_rule_spec("0x40000000 4k rw ignore").unwrap()),
+            Ok(0)
+        );
+        let mut value = config.clone();
+        rules.apply(&mut value);
+        assert_eq!(
+            value["mem_access_rules"],
+            json!([
+                {"start": "0x40000000", "end": "0x40001000", "allow": "rw-", "action": "ignore", "note": "rcc"},
+                {"start": "0x40002000", "end": "0x40002004", "allow": "rw-", "action": "crash"},
+            ])
+        );
+        assert!(parse_rule_spec("0x0 0 r").is_ok());
+        assert!(rules
+            .set(None, parse_rule_spec("0x0 0 r").unwrap())
+            .is_err());
+    }
+}
@@ -0,0 +1,77 @@
+// config/diff.rs
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+// Line diff over the longest common subsequence. Configs are a few hundred
+// lines at most, so the quadratic table is fine.
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // lcs[i][j] is the common length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ret = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ret.push(DiffLine::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ret.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            ret.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    ret
+}
+
+// Number of added and removed lines
+pub fn diff_stats(diff: &[DiffLine]) -> (usize, usize) {
+    diff.iter().fold((0, 0), |(a, r), l| match l {
+        DiffLine::Added(_) => (a + 1, r),
+        DiffLine::Removed(_) => (a, r + 1),
+        DiffLine::Same(_) => (a, r),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff() {
+        let diff = line_diff(
+            "{\n  \"a\": 1,\n  \"b\": 2\n}",
+            "{\n  \"a\": 1,\n  \"b\": 3\n}",
+        );
+        assert_eq!(
+            diff,
+            vec![
+                DiffLine::Same("{".into()),
+                DiffLine::Same("  \"a\": 1,".into()),
+                DiffLine::Removed("  \"b\": 2".into()),
+                DiffLine::Added("  \"b\": 3".into()),
+                DiffLine::Same("}".into()),
+            ]
+        );
+        assert_eq!(diff_stats(&diff), (1, 1));
+        assert_eq!(diff_stats(&line_diff("", "x\ny")), (2, 0));
+    }
+}
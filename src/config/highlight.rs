@@ -0,0 +1,177 @@
+// config/highlight.rs
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Key,
+    String,
+    Number,
+    // true, false and null
+    Literal,
+    Punct,
+    Space,
+    // Anything that cannot start a JSON token
+    Invalid,
+}
+
+// A token over char indices of its line, so it lines up with the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+// Split one line of JSON into tokens. Strings cannot span lines in JSON,
+// so lines are tokenized on their own; an unterminated string runs to the
+// end of its line.
+pub fn tokenize_line(line: &str) -> Vec<Token> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut ret: Vec<Token> = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let kind = if c.is_whitespace() {
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+            TokenKind::Space
+        } else if c == '"' {
+            i += 1;
+            let mut escaped = false;
+            while i < chars.len() {
+                let c = chars[i];
+                i += 1;
+                match c {
+                    '\\' if !escaped => escaped = true,
+                    '"' if !escaped => break,
+                    _ => escaped = false,
+                }
+            }
+            // A string followed by a colon is an object key
+            let next = chars[i..].iter().find(|c| !c.is_whitespace());
+            if next == Some(&':') {
+                TokenKind::Key
+            } else {
+                TokenKind::String
+            }
+        } else if c == '-' || c.is_ascii_digit() {
+            i += 1;
+            while i < chars.len() && matches!(chars[i], '0'..='9' | '.' | 'e' | 'E' | '+' | '-') {
+                i += 1;
+            }
+            TokenKind::Number
+        } else if "{}[]:,".contains(c) {
+            i += 1;
+            TokenKind::Punct
+        } else {
+            while i < chars.len() && chars[i].is_alphanumeric() {
+                i += 1;
+            }
+            i = i.max(start + 1);
+            let word: String = chars[start..i].iter().collect();
+            if matches!(word.as_str(), "true" | "false" | "null") {
+                TokenKind::Literal
+            } else {
+                TokenKind::Invalid
+            }
+        };
+        ret.push(Token {
+            kind,
+            start,
+            end: i,
+        });
+    }
+    ret
+}
+
+// Where a config stops being valid JSON
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    // 1-based, as serde reports them
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    // The line that most likely lacks a comma, 1-based
+    pub missing_comma: Option<usize>,
+}
+
+impl SyntaxError {
+    // Cursor position for the error, 0-based
+    pub fn position(&self) -> (usize, usize) {
+        (self.line.saturating_sub(1), self.column.saturating_sub(1))
+    }
+}
+
+pub fn syntax_error(text: &str) -> Option<SyntaxError> {
+    let err = serde_json::from_str::<serde_json::Value>(text).err()?;
+    let message = err.to_string();
+    // serde notices a missing comma at the token after it, often a line later
+    let missing_comma = if message.starts_with("expected `,`") {
+        let lines: Vec<&str> = text.lines().collect();
+        let before = err.line().saturating_sub(1).min(lines.len());
+        let at = lines.get(err.line().saturating_sub(1)).map_or("", |l| {
+            let col = err.column().saturating_sub(1);
+            l.char_indices().nth(col).map_or(*l, |(i, _)| &l[..i])
+        });
+        if at.trim().is_empty() {
+            (0..before)
+                .rev()
+                .find(|i| !lines[*i].trim().is_empty())
+                .map(|i| i + 1)
+        } else {
+            Some(err.line())
+        }
+    } else {
+        None
+    };
+    Some(SyntaxError {
+        line: err.line(),
+        column: err.column(),
+        message: match message.rfind(" at line ") {
+            Some(i) => message[..i].to_string(),
+            None => message,
+        },
+        missing_comma,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(line: &str) -> Vec<TokenKind> {
+        tokenize_line(line)
+            .into_iter()
+            .filter(|t| t.kind != TokenKind::Space)
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_line() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds(r#"  "base": 134217728, "name": "fl\"ash","#),
+            vec![Key, Punct, Number, Punct, Key, Punct, String, Punct]
+        );
+        assert_eq!(
+            kinds("[true, null, -1.5e3]"),
+            vec![Punct, Literal, Punct, Literal, Punct, Number, Punct]
+        );
+        assert_eq!(kinds("  oops \"open"), vec![Invalid, String]);
+        let t = tokenize_line("\"é\": 1");
+        assert_eq!((t[0].start, t[0].end), (0, 3));
+    }
+
+    #[test]
+    fn test_missing_comma() {
+        let text = "{\n  \"a\": 1\n  \"b\": 2\n}";
+        let err = syntax_error(text).unwrap();
+        assert_eq!((err.line, err.column), (3, 3));
+        assert_eq!(err.position(), (2, 2));
+        assert_eq!(err.missing_comma, Some(2));
+        assert_eq!(err.message, "expected `,` or `}`");
+        assert!(syntax_error("{\"a\": 1}").is_none());
+    }
+}
@@ -0,0 +1,503 @@
+// config/interrupts.rs
+// The interrupts the emulator raises. Which firing policies exist depends
+// on the interrupt feature the emulator was built with.
+use serde_json::{Map, Value};
+
+use super::memory_map::{find_list, get_list, put_list};
+use super::schema::{parse_int, Schema};
+use super::validate::{as_u64, Issue, IRQ_KEYS};
+use crate::firmware::vector_table::VectorTable;
+
+const HANDLER_KEYS: &[&str] = &["handler", "handler_addr", "isr", "address", "addr"];
+const POLICY_KEYS: &[&str] = &["policy", "mode", "trigger"];
+const PERIOD_KEYS: &[&str] = &["period", "interval", "every"];
+// Where the list goes when the config has none yet
+const DEFAULT_LIST: &str = "interrupts";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptMode {
+    // The fuzz input picks interrupts from the list
+    List,
+    // Interrupts fire on a fixed period
+    Fixed,
+    // The fuzzer learns when to fire them
+    Guided,
+}
+
+impl InterruptMode {
+    pub fn feature(&self) -> &'static str {
+        match self {
+            InterruptMode::List => "interrupts_list",
+            InterruptMode::Fixed => "interrupts_fixed",
+            InterruptMode::Guided => "interrupts_guided",
+        }
+    }
+
+    pub fn policies(&self) -> &'static [FiringPolicy] {
+        match self {
+            InterruptMode::List => &[FiringPolicy::Input],
+            InterruptMode::Fixed => &[FiringPolicy::Periodic],
+            InterruptMode::Guided => &[FiringPolicy::Guided, FiringPolicy::Periodic],
+        }
+    }
+}
+
+// The interrupt modes this build supports
+pub fn compiled_modes() -> Vec<InterruptMode> {
+    let mut ret = vec![];
+    if cfg!(feature = "interrupts_list") {
+        ret.push(InterruptMode::List);
+    }
+    if cfg!(feature = "interrupts_fixed") {
+        ret.push(InterruptMode::Fixed);
+    }
+    if cfg!(feature = "interrupts_guided") {
+        ret.push(InterruptMode::Guided);
+    }
+    ret
+}
+
+pub fn allowed_policies(modes: &[InterruptMode]) -> Vec<FiringPolicy> {
+    let mut ret: Vec<FiringPolicy> = vec![];
+    for p in modes.iter().flat_map(|m| m.policies()) {
+        if !ret.contains(p) {
+            ret.push(*p);
+        }
+    }
+    ret
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FiringPolicy {
+    Input,
+    Periodic,
+    Guided,
+}
+
+impl FiringPolicy {
+    pub const ALL: [FiringPolicy; 3] = [
+        FiringPolicy::Input,
+        FiringPolicy::Periodic,
+        FiringPolicy::Guided,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FiringPolicy::Input => "input",
+            FiringPolicy::Periodic => "periodic",
+            FiringPolicy::Guided => "guided",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        Self::ALL
+            .iter()
+            .find(|p| p.name() == s.trim().to_ascii_lowercase())
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "Unknown firing policy {}, expected one of: input, periodic, guided",
+                    s
+                )
+            })
+    }
+
+    pub fn needs_period(&self) -> bool {
+        *self == FiringPolicy::Periodic
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interrupt {
+    pub irq: u64,
+    pub handler: Option<u64>,
+    pub policy: Option<FiringPolicy>,
+    // In basic blocks
+    pub period: Option<u64>,
+    // The entry with any keys we do not know about
+    entry: Map<String, Value>,
+}
+
+// How the list entries spell their keys, taken from the first one
+#[derive(Debug, Clone, PartialEq)]
+struct Layout {
+    // The list holds bare IRQ numbers
+    numbers_only: bool,
+    irq_key: String,
+    handler_key: String,
+    policy_key: String,
+    period_key: String,
+    hex_strings: bool,
+}
+
+impl Layout {
+    fn of(items: &[Value]) -> Self {
+        let first = items.iter().find_map(|v| v.as_object());
+        let pick = |keys: &[&str]| {
+            first
+                .and_then(|o| keys.iter().find(|k| o.contains_key(**k)))
+                .unwrap_or(&keys[0])
+                .to_string()
+        };
+        let handler_key = pick(HANDLER_KEYS);
+        Self {
+            numbers_only: !items.is_empty() && first.is_none(),
+            irq_key: pick(IRQ_KEYS),
+            hex_strings: first
+                .and_then(|o| o.get(&handler_key))
+                .is_some_and(|v| v.is_string()),
+            handler_key,
+            policy_key: pick(POLICY_KEYS),
+            period_key: pick(PERIOD_KEYS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterruptList {
+    // Dotted path of the list, e.g. `interrupts`
+    pub list_path: String,
+    pub interrupts: Vec<Interrupt>,
+    layout: Layout,
+}
+
+impl InterruptList {
+    pub fn from_config(value: &Value, default_list: Option<&str>) -> Self {
+        let list_path = find_list(value, &is_interrupt_key)
+            .or(default_list.map(|s| s.to_string()))
+            .unwrap_or_else(|| DEFAULT_LIST.to_string());
+        let items = get_list(value, &list_path);
+        let layout = Layout::of(&items);
+        let interrupts = items
+            .iter()
+            .filter_map(|item| match item {
+                Value::Object(obj) => Some(Interrupt {
+                    irq: obj.get(&layout.irq_key).and_then(as_u64)?,
+                    handler: obj.get(&layout.handler_key).and_then(as_u64),
+                    policy: obj
+                        .get(&layout.policy_key)
+                        .and_then(|v| v.as_str())
+                        .and_then(|s| FiringPolicy::parse(s).ok()),
+                    period: obj.get(&layout.period_key).and_then(as_u64),
+                    entry: obj.clone(),
+                }),
+                other => Some(Interrupt {
+                    irq: as_u64(other)?,
+                    handler: None,
+                    policy: None,
+                    period: None,
+                    entry: Map::new(),
+                }),
+            })
+            .collect();
+        Self {
+            list_path,
+            interrupts,
+            layout,
+        }
+    }
+
+    // Lists of bare numbers have nowhere to put a handler or policy
+    pub fn numbers_only(&self) -> bool {
+        self.layout.numbers_only
+    }
+
+    pub fn path_of(&self, idx: usize) -> String {
+        format!("{}[{}]", self.list_path, idx)
+    }
+
+    pub fn set(&mut self, idx: Option<usize>, irq: Interrupt) -> Result<usize, String> {
+        if self.numbers_only()
+            && (irq.handler.is_some() || irq.policy.is_some() || irq.period.is_some())
+        {
+            return Err(format!(
+                "{} only holds IRQ numbers, there is no room for a handler or policy",
+                self.list_path
+            ));
+        }
+        if let Some(other) = self
+            .interrupts
+            .iter()
+            .enumerate()
+            .position(|(i, o)| o.irq == irq.irq && Some(i) != idx)
+        {
+            return Err(format!(
+                "IRQ {} is already listed at {}",
+                irq.irq,
+                self.path_of(other)
+            ));
+        }
+        match idx {
+            Some(i) => {
+                let entry = std::mem::take(&mut self.interrupts[i].entry);
+                self.interrupts[i] = Interrupt { entry, ..irq };
+                Ok(i)
+            }
+            None => {
+                self.interrupts.push(irq);
+                Ok(self.interrupts.len() - 1)
+            }
+        }
+    }
+
+    // Add interrupts found in the vector table and fill in handlers the
+    // listed ones lack, returns how many entries changed
+    pub fn import_handlers(&mut self, handlers: &[(u64, u64)]) -> usize {
+        let mut changed = 0;
+        for &(irq, handler) in handlers {
+            // Lists of bare numbers only take the IRQ
+            let handler = (!self.numbers_only()).then_some(handler);
+            match self.interrupts.iter_mut().find(|i| i.irq == irq) {
+                Some(listed) if listed.handler.is_none() && handler.is_some() => {
+                    listed.handler = handler;
+                    changed += 1;
+                }
+                Some(_) => {}
+                None => {
+                    self.interrupts.push(Interrupt {
+                        irq,
+                        handler,
+                        policy: None,
+                        period: None,
+                        entry: Map::new(),
+                    });
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
+
+    pub fn delete(&mut self, idx: usize) {
+        if idx < self.interrupts.len() {
+            self.interrupts.remove(idx);
+        }
+    }
+
+    pub fn apply(&self, value: &mut Value) {
+        let layout = &self.layout;
+        let number = |n: u64| {
+            if layout.hex_strings {
+                Value::from(format!("{:#x}", n))
+            } else {
+                Value::from(n)
+            }
+        };
+        let list: Vec<Value> = self
+            .interrupts
+            .iter()
+            .map(|irq| {
+                if layout.numbers_only {
+                    return Value::from(irq.irq);
+                }
+                let mut obj = irq.entry.clone();
+                obj.insert(layout.irq_key.clone(), Value::from(irq.irq));
+                let mut put = |key: &String, v: Option<Value>| match v {
+                    Some(v) => {
+                        obj.insert(key.clone(), v);
+                    }
+                    None => {
+                        obj.remove(key);
+                    }
+                };
+                put(&layout.handler_key, irq.handler.map(number));
+                put(
+                    &layout.policy_key,
+                    irq.policy.map(|p| Value::from(p.name())),
+                );
+                put(&layout.period_key, irq.period.map(Value::from));
+                Value::Object(obj)
+            })
+            .collect();
+        put_list(value, &self.list_path, list);
+    }
+
+    // Problems with the list for a build that supports `policies`, checking
+    // handlers against the firmware's vector table when there is one
+    pub fn check(&self, policies: &[FiringPolicy], vectors: Option<&VectorTable>) -> Vec<Issue> {
+        let mut issues = vec![];
+        for (i, irq) in self.interrupts.iter().enumerate() {
+            let path = self.path_of(i);
+            if let Some(p) = irq.policy {
+                if !policies.contains(&p) {
+                    issues.push(Issue::new(
+                        &path,
+                        format!(
+                            "The {} policy is not available in this build, it supports: {}",
+                            p.name(),
+                            policies
+                                .iter()
+                                .map(|p| p.name())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    ));
+                }
+                if p.needs_period() && irq.period.is_none_or(|n| n == 0) {
+                    issues.push(Issue::new(
+                        &path,
+                        format!("IRQ {} fires periodically but has no period", irq.irq),
+                    ));
+                }
+            }
+            let Some(vt) = vectors else {
+                continue;
+            };
+            match vt.irq_slot(irq.irq) {
+                None => issues.push(Issue::new(
+                    &path,
+                    format!(
+                        "IRQ {} is past the end of the binary's vector table",
+                        irq.irq
+                    ),
+                )),
+                Some(0) => issues.push(Issue::new(
+                    &path,
+                    format!("The vector table has no handler for IRQ {}", irq.irq),
+                )),
+                // Thumb handlers have bit 0 set in the table
+                Some(slot) => match irq.handler {
+                    Some(h) if h & !1 != (slot & !1) as u64 => issues.push(Issue::new(
+                        &path,
+                        format!(
+                            "Handler {:#x} does not match the vector table, which has {:#x}",
+                            h,
+                            slot & !1
+                        ),
+                    )),
+                    _ => {}
+                },
+            }
+        }
+        issues
+    }
+}
+
+// The array field of a schema that holds the interrupts
+pub fn interrupt_list_field(schema: &Schema) -> Option<String> {
+    schema
+        .fields
+        .iter()
+        .find(|f| f.default.is_array() && is_interrupt_key(f.key()))
+        .map(|f| f.dotted())
+}
+
+fn is_interrupt_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.contains("interrupt") || key.contains("irq")
+}
+
+// "irq [handler] [policy] [period]", e.g. "5 0x08000201 periodic 1000"
+pub fn parse_interrupt_spec(input: &str) -> Result<Interrupt, String> {
+    let mut parts = input.split_whitespace();
+    let irq = parse_int(
+        parts
+            .next()
+            .ok_or("Expected: irq [handler] [policy] [period]")?,
+    )?;
+    let mut ret = Interrupt {
+        irq,
+        handler: None,
+        policy: None,
+        period: None,
+        entry: Map::new(),
+    };
+    for part in parts {
+        if let Ok(p) = FiringPolicy::parse(part) {
+            ret.policy = Some(p);
+        } else if ret.policy.is_some() {
+            ret.period = Some(parse_int(part)?);
+        } else if ret.handler.is_none() {
+            ret.handler = Some(parse_int(part)?);
+        } else {
+            return Err(format!("Unexpected {}", part));
+        }
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_against_vectors() {
+        let config = json!({"emu": {"irqs": [
+            {"irq": 0, "handler": "0x08000200", "policy": "periodic", "period": 500, "prio": 1},
+            {"irq": 1, "handler": "0x08000300", "policy": "guided"},
+            {"irq": 7, "policy": "periodic"},
+        ]}});
+        let list = InterruptList::from_config(&config, None);
+        assert_eq!(list.list_path, "emu.irqs");
+        let mut table = vec![0x2000_8000u32, 0x0800_0101];
+        table.extend([0; 14]);
+        table.extend([0x0800_0201, 0x0800_0401, 0]);
+        let bytes: Vec<u8> = table.iter().flat_map(|w| w.to_le_bytes()).collect();
+        let vt = VectorTable::parse(&bytes).unwrap();
+        let issues: Vec<String> = list
+            .check(&[FiringPolicy::Periodic], Some(&vt))
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                "emu.irqs[1]: The guided policy is not available in this build, it supports: periodic",
+                "emu.irqs[1]: Handler 0x8000300 does not match the vector table, which has 0x8000400",
+                "emu.irqs[2]: IRQ 7 fires periodically but has no period",
+                "emu.irqs[2]: IRQ 7 is past the end of the binary's vector table",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edit_and_apply() {
+        let config = json!({"interrupts": [{"irq": 3, "handler": "0x100", "prio": 2}]});
+        let mut list = InterruptList::from_config(&config, None);
+        let edited = parse_interrupt_spec("3 periodic 1000").unwrap();
+        assert_eq!(list.set(Some(0), edited), Ok(0));
+        let added = parse_interrupt_spec("4 0x200 input").unwrap();
+        assert_eq!(list.set(None, added), Ok(1));
+        assert!(list.set(None, parse_interrupt_spec("3").unwrap()).is_err());
+
+        let mut value = config.clone();
+        list.apply(&mut value);
+        assert_eq!(
+            value["interrupts"],
+            json!([
+                {"irq": 3, "policy": "periodic", "period": 1000, "prio": 2},
+                {"irq": 4, "handler": "0x200", "policy": "input"},
+            ])
+        );
+
+        // Handlers already set are kept
+        let partial = json!({"interrupts": [{"irq": 3, "handler": "0x100"}, {"irq": 4}]});
+        let mut imported = InterruptList::from_config(&partial, None);
+        let handlers = [(3, 0x300), (4, 0x400), (5, 0x500)];
+        assert_eq!(imported.import_handlers(&handlers), 2);
+        assert_eq!(imported.import_handlers(&handlers), 0);
+        let mut value = json!({});
+        imported.apply(&mut value);
+        assert_eq!(
+            value["interrupts"],
+            json!([
+                {"irq": 3, "handler": "0x100"},
+                {"irq": 4, "handler": "0x400"},
+                {"irq": 5, "handler": "0x500"},
+            ])
+        );
+
+        let mut numbers = InterruptList::from_config(&json!({"irqs": [1, 2]}), None);
+        assert!(numbers.numbers_only());
+        assert!(numbers
+            .set(None, parse_interrupt_spec("4 0x200").unwrap())
+            .is_err());
+        assert_eq!(numbers.import_handlers(&[(2, 0x200), (4, 0x400)]), 1);
+        assert_eq!(numbers.interrupts[2].handler, None);
+        assert_eq!(
+            allowed_policies(&[InterruptMode::Guided, InterruptMode::Fixed]).len(),
+            2
+        );
+    }
+}
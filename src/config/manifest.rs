@@ -0,0 +1,118 @@
+// config/manifest.rs
+// project.json: what the project fuzzes and how, so reopening it brings back
+// the binary, port, profile, cores and limits instead of asking for them again
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::campaign::limits::CampaignLimits;
+
+pub const MANIFEST_FILE: &str = "project.json";
+
+// Anything left as None was never set and keeps the app's default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectManifest {
+    // Relative to the project when it is inside it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary: Option<PathBuf>,
+    // Only for raw binaries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_address: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    // The active configuration profile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    // Cores new campaigns run on, none means a single unpinned client
+    pub cores: Vec<usize>,
+    // None until they are first saved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limits: Option<CampaignLimits>,
+}
+
+impl ProjectManifest {
+    pub fn load(project_path: &Path) -> Result<Self, String> {
+        let path = project_path.join(MANIFEST_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let s = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", MANIFEST_FILE, e))?;
+        serde_json::from_str(&s).map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE, e))
+    }
+
+    pub fn save(&self, project_path: &Path) -> Result<(), String> {
+        let s = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(project_path.join(MANIFEST_FILE), s)
+            .map_err(|e| format!("Failed to write {}: {}", MANIFEST_FILE, e))
+    }
+
+    // Change the manifest on disk, keeping what `change` does not touch
+    pub fn update(
+        project_path: &Path,
+        change: impl FnOnce(&mut ProjectManifest),
+    ) -> Result<(), String> {
+        let mut manifest = Self::load(project_path)?;
+        change(&mut manifest);
+        manifest.save(project_path)
+    }
+
+    pub fn binary_path(&self, project_path: &Path) -> Option<PathBuf> {
+        self.binary.as_ref().map(|b| project_path.join(b))
+    }
+
+    // Binaries inside the project are stored relative to it, so the project
+    // can be moved or checked out elsewhere
+    pub fn set_binary(&mut self, project_path: &Path, binary: &Path) {
+        self.binary = Some(
+            binary
+                .strip_prefix(project_path)
+                .map_or(binary.to_path_buf(), |p| p.to_path_buf()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path();
+        assert_eq!(
+            ProjectManifest::load(project).unwrap(),
+            ProjectManifest::default()
+        );
+
+        let mut manifest = ProjectManifest {
+            port: Some(4000),
+            cores: vec![0, 1],
+            ..Default::default()
+        };
+        manifest.set_binary(project, &project.join("build/fw.elf"));
+        assert_eq!(manifest.binary, Some(PathBuf::from("build/fw.elf")));
+        manifest.save(project).unwrap();
+        ProjectManifest::update(project, |m| m.profile = Some("irq".to_string())).unwrap();
+
+        let loaded = ProjectManifest::load(project).unwrap();
+        assert_eq!(loaded.port, Some(4000));
+        assert_eq!(loaded.profile.as_deref(), Some("irq"));
+        assert_eq!(
+            loaded.binary_path(project),
+            Some(project.join("build/fw.elf"))
+        );
+
+        let mut outside = loaded.clone();
+        outside.set_binary(project, Path::new("/opt/fw/app.hex"));
+        assert_eq!(
+            outside.binary_path(project),
+            Some(PathBuf::from("/opt/fw/app.hex"))
+        );
+
+        fs::write(project.join(MANIFEST_FILE), "{\"port\": \"x\"}").unwrap();
+        assert!(ProjectManifest::load(project).is_err());
+    }
+}
@@ -0,0 +1,840 @@
+// config/memory_map.rs
+use serde_json::{Map, Value};
+
+#[cfg(feature = "for_fuzzer")]
+use super::schema::read_emu_opts;
+use super::schema::{parse_int, parse_size, quoted_after, set_path, Schema};
+use super::validate::{as_u64, find_regions, PAGE_SIZE, SIZE_KEYS, START_KEYS};
+
+const NAME_KEY: &str = "name";
+const PERM_KEYS: &[&str] = &["perms", "permissions", "prot", "access"];
+// More fields than a region has, so a probe that goes nowhere stops
+const MAX_PROBES: usize = 32;
+
+// How the emulator reads a config: what it parses, written back out
+pub type Reader = fn(&Value) -> Result<Value, String>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Perms {
+    pub read: bool,
+    pub write: bool,
+    pub exec: bool,
+}
+
+impl Perms {
+    // "rwx" style strings, or the emulator's bitmask (1 read, 2 write, 4 exec)
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(n) => {
+                let bits = n.as_u64()?;
+                Some(Self {
+                    read: bits & 1 != 0,
+                    write: bits & 2 != 0,
+                    exec: bits & 4 != 0,
+                })
+            }
+            Value::String(s) => Self::parse(s).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut perms = Self::default();
+        for c in s.trim().chars() {
+            match c.to_ascii_lowercase() {
+                'r' => perms.read = true,
+                'w' => perms.write = true,
+                'x' => perms.exec = true,
+                '-' => {}
+                _ => {
+                    return Err(format!(
+                        "Invalid permissions {}, expected e.g. rw- or r-x",
+                        s
+                    ))
+                }
+            }
+        }
+        Ok(perms)
+    }
+
+    pub fn union(&self, other: &Perms) -> Perms {
+        Perms {
+            read: self.read || other.read,
+            write: self.write || other.write,
+            exec: self.exec || other.exec,
+        }
+    }
+
+    fn to_value(self, as_number: bool) -> Value {
+        if as_number {
+            Value::from(self.read as u64 | (self.write as u64) << 1 | (self.exec as u64) << 2)
+        } else {
+            Value::from(self.to_string())
+        }
+    }
+}
+
+impl std::fmt::Display for Perms {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            if self.read { 'r' } else { '-' },
+            if self.write { 'w' } else { '-' },
+            if self.exec { 'x' } else { '-' }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapRegion {
+    pub name: String,
+    pub start: u64,
+    pub size: u64,
+    pub perms: Option<Perms>,
+    // Where the region is set up in the config
+    pub path: String,
+    // The region's object in the list, with any keys we do not know about.
+    // None for regions set up elsewhere in the config, which are read-only.
+    entry: Option<Map<String, Value>>,
+}
+
+impl MapRegion {
+    pub fn end(&self) -> u64 {
+        self.start.saturating_add(self.size)
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        self.entry.is_none()
+    }
+
+    pub fn overlaps(&self, other: &MapRegion) -> bool {
+        self.start < other.end() && other.start < self.end()
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.start.is_multiple_of(PAGE_SIZE) && self.size.is_multiple_of(PAGE_SIZE)
+    }
+}
+
+// Which fields of a region hold what. They come from a region as the
+// emulator reads it, so only fields it has are written; without the
+// emulator, from the first region in the list.
+#[derive(Debug, Clone, PartialEq)]
+struct Layout {
+    // A new region, with every field the emulator reads at its default.
+    // Empty without the emulator.
+    template: Map<String, Value>,
+    start_key: String,
+    size_key: String,
+    // `end` instead of a size
+    size_is_end: bool,
+    // None when the regions have no name or permissions to keep in step
+    name_key: Option<String>,
+    perms_key: Option<String>,
+    perms_as_number: bool,
+    hex_strings: bool,
+}
+
+impl Layout {
+    // None when the region has no start or size we know, then the list is
+    // not one of memory regions, or one spelled in a way we cannot edit
+    fn of(obj: &Map<String, Value>, template: Map<String, Value>) -> Option<Self> {
+        let start_key = START_KEYS.iter().find(|k| obj.contains_key(**k))?;
+        let (size_key, size_is_end) = match SIZE_KEYS.iter().find(|k| obj.contains_key(**k)) {
+            Some(k) => (k.to_string(), false),
+            None if obj.contains_key("end") => ("end".to_string(), true),
+            None => return None,
+        };
+        let perms_key = PERM_KEYS
+            .iter()
+            .find(|k| obj.contains_key(**k))
+            .map(|k| k.to_string());
+        Some(Self {
+            template,
+            start_key: start_key.to_string(),
+            size_key,
+            size_is_end,
+            name_key: obj
+                .get(NAME_KEY)
+                .filter(|v| v.is_string())
+                .map(|_| NAME_KEY.to_string()),
+            perms_as_number: perms_key.as_ref().is_some_and(|k| obj[k].is_number()),
+            perms_key,
+            hex_strings: obj[*start_key].is_string(),
+        })
+    }
+
+    // Name, start, size and permissions of a region in the list
+    fn read(&self, obj: &Map<String, Value>) -> (String, u64, u64, Option<Perms>) {
+        let start = obj.get(&self.start_key).and_then(as_u64).unwrap_or(0);
+        let size = match obj.get(&self.size_key).and_then(as_u64) {
+            Some(end) if self.size_is_end => end.saturating_sub(start),
+            Some(size) => size,
+            None => 0,
+        };
+        let name = self
+            .name_key
+            .as_ref()
+            .and_then(|k| obj.get(k))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let perms = self
+            .perms_key
+            .as_ref()
+            .and_then(|k| obj.get(k))
+            .and_then(Perms::from_value);
+        (name, start, size, perms)
+    }
+
+    fn number(&self, n: u64) -> Value {
+        if self.hex_strings {
+            Value::from(format!("{:#x}", n))
+        } else {
+            Value::from(n)
+        }
+    }
+}
+
+// The memory regions of a config, as one list that can be edited
+#[derive(Debug, Clone)]
+pub struct MemoryMap {
+    // Dotted path of the region list, e.g. `memory_map` or `board.regions`
+    pub list_path: String,
+    pub regions: Vec<MapRegion>,
+    layout: Layout,
+    emulator: Option<Reader>,
+}
+
+impl MemoryMap {
+    // `default_list` is where the emulator keeps its region list, for
+    // configs that do not have one yet. Fails when there is neither, when
+    // the emulator does not read regions there, or the list holds objects
+    // that do not look like regions.
+    pub fn from_config(value: &Value, default_list: Option<&str>) -> Result<Self, String> {
+        Self::with_emulator(value, default_list, emulator())
+    }
+
+    pub fn with_emulator(
+        value: &Value,
+        default_list: Option<&str>,
+        emulator: Option<Reader>,
+    ) -> Result<Self, String> {
+        let found = find_regions(value);
+        // The list is the array most regions were found in
+        let mut lists: Vec<(String, usize)> = vec![];
+        for r in &found {
+            if let Some(parent) = r.path.strip_suffix(']').and_then(|p| p.rsplit_once('[')) {
+                match lists.iter_mut().find(|(p, _)| p == parent.0) {
+                    Some((_, n)) => *n += 1,
+                    None => lists.push((parent.0.to_string(), 1)),
+                }
+            }
+        }
+        let list_path = lists
+            .iter()
+            .max_by_key(|(_, n)| *n)
+            .map(|(p, _)| p.clone())
+            .or(default_list.map(|s| s.to_string()))
+            .ok_or(
+                "Could not find memory regions in the configuration, and the emulator options have no region list to add them to",
+            )?;
+
+        let items = get_list(value, &list_path);
+        let template = match emulator {
+            Some(read) => region_template(value, &list_path, read)?,
+            None => Map::new(),
+        };
+        let first = match emulator {
+            Some(_) => Some(&template),
+            None => items.iter().find_map(|v| v.as_object()),
+        };
+        let Some(first) = first else {
+            return Err(format!(
+                "{} has no regions to take the fields of one from, add the first region to the configuration by hand",
+                list_path
+            ));
+        };
+        let layout = Layout::of(first, template.clone()).ok_or_else(|| {
+            format!(
+                "The entries of {} have no start ({}) or size ({} or end) field, they cannot be edited as memory regions",
+                list_path,
+                START_KEYS.join("/"),
+                SIZE_KEYS.join("/")
+            )
+        })?;
+
+        let mut regions = vec![];
+        // Regions set up outside the list are shown, but only the list is edited
+        for r in found
+            .iter()
+            .filter(|r| !r.path.starts_with(&format!("{}[", list_path)))
+        {
+            regions.push(MapRegion {
+                name: r.name.clone(),
+                start: r.start,
+                size: r.size,
+                perms: None,
+                path: r.path.clone(),
+                entry: None,
+            });
+        }
+        for (i, item) in items.iter().enumerate() {
+            let Some(obj) = item.as_object() else {
+                continue;
+            };
+            let (name, start, size, perms) = layout.read(obj);
+            regions.push(MapRegion {
+                name,
+                start,
+                size,
+                perms,
+                path: format!("{}[{}]", list_path, i),
+                entry: Some(obj.clone()),
+            });
+        }
+        Ok(Self {
+            list_path,
+            regions,
+            layout,
+            emulator,
+        })
+    }
+
+    // Indices of the regions ordered by address
+    pub fn by_address(&self) -> Vec<usize> {
+        let mut ret: Vec<usize> = (0..self.regions.len()).collect();
+        ret.sort_by_key(|i| (self.regions[*i].start, self.regions[*i].end()));
+        ret
+    }
+
+    pub fn overlaps_any(&self, idx: usize) -> bool {
+        let r = &self.regions[idx];
+        self.regions
+            .iter()
+            .enumerate()
+            .any(|(i, o)| i != idx && r.overlaps(o))
+    }
+
+    fn editable(&self, idx: usize) -> Result<&MapRegion, String> {
+        let r = self
+            .regions
+            .get(idx)
+            .ok_or_else(|| "No region selected".to_string())?;
+        if r.is_fixed() {
+            return Err(format!(
+                "'{}' is set up at {}, change it in the configuration form",
+                r.name, r.path
+            ));
+        }
+        Ok(r)
+    }
+
+    pub fn add(&mut self, name: &str, start: u64, size: u64, perms: Option<Perms>) -> usize {
+        self.regions.push(MapRegion {
+            name: name.to_string(),
+            start,
+            size,
+            perms,
+            path: String::new(),
+            entry: Some(self.layout.template.clone()),
+        });
+        self.regions.len() - 1
+    }
+
+    pub fn edit(
+        &mut self,
+        idx: usize,
+        name: &str,
+        start: u64,
+        size: u64,
+        perms: Option<Perms>,
+    ) -> Result<(), String> {
+        self.editable(idx)?;
+        let r = &mut self.regions[idx];
+        r.name = name.to_string();
+        r.start = start;
+        r.size = size;
+        if perms.is_some() {
+            r.perms = perms;
+        }
+        Ok(())
+    }
+
+    pub fn resize(&mut self, idx: usize, start: u64, size: u64) -> Result<(), String> {
+        if size == 0 {
+            return Err("A region needs a size".to_string());
+        }
+        self.editable(idx)?;
+        self.regions[idx].start = start;
+        self.regions[idx].size = size;
+        Ok(())
+    }
+
+    // Cut a region in two at `at`, the second half keeps everything else
+    pub fn split(&mut self, idx: usize, at: u64) -> Result<usize, String> {
+        let r = self.editable(idx)?;
+        if at <= r.start || at >= r.end() {
+            return Err(format!(
+                "{:#x} is not inside '{}' ({:#x}-{:#x})",
+                at,
+                r.name,
+                r.start,
+                r.end()
+            ));
+        }
+        let mut upper = r.clone();
+        upper.name = format!("{}_2", r.name);
+        upper.start = at;
+        upper.size = r.end() - at;
+        self.regions[idx].size = at - self.regions[idx].start;
+        self.regions.insert(idx + 1, upper);
+        Ok(idx + 1)
+    }
+
+    // Join two regions that touch or overlap into the first one
+    pub fn merge(&mut self, idx: usize, other: usize) -> Result<(), String> {
+        let a = self.editable(idx)?.clone();
+        let b = self.editable(other)?.clone();
+        if idx == other || b.start > a.end() || a.start > b.end() {
+            return Err(format!(
+                "'{}' and '{}' do not touch, only neighbouring regions can be merged",
+                a.name, b.name
+            ));
+        }
+        let start = a.start.min(b.start);
+        let end = a.end().max(b.end());
+        let r = &mut self.regions[idx];
+        r.start = start;
+        r.size = end - start;
+        r.perms = match (a.perms, b.perms) {
+            (Some(p), Some(q)) => Some(p.union(&q)),
+            (p, q) => p.or(q),
+        };
+        self.regions.remove(other);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, idx: usize) -> Result<(), String> {
+        self.editable(idx)?;
+        self.regions.remove(idx);
+        Ok(())
+    }
+
+    // Make sure `start..start + size` is mapped, for what a binary loads
+    // there. A region that already holds it is left alone, one that only
+    // overlaps it is grown over it, and otherwise a new region is added.
+    // Returns the region and whether the map changed.
+    pub fn cover(
+        &mut self,
+        name: &str,
+        start: u64,
+        size: u64,
+        perms: Perms,
+    ) -> Result<(usize, bool), String> {
+        let end = start.saturating_add(size);
+        let overlapping: Vec<usize> = (0..self.regions.len())
+            .filter(|i| self.regions[*i].start < end && start < self.regions[*i].end())
+            .collect();
+        if let Some(&i) = overlapping
+            .iter()
+            .find(|i| self.regions[**i].start <= start && end <= self.regions[**i].end())
+        {
+            return Ok((i, false));
+        }
+        let Some(&i) = overlapping.first() else {
+            return Ok((self.add(name, start, size, Some(perms)), true));
+        };
+        let r = self.editable(i)?;
+        let (new_start, new_end) = (r.start.min(start), r.end().max(end));
+        self.resize(i, new_start, new_end - new_start)?;
+        self.regions[i].perms.get_or_insert(perms);
+        Ok((i, true))
+    }
+
+    // Write the region list back into the config. With the emulator, the
+    // config is left alone unless it reads every region back as written.
+    pub fn apply(&self, value: &mut Value) -> Result<(), String> {
+        let layout = &self.layout;
+        let list: Vec<Value> = self
+            .regions
+            .iter()
+            .filter_map(|r| {
+                let mut obj = r.entry.clone()?;
+                if let Some(key) = &layout.name_key {
+                    obj.insert(key.clone(), Value::from(r.name.as_str()));
+                }
+                obj.insert(layout.start_key.clone(), layout.number(r.start));
+                let size = if layout.size_is_end { r.end() } else { r.size };
+                obj.insert(layout.size_key.clone(), layout.number(size));
+                if let (Some(p), Some(key)) = (r.perms, &layout.perms_key) {
+                    obj.insert(key.clone(), p.to_value(layout.perms_as_number));
+                }
+                Some(Value::Object(obj))
+            })
+            .collect();
+        let mut new = value.clone();
+        put_list(&mut new, &self.list_path, list);
+        if let Some(read) = self.emulator {
+            self.check_read_back(&new, read)?;
+        }
+        *value = new;
+        Ok(())
+    }
+
+    fn check_read_back(&self, value: &Value, read: Reader) -> Result<(), String> {
+        let read = read(value)
+            .map_err(|e| format!("The emulator cannot read the new memory map: {}", e))?;
+        let read_list = get_list(&read, &self.list_path);
+        let written: Vec<&MapRegion> = self.regions.iter().filter(|r| !r.is_fixed()).collect();
+        if read_list.len() != written.len() {
+            return Err(format!(
+                "The emulator reads {} regions from {}, not the {} written",
+                read_list.len(),
+                self.list_path,
+                written.len()
+            ));
+        }
+        for (r, v) in written.iter().zip(&read_list) {
+            let (name, start, size, perms) = v
+                .as_object()
+                .map(|obj| self.layout.read(obj))
+                .unwrap_or_default();
+            let same_name = self.layout.name_key.is_none() || name == r.name;
+            let same_perms =
+                r.perms.is_none() || self.layout.perms_key.is_none() || perms == r.perms;
+            if !same_name || start != r.start || size != r.size || !same_perms {
+                return Err(format!(
+                    "The emulator reads '{}' back as '{}' {:#x}-{:#x}, the memory map was not changed",
+                    r.name,
+                    name,
+                    start,
+                    start.saturating_add(size)
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "for_fuzzer")]
+fn emulator() -> Option<Reader> {
+    Some(read_emu_opts)
+}
+
+#[cfg(not(feature = "for_fuzzer"))]
+fn emulator() -> Option<Reader> {
+    None
+}
+
+// A region as the emulator reads it. An empty one is put in the list and
+// each field the emulator says is missing is filled in, with a value of
+// another type while it rejects the type, until it reads the config.
+fn region_template(
+    value: &Value,
+    list_path: &str,
+    read: Reader,
+) -> Result<Map<String, Value>, String> {
+    let mut entry = Map::new();
+    // The field filled in last and which filler it has
+    let mut filled: Option<(String, usize)> = None;
+    for _ in 0..MAX_PROBES {
+        let mut probe = value.clone();
+        put_list(&mut probe, list_path, vec![Value::Object(entry.clone())]);
+        let err = match read(&probe) {
+            Ok(read) => {
+                return get_list(&read, list_path)
+                    .first()
+                    .and_then(|v| v.as_object())
+                    .cloned()
+                    .ok_or_else(|| {
+                        format!(
+                            "The emulator does not read memory regions from {}",
+                            list_path
+                        )
+                    });
+            }
+            Err(e) => e,
+        };
+        let next = match (quoted_after(&err, "missing field"), filled.take()) {
+            (Some(field), _) if !entry.contains_key(&field) => Some((field, 0)),
+            (None, Some((field, i))) if err.contains("invalid type") => Some((field, i + 1)),
+            _ => None,
+        };
+        match next.and_then(|(field, i)| Some((field, i, filler(i)?))) {
+            Some((field, i, v)) => {
+                entry.insert(field.clone(), v);
+                filled = Some((field, i));
+            }
+            None => {
+                return Err(format!(
+                    "The emulator cannot read a region from {}: {}",
+                    list_path, err
+                ))
+            }
+        }
+    }
+    Err(format!(
+        "The emulator cannot read a region from {}, it asks for more fields than a region has",
+        list_path
+    ))
+}
+
+// Tried in turn for a field the emulator asks for, until one has its type
+fn filler(i: usize) -> Option<Value> {
+    match i {
+        0 => Some(Value::from(0)),
+        1 => Some(Value::from("")),
+        2 => Some(Value::from(false)),
+        3 => Some(Value::Array(vec![])),
+        4 => Some(Value::Object(Map::new())),
+        _ => None,
+    }
+}
+
+// The array field of a schema that holds the memory regions
+pub fn region_list_field(schema: &Schema) -> Option<String> {
+    schema
+        .fields
+        .iter()
+        .find(|f| {
+            let key = f.key().to_ascii_lowercase();
+            f.default.is_array() && (key.contains("region") || key.contains("memory"))
+        })
+        .map(|f| f.dotted())
+}
+
+// The array at a dotted path, empty if there is none
+pub fn get_list(value: &Value, list_path: &str) -> Vec<Value> {
+    value
+        .pointer(&to_pointer(list_path))
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default()
+}
+
+// Replace the array at a dotted path, creating it if needed
+pub fn put_list(value: &mut Value, list_path: &str, list: Vec<Value>) {
+    match value.pointer_mut(&to_pointer(list_path)) {
+        Some(v) => *v = Value::Array(list),
+        None => {
+            let path: Vec<String> = list_path.split('.').map(|s| s.to_string()).collect();
+            set_path(value, &path, Value::Array(list));
+        }
+    }
+}
+
+// The first array in a config whose key matches, as a dotted path
+pub fn find_list(value: &Value, matches: &dyn Fn(&str) -> bool) -> Option<String> {
+    find_list_in(value, "", matches)
+}
+
+fn find_list_in(value: &Value, path: &str, matches: &dyn Fn(&str) -> bool) -> Option<String> {
+    let Value::Object(map) = value else {
+        return None;
+    };
+    for (key, v) in map {
+        let p = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        if v.is_array() && matches(key) {
+            return Some(p);
+        }
+        if let Some(found) = find_list_in(v, &p, matches) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+// `a.b[2].c` as the JSON pointer `/a/b/2/c`
+pub fn to_pointer(path: &str) -> String {
+    let mut ret = String::new();
+    for seg in path.split('.').filter(|s| !s.is_empty()) {
+        let mut parts = seg.split('[');
+        if let Some(key) = parts.next() {
+            ret.push('/');
+            ret.push_str(&key.replace('~', "~0").replace('/', "~1"));
+        }
+        for idx in parts {
+            ret.push('/');
+            ret.push_str(idx.trim_end_matches(']'));
+        }
+    }
+    ret
+}
+
+// "name start size [perms]", e.g. "sram 0x20000000 64k rw-"
+pub fn parse_region_spec(input: &str) -> Result<(String, u64, u64, Option<Perms>), String> {
+    let parts: Vec<&str> = input.split_whitespace().collect();
+    if parts.len() < 3 || parts.len() > 4 {
+        return Err("Expected: name start size [perms], e.g. sram 0x20000000 64k rw-".to_string());
+    }
+    let start = parse_int(parts[1])?;
+    let size = parse_size(parts[2])?;
+    if size == 0 {
+        return Err("A region needs a size".to_string());
+    }
+    let perms = parts.get(3).map(|p| Perms::parse(p)).transpose()?;
+    Ok((parts[0].to_string(), start, size, perms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config() -> Value {
+        json!({
+            "flash_base": 0x0800_0000u64,
+            "flash_size": 0x10000,
+            "board": {"regions": [
+                {"name": "sram", "start": "0x20000000", "size": "0x8000", "perms": "rw-", "cache": true},
+                {"name": "periph", "start": "0x40000000", "size": "0x1000", "perms": "rw-"},
+            ]},
+        })
+    }
+
+    // Stands in for EmuOpts: a `regions` list of name, start, size and
+    // optional perms, anything else is dropped
+    fn emulator(value: &Value) -> Result<Value, String> {
+        let mut regions = vec![];
+        for r in get_list(value, "regions") {
+            let mut read = Map::new();
+            match r.get("name") {
+                Some(Value::String(s)) => read.insert("name".into(), json!(s)),
+                Some(v) => return Err(format!("invalid type: {}, expected a string", v)),
+                None => return Err("missing field `name`".to_string()),
+            };
+            for key in ["start", "size"] {
+                match r.get(key) {
+                    Some(v) if v.is_u64() => read.insert(key.into(), v.clone()),
+                    Some(v) => return Err(format!("invalid type: {}, expected u64", v)),
+                    None => return Err(format!("missing field `{}`", key)),
+                };
+            }
+            read.insert(
+                "perms".into(),
+                r.get("perms").cloned().unwrap_or(json!("---")),
+            );
+            regions.push(Value::Object(read));
+        }
+        Ok(json!({"regions": regions}))
+    }
+
+    // One that reads sizes in whole pages
+    fn paged_emulator(value: &Value) -> Result<Value, String> {
+        let mut read = emulator(value)?;
+        for r in read["regions"].as_array_mut().unwrap() {
+            let size = r["size"].as_u64().unwrap();
+            r["size"] = json!(size.next_multiple_of(PAGE_SIZE));
+        }
+        Ok(read)
+    }
+
+    #[test]
+    fn test_load_and_apply() {
+        let map = MemoryMap::with_emulator(&config(), None, None).unwrap();
+        assert_eq!(map.list_path, "board.regions");
+        assert_eq!(map.regions.len(), 3);
+        assert!(map.regions[0].is_fixed());
+        assert_eq!(map.regions[1].perms, Some(Perms::parse("rw").unwrap()));
+
+        let mut map = map;
+        let idx = map.add("sram2", 0x2000_8000, 0x8000, Perms::parse("rwx").ok());
+        map.merge(1, idx).unwrap();
+        let mut value = config();
+        map.apply(&mut value).unwrap();
+        assert_eq!(
+            value["board"]["regions"][0],
+            json!({"name": "sram", "start": "0x20000000", "size": "0x10000", "perms": "rwx", "cache": true})
+        );
+        assert_eq!(value["board"]["regions"].as_array().unwrap().len(), 2);
+        assert!(map.delete(0).is_err());
+    }
+
+    #[test]
+    fn test_cover() {
+        let mut map = MemoryMap::with_emulator(&config(), None, None).unwrap();
+        let rw = Perms::parse("rw-").unwrap();
+        // Inside the fixed flash and inside sram, nothing to do
+        assert_eq!(map.cover("flash", 0x0800_0000, 0x1000, rw), Ok((0, false)));
+        assert_eq!(map.cover("ram", 0x2000_0000, 0x2000, rw), Ok((1, false)));
+        assert_eq!(map.cover("ram", 0x2000_4000, 0x8000, rw), Ok((1, true)));
+        assert_eq!(map.regions[1].size, 0xc000);
+        assert!(map.cover("flash", 0x0800_f000, 0x2000, rw).is_err());
+        assert_eq!(map.cover("ccm", 0x1000_0000, 0x1000, rw), Ok((3, true)));
+        assert_eq!(map.regions[3].perms, Some(rw));
+    }
+
+    #[test]
+    fn test_split_resize() {
+        // The fields of a new region come from the emulator
+        let mut map =
+            MemoryMap::with_emulator(&json!({}), Some("regions"), Some(emulator)).unwrap();
+        let idx = map.add("flash", 0x0800_0000, 0x20000, None);
+        assert!(map.split(idx, 0x0900_0000).is_err());
+        let upper = map.split(idx, 0x0801_0000).unwrap();
+        assert_eq!(map.regions[upper].name, "flash_2");
+        assert_eq!(
+            (map.regions[idx].size, map.regions[upper].start),
+            (0x10000, 0x0801_0000)
+        );
+        map.resize(upper, 0x0801_0000, 0x800).unwrap();
+        assert!(!map.regions[upper].is_aligned());
+
+        let mut value = json!({});
+        map.apply(&mut value).unwrap();
+        assert_eq!(
+            value["regions"][1],
+            json!({"name": "flash_2", "start": 0x0801_0000u64, "size": 0x800, "perms": "---"})
+        );
+        assert_eq!(
+            parse_region_spec("ram 0x20000000 64k rw-"),
+            Ok((
+                "ram".to_string(),
+                0x2000_0000,
+                0x10000,
+                Perms::parse("rw").ok()
+            ))
+        );
+        assert_eq!(to_pointer("a.b[2].c"), "/a/b/2/c");
+    }
+
+    #[test]
+    fn test_read_back() {
+        let mut map =
+            MemoryMap::with_emulator(&json!({}), Some("regions"), Some(paged_emulator)).unwrap();
+        let idx = map.add("ram", 0x2000_0000, 0x800, Perms::parse("rw").ok());
+        // The emulator would map a whole page, so nothing is written
+        let mut value = json!({"entry": 1});
+        assert!(map.apply(&mut value).is_err());
+        assert_eq!(value, json!({"entry": 1}));
+        map.resize(idx, 0x2000_0000, PAGE_SIZE).unwrap();
+        map.apply(&mut value).unwrap();
+        assert_eq!(value["regions"][0]["size"], json!(PAGE_SIZE));
+    }
+
+    #[test]
+    fn test_unrecognised_layout() {
+        // Nowhere to put regions
+        assert!(MemoryMap::with_emulator(&json!({}), None, None).is_err());
+        // Without the emulator nor a region to go by, no fields are made up
+        assert!(MemoryMap::with_emulator(&json!({}), Some("regions"), None).is_err());
+        // Somewhere the emulator does not read regions from
+        assert!(MemoryMap::with_emulator(&config(), None, Some(emulator)).is_err());
+        // A list of something else than regions
+        let other = json!({"regions": [{"from": 1, "to": 2}]});
+        assert!(MemoryMap::with_emulator(&other, Some("regions"), None).is_err());
+        // Regions without names or permissions are not given any
+        let plain = json!({"regions": [{"base": 0x1000, "size": 0x1000}]});
+        let mut map = MemoryMap::with_emulator(&plain, None, None).unwrap();
+        map.add("ram", 0x2000, 0x1000, Perms::parse("rw").ok());
+        let mut value = plain.clone();
+        map.apply(&mut value).unwrap();
+        assert_eq!(value["regions"][1], json!({"base": 0x2000, "size": 0x1000}));
+    }
+}
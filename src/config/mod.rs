@@ -0,0 +1,11 @@
+pub mod access_rules;
+pub mod diff;
+pub mod highlight;
+pub mod interrupts;
+pub mod manifest;
+pub mod memory_map;
+pub mod profiles;
+pub mod sandbox;
+pub mod schema;
+pub mod source;
+pub mod validate;
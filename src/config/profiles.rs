@@ -0,0 +1,170 @@
+// config/profiles.rs
+// Named configurations of a project. `config.json` is the default profile,
+// the others live in `configs/<name>.json` and project.json names the one
+// the config window and the fuzzer use.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::manifest::ProjectManifest;
+
+pub const DEFAULT_PROFILE: &str = "default";
+pub const PROFILES_DIR: &str = "configs";
+
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+        && !name.starts_with('.')
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    if is_valid_profile_name(name) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid profile name {:?}, use letters, digits, '.', '_' and '-'",
+            name
+        ))
+    }
+}
+
+pub fn profile_path(project_path: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        project_path.join("config.json")
+    } else {
+        project_path
+            .join(PROFILES_DIR)
+            .join(format!("{}.json", name))
+    }
+}
+
+// The default profile first, then the others by name
+pub fn list_profiles(project_path: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(project_path.join(PROFILES_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    let name = name.strip_suffix(".json")?.to_string();
+                    (is_valid_profile_name(&name) && name != DEFAULT_PROFILE).then_some(name)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names.insert(0, DEFAULT_PROFILE.to_string());
+    names
+}
+
+// Falls back to the default profile when the active one is gone
+pub fn active_profile(project_path: &Path) -> String {
+    ProjectManifest::load(project_path)
+        .ok()
+        .and_then(|m| m.profile)
+        .map(|s| s.trim().to_string())
+        .filter(|name| is_valid_profile_name(name) && profile_path(project_path, name).is_file())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+pub fn active_config_path(project_path: &Path) -> PathBuf {
+    profile_path(project_path, &active_profile(project_path))
+}
+
+pub fn set_active_profile(project_path: &Path, name: &str) -> Result<(), String> {
+    if !profile_path(project_path, name).is_file() {
+        return Err(format!("There is no profile named {}", name));
+    }
+    ProjectManifest::update(project_path, |m| m.profile = Some(name.to_string()))
+        .map_err(|e| format!("Failed to set the active profile: {}", e))
+}
+
+// Write a new profile, never over an existing one
+pub fn create_profile(project_path: &Path, name: &str, config: &str) -> Result<PathBuf, String> {
+    check_name(name)?;
+    let path = profile_path(project_path, name);
+    if path.exists() {
+        return Err(format!("A profile named {} already exists", name));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    fs::write(&path, config).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+pub fn clone_profile(project_path: &Path, from: &str, to: &str) -> Result<PathBuf, String> {
+    let src = profile_path(project_path, from);
+    let config =
+        fs::read_to_string(&src).map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+    create_profile(project_path, to, &config)
+}
+
+// The default profile is config.json, which projects cannot do without
+pub fn rename_profile(project_path: &Path, from: &str, to: &str) -> Result<(), String> {
+    if from == DEFAULT_PROFILE {
+        return Err("The default profile cannot be renamed, clone it instead".to_string());
+    }
+    check_name(to)?;
+    let dst = profile_path(project_path, to);
+    if dst.exists() {
+        return Err(format!("A profile named {} already exists", to));
+    }
+    let was_active = active_profile(project_path) == from;
+    fs::rename(profile_path(project_path, from), &dst)
+        .map_err(|e| format!("Failed to rename {}: {}", from, e))?;
+    if was_active {
+        set_active_profile(project_path, to)?;
+    }
+    Ok(())
+}
+
+pub fn delete_profile(project_path: &Path, name: &str) -> Result<(), String> {
+    if name == DEFAULT_PROFILE {
+        return Err("The default profile cannot be deleted".to_string());
+    }
+    let was_active = active_profile(project_path) == name;
+    fs::remove_file(profile_path(project_path, name))
+        .map_err(|e| format!("Failed to delete {}: {}", name, e))?;
+    if was_active {
+        set_active_profile(project_path, DEFAULT_PROFILE)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path();
+        fs::write(project.join("config.json"), "{\"a\": 1}").unwrap();
+        assert_eq!(list_profiles(project), vec!["default"]);
+        assert_eq!(active_config_path(project), project.join("config.json"));
+
+        clone_profile(project, "default", "irq-fixed").unwrap();
+        create_profile(project, "bare", "{}").unwrap();
+        assert!(create_profile(project, "bare", "{}").is_err());
+        assert!(create_profile(project, "../x", "{}").is_err());
+        assert_eq!(list_profiles(project), vec!["default", "bare", "irq-fixed"]);
+        set_active_profile(project, "bare").unwrap();
+        assert_eq!(active_profile(project), "bare");
+
+        set_active_profile(project, "irq-fixed").unwrap();
+        rename_profile(project, "irq-fixed", "irq-guided").unwrap();
+        assert_eq!(active_profile(project), "irq-guided");
+        assert_eq!(
+            fs::read_to_string(active_config_path(project)).unwrap(),
+            "{\"a\": 1}"
+        );
+        delete_profile(project, "irq-guided").unwrap();
+        assert_eq!(active_profile(project), DEFAULT_PROFILE);
+        assert!(delete_profile(project, DEFAULT_PROFILE).is_err());
+        assert!(rename_profile(project, DEFAULT_PROFILE, "x").is_err());
+    }
+}
@@ -0,0 +1,356 @@
+// config/sandbox.rs
+// Confinement for config scripts. The script gets its own user, mount,
+// network and IPC namespaces and a root that only holds the system
+// directories, the project and the files it is handed, all read-only. It
+// cannot change files, read anything else the user can or reach the network.
+use std::collections::HashSet;
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+// What a script needs to start: shells, interpreters and their libraries
+const SYSTEM_DIRS: &[&str] = &["/usr", "/bin", "/sbin", "/lib", "/lib32", "/lib64", "/etc"];
+// Writable, they discard or make up whatever goes through them
+const DEVICES: &[&str] = &["/dev/null", "/dev/zero", "/dev/random", "/dev/urandom"];
+
+// The new root is put together in /newroot while the old one is at /oldroot
+const NEW_ROOT: &str = "/newroot";
+const OLD_ROOT: &str = "/oldroot";
+
+enum Step {
+    Dir(CString),
+    // An empty file for a file to be bound over
+    File(CString),
+    Symlink {
+        target: CString,
+        link: CString,
+    },
+    Bind {
+        src: CString,
+        dst: CString,
+        read_only: bool,
+    },
+}
+
+// Everything is worked out before the fork, `enter` only makes system calls
+pub struct Sandbox {
+    steps: Vec<Step>,
+    uid_map: Vec<u8>,
+    gid_map: Vec<u8>,
+    workdir: CString,
+}
+
+impl Sandbox {
+    // A sandbox running in `workdir` that can also read `visible`. Paths
+    // that do not exist are left out.
+    pub fn new(workdir: &Path, visible: &[&Path]) -> Result<Self, String> {
+        let workdir = workdir
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", workdir.display(), e))?;
+        let mut plan = Plan::default();
+        for dir in SYSTEM_DIRS {
+            let path = Path::new(dir);
+            match fs::symlink_metadata(path) {
+                // e.g. /bin -> usr/bin
+                Ok(meta) if meta.file_type().is_symlink() => {
+                    let target = fs::read_link(path).map_err(|e| e.to_string())?;
+                    plan.steps.push(Step::Symlink {
+                        target: cstring(&target)?,
+                        link: cstring(&under(NEW_ROOT, path))?,
+                    });
+                }
+                Ok(meta) if meta.is_dir() => plan.bind(path, true, true)?,
+                _ => {}
+            }
+        }
+        for dev in DEVICES {
+            let path = Path::new(dev);
+            if path.exists() {
+                plan.bind(path, false, false)?;
+            }
+        }
+        plan.bind(&workdir, true, true)?;
+        for path in visible {
+            if let Ok(path) = path.canonicalize() {
+                let is_dir = path.is_dir();
+                plan.bind(&path, is_dir, true)?;
+            }
+        }
+
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        Ok(Self {
+            steps: plan.steps,
+            uid_map: format!("{} {} 1\n", uid, uid).into_bytes(),
+            gid_map: format!("{} {} 1\n", gid, gid).into_bytes(),
+            workdir: cstring(&workdir)?,
+        })
+    }
+
+    // Move the calling process into the sandbox. Meant for the child between
+    // fork and exec: it only makes system calls and does not allocate.
+    pub fn enter(&self) -> io::Result<()> {
+        unsafe {
+            check(libc::unshare(
+                libc::CLONE_NEWUSER | libc::CLONE_NEWNS | libc::CLONE_NEWNET | libc::CLONE_NEWIPC,
+            ))?;
+            write_file(c"/proc/self/setgroups", b"deny")?;
+            write_file(c"/proc/self/uid_map", &self.uid_map)?;
+            write_file(c"/proc/self/gid_map", &self.gid_map)?;
+
+            // Nothing done from here on reaches the mounts of the user
+            check(libc::mount(
+                std::ptr::null(),
+                c"/".as_ptr(),
+                std::ptr::null(),
+                libc::MS_REC | libc::MS_PRIVATE,
+                std::ptr::null(),
+            ))?;
+            // A scratch root, with the old one below it to bind from
+            mount_tmpfs(c"/tmp")?;
+            mkdir(c"/tmp/newroot")?;
+            mkdir(c"/tmp/oldroot")?;
+            pivot_root(c"/tmp", c"/tmp/oldroot")?;
+            check(libc::chdir(c"/".as_ptr()))?;
+            mount_tmpfs(c"/newroot")?;
+
+            for step in &self.steps {
+                match step {
+                    Step::Dir(path) => mkdir(path)?,
+                    Step::File(path) => {
+                        let fd = check(libc::open(
+                            path.as_ptr(),
+                            libc::O_CREAT | libc::O_WRONLY | libc::O_CLOEXEC,
+                            0o644,
+                        ))?;
+                        libc::close(fd);
+                    }
+                    Step::Symlink { target, link } => {
+                        check(libc::symlink(target.as_ptr(), link.as_ptr()))?;
+                    }
+                    Step::Bind {
+                        src,
+                        dst,
+                        read_only,
+                    } => {
+                        check(libc::mount(
+                            src.as_ptr(),
+                            dst.as_ptr(),
+                            std::ptr::null(),
+                            libc::MS_BIND | libc::MS_REC,
+                            std::ptr::null(),
+                        ))?;
+                        if *read_only {
+                            make_read_only(dst)?;
+                        }
+                    }
+                }
+            }
+
+            check(libc::umount2(c"/oldroot".as_ptr(), libc::MNT_DETACH))?;
+            check(libc::mount(
+                std::ptr::null(),
+                c"/newroot".as_ptr(),
+                std::ptr::null(),
+                libc::MS_REMOUNT | libc::MS_RDONLY | libc::MS_NOSUID | libc::MS_NODEV,
+                std::ptr::null(),
+            ))?;
+            check(libc::chdir(c"/newroot".as_ptr()))?;
+            pivot_root(c".", c".")?;
+            check(libc::umount2(c".".as_ptr(), libc::MNT_DETACH))?;
+            check(libc::chdir(self.workdir.as_ptr()))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Plan {
+    steps: Vec<Step>,
+    // Directories of the new root that are made or bound already
+    dirs: HashSet<PathBuf>,
+    bound: Vec<PathBuf>,
+}
+
+impl Plan {
+    // Show `path` at the same place in the new root
+    fn bind(&mut self, path: &Path, is_dir: bool, read_only: bool) -> Result<(), String> {
+        if self.bound.iter().any(|b| path.starts_with(b)) {
+            return Ok(());
+        }
+        let parents: Vec<&Path> = path.ancestors().skip(1).collect();
+        for parent in parents.into_iter().rev() {
+            if parent != Path::new("/") && self.dirs.insert(parent.to_path_buf()) {
+                self.steps
+                    .push(Step::Dir(cstring(&under(NEW_ROOT, parent))?));
+            }
+        }
+        let dst = cstring(&under(NEW_ROOT, path))?;
+        self.steps.push(if is_dir {
+            Step::Dir(dst.clone())
+        } else {
+            Step::File(dst.clone())
+        });
+        self.steps.push(Step::Bind {
+            src: cstring(&under(OLD_ROOT, path))?,
+            dst,
+            read_only,
+        });
+        self.dirs.insert(path.to_path_buf());
+        self.bound.push(path.to_path_buf());
+        Ok(())
+    }
+}
+
+fn under(root: &str, path: &Path) -> PathBuf {
+    Path::new(root).join(path.strip_prefix("/").unwrap_or(path))
+}
+
+fn cstring(path: &Path) -> Result<CString, String> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| format!("{} cannot be used in the sandbox", path.display()))
+}
+
+fn check<T: PartialOrd + Default>(ret: T) -> io::Result<T> {
+    if ret < T::default() {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+unsafe fn write_file(path: &std::ffi::CStr, data: &[u8]) -> io::Result<()> {
+    let fd = check(libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC))?;
+    let written = libc::write(fd, data.as_ptr().cast(), data.len());
+    libc::close(fd);
+    check(written).map(|_| ())
+}
+
+unsafe fn mkdir(path: &std::ffi::CStr) -> io::Result<()> {
+    match check(libc::mkdir(path.as_ptr(), 0o755)) {
+        Err(e) if e.raw_os_error() != Some(libc::EEXIST) => Err(e),
+        _ => Ok(()),
+    }
+}
+
+unsafe fn mount_tmpfs(path: &std::ffi::CStr) -> io::Result<()> {
+    check(libc::mount(
+        c"tmpfs".as_ptr(),
+        path.as_ptr(),
+        c"tmpfs".as_ptr(),
+        libc::MS_NOSUID | libc::MS_NODEV,
+        std::ptr::null(),
+    ))
+    .map(|_| ())
+}
+
+unsafe fn pivot_root(new_root: &std::ffi::CStr, put_old: &std::ffi::CStr) -> io::Result<()> {
+    check(libc::syscall(
+        libc::SYS_pivot_root,
+        new_root.as_ptr(),
+        put_old.as_ptr(),
+    ))
+    .map(|_| ())
+}
+
+// Read-only along with everything mounted below it. Kernels before 5.12
+// only get the top mount changed.
+unsafe fn make_read_only(path: &std::ffi::CStr) -> io::Result<()> {
+    let attr = libc::mount_attr {
+        attr_set: libc::MOUNT_ATTR_RDONLY | libc::MOUNT_ATTR_NOSUID | libc::MOUNT_ATTR_NODEV,
+        attr_clr: 0,
+        propagation: 0,
+        userns_fd: 0,
+    };
+    let ret = libc::syscall(
+        libc::SYS_mount_setattr,
+        libc::AT_FDCWD,
+        path.as_ptr(),
+        libc::AT_RECURSIVE,
+        &attr as *const libc::mount_attr,
+        std::mem::size_of::<libc::mount_attr>(),
+    );
+    match check(ret) {
+        Err(e) if e.raw_os_error() == Some(libc::ENOSYS) => {}
+        other => return other.map(|_| ()),
+    }
+    // A remount has to keep the flags the user namespace may not clear
+    let mut st: libc::statvfs = std::mem::zeroed();
+    check(libc::statvfs(path.as_ptr(), &mut st))?;
+    let mut flags =
+        libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY | libc::MS_NOSUID | libc::MS_NODEV;
+    for (st_flag, ms_flag) in [
+        (libc::ST_NOEXEC, libc::MS_NOEXEC),
+        (libc::ST_NOATIME, libc::MS_NOATIME),
+        (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+        (libc::ST_RELATIME, libc::MS_RELATIME),
+    ] {
+        if st.f_flag & st_flag != 0 {
+            flags |= ms_flag;
+        }
+    }
+    check(libc::mount(
+        std::ptr::null(),
+        path.as_ptr(),
+        std::ptr::null(),
+        flags,
+        std::ptr::null(),
+    ))
+    .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    fn run(sandbox: Sandbox, script: &str) -> std::process::Output {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg(script);
+        unsafe {
+            cmd.pre_exec(move || sandbox.enter());
+        }
+        cmd.output().unwrap()
+    }
+
+    #[test]
+    fn test_sandbox() {
+        let project = tempfile::tempdir().unwrap();
+        let elsewhere = tempfile::tempdir().unwrap();
+        fs::write(project.path().join("opts.json"), "{}").unwrap();
+        let secret = elsewhere.path().join("secret");
+        fs::write(&secret, "hidden").unwrap();
+        let handed = elsewhere.path().join("fw.elf");
+        fs::write(&handed, "elf").unwrap();
+
+        let sandbox = Sandbox::new(project.path(), &[&handed]).unwrap();
+        let out = run(sandbox, &format!("cat opts.json {}", handed.display()));
+        assert!(out.status.success());
+        assert_eq!(out.stdout, b"{}elf");
+
+        // Nothing outside what it was handed
+        let sandbox = Sandbox::new(project.path(), &[]).unwrap();
+        assert!(!run(sandbox, &format!("cat {}", secret.display()))
+            .status
+            .success());
+
+        // Nothing changed, in the project or anywhere else
+        let sandbox = Sandbox::new(project.path(), &[&handed]).unwrap();
+        let out = run(
+            sandbox,
+            &format!(
+                "echo x > opts.json || echo x > new || echo x > {}",
+                handed.display()
+            ),
+        );
+        assert!(!out.status.success());
+        assert_eq!(
+            fs::read_to_string(project.path().join("opts.json")).unwrap(),
+            "{}"
+        );
+        assert!(!project.path().join("new").exists());
+        assert_eq!(fs::read_to_string(&handed).unwrap(), "elf");
+    }
+}
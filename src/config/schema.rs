@@ -0,0 +1,575 @@
+// config/schema.rs
+use serde_json::{Map, Value};
+
+#[cfg(feature = "for_fuzzer")]
+use flashfuzzemu::opts::EmuOpts;
+
+// Value put into string fields to make the parser list an enum's variants
+#[cfg(feature = "for_fuzzer")]
+const ENUM_PROBE: &str = "__fuzzer_tui_probe__";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldKind {
+    // A nested struct, only shown as a header above its fields
+    Section,
+    Bool,
+    Address,
+    Size,
+    Integer,
+    Float,
+    Text,
+    #[cfg(feature = "for_fuzzer")]
+    Enum(Vec<String>),
+    // Lists, optional values and anything else is edited as plain JSON
+    Json,
+}
+
+impl FieldKind {
+    pub fn describe(&self) -> String {
+        match self {
+            FieldKind::Section => "Group of settings".to_string(),
+            FieldKind::Bool => "true or false".to_string(),
+            FieldKind::Address => "Address, e.g. 0x08000000".to_string(),
+            FieldKind::Size => "Size in bytes, e.g. 0x4000, 16384 or 16k".to_string(),
+            FieldKind::Integer => "Whole number, decimal or 0x hex".to_string(),
+            FieldKind::Float => "Number".to_string(),
+            FieldKind::Text => "Text".to_string(),
+            #[cfg(feature = "for_fuzzer")]
+            FieldKind::Enum(variants) => format!("One of: {}", variants.join(", ")),
+            FieldKind::Json => "JSON value, e.g. null, 42, [..] or {..}".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub path: Vec<String>,
+    pub kind: FieldKind,
+    pub default: Value,
+}
+
+impl Field {
+    pub fn key(&self) -> &str {
+        self.path.last().map_or("", |k| k.as_str())
+    }
+
+    pub fn depth(&self) -> usize {
+        self.path.len().saturating_sub(1)
+    }
+
+    pub fn dotted(&self) -> String {
+        self.path.join(".")
+    }
+
+    pub fn help(&self) -> String {
+        match field_help(self.key()) {
+            Some(h) => format!("{} ({})", h, self.kind.describe()),
+            None => self.kind.describe(),
+        }
+    }
+}
+
+// The fields of a config, generated from its default value: the keys are
+// the fields and the shape of each value decides how it is edited
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    pub fields: Vec<Field>,
+    pub defaults: Value,
+}
+
+impl Schema {
+    pub fn from_defaults(defaults: Value) -> Self {
+        let mut fields = vec![];
+        if let Value::Object(map) = &defaults {
+            walk(&[], map, &mut fields);
+        }
+        Self { fields, defaults }
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    pub fn emu_opts() -> Self {
+        let defaults =
+            serde_json::to_value(EmuOpts::default()).unwrap_or_else(|_| Value::Object(Map::new()));
+        let mut schema = Self::from_defaults(defaults);
+        schema.probe_enums(|v| {
+            EmuOpts::from_json(v.to_string())
+                .err()
+                .map(|e| e.to_string())
+        });
+        schema
+    }
+
+    // Unit enums serialize as plain strings. Feed each string field a bogus
+    // value and, if the parser answers with a list of variants, make it an enum.
+    #[cfg(feature = "for_fuzzer")]
+    pub fn probe_enums(&mut self, check: impl Fn(&Value) -> Option<String>) {
+        for i in 0..self.fields.len() {
+            if self.fields[i].kind != FieldKind::Text {
+                continue;
+            }
+            let mut probe = self.defaults.clone();
+            set_path(&mut probe, &self.fields[i].path, Value::from(ENUM_PROBE));
+            if let Some(variants) = check(&probe).and_then(|e| parse_variants(&e)) {
+                self.fields[i].kind = FieldKind::Enum(variants);
+            }
+        }
+    }
+
+    // Field for a path such as `flash.base` or `regions[2].size`. Paths
+    // below a field, like array elements, resolve to that field.
+    pub fn field_for_path(&self, path: &str) -> Option<usize> {
+        let mut path = path;
+        loop {
+            if let Some(i) = self.fields.iter().position(|f| f.dotted() == path) {
+                return Some(i);
+            }
+            path = &path[..path.rfind(['.', '['])?];
+        }
+    }
+}
+
+fn walk(prefix: &[String], map: &Map<String, Value>, out: &mut Vec<Field>) {
+    for (key, value) in map {
+        let mut path = prefix.to_vec();
+        path.push(key.clone());
+        match value {
+            Value::Object(inner) if !inner.is_empty() => {
+                out.push(Field {
+                    path: path.clone(),
+                    kind: FieldKind::Section,
+                    default: value.clone(),
+                });
+                walk(&path, inner, out);
+            }
+            _ => out.push(Field {
+                kind: infer_kind(key, value),
+                path,
+                default: value.clone(),
+            }),
+        }
+    }
+}
+
+fn infer_kind(key: &str, value: &Value) -> FieldKind {
+    let key = key.to_ascii_lowercase();
+    match value {
+        Value::Bool(_) => FieldKind::Bool,
+        Value::Number(n) if n.is_u64() && is_address_key(&key) => FieldKind::Address,
+        Value::Number(n) if n.is_u64() && is_size_key(&key) => FieldKind::Size,
+        Value::Number(n) if n.is_f64() => FieldKind::Float,
+        Value::Number(_) => FieldKind::Integer,
+        Value::String(_) => FieldKind::Text,
+        _ => FieldKind::Json,
+    }
+}
+
+fn is_address_key(key: &str) -> bool {
+    matches!(key, "entry" | "pc" | "sp" | "base" | "start" | "end")
+        || key.ends_with("addr")
+        || key.ends_with("address")
+        || key.ends_with("_base")
+        || key.starts_with("base_")
+        || key.ends_with("_start")
+        || key.ends_with("_end")
+}
+
+fn is_size_key(key: &str) -> bool {
+    key.contains("size") || key == "len" || key.ends_with("_len") || key.contains("length")
+}
+
+// Help for settings whose name says little about what they do
+fn field_help(key: &str) -> Option<&'static str> {
+    Some(match key {
+        "entry" => "Where execution starts, usually the reset handler",
+        "arch" | "cpu" => "Core the firmware is emulated on",
+        "debug" => "Print emulator debug output",
+        "timeout" | "timeout_ms" => "How long one input may run before it counts as a timeout",
+        "exit_addr" => "Stop the run when execution reaches this address",
+        "flash_base" => "Start of flash, where the binary is loaded",
+        "flash_size" => "Size of flash",
+        "ram_base" => "Start of RAM",
+        "ram_size" => "Size of RAM",
+        "regions" | "memory_map" => "Memory regions mapped into the emulator",
+        "interrupts" => "Interrupts raised while fuzzing",
+        _ => return None,
+    })
+}
+
+// Variants from serde's "unknown variant `x`, expected one of `A`, `B`"
+#[cfg(feature = "for_fuzzer")]
+fn parse_variants(err: &str) -> Option<Vec<String>> {
+    let rest = &err[err.find("unknown variant")?..];
+    let rest = &rest[rest.find("expected")?..];
+    let variants: Vec<String> = rest
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .map(|s| s.to_string())
+        .collect();
+    if variants.is_empty() {
+        None
+    } else {
+        Some(variants)
+    }
+}
+
+pub fn get_path<'v>(value: &'v Value, path: &[String]) -> Option<&'v Value> {
+    path.iter().try_fold(value, |v, key| v.get(key))
+}
+
+// Set a value, creating the objects on the way if the config leaves them out
+pub fn set_path(value: &mut Value, path: &[String], new: Value) {
+    let mut cur = value;
+    for key in path {
+        if !cur.is_object() {
+            *cur = Value::Object(Map::new());
+        }
+        cur = cur
+            .as_object_mut()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert(Value::Null);
+    }
+    *cur = new;
+}
+
+// How a value is shown in the form
+pub fn format_value(kind: &FieldKind, value: &Value) -> String {
+    match (kind, value) {
+        (FieldKind::Size, Value::Number(n)) => match n.as_u64() {
+            Some(size) if size >= 1024 && size.is_multiple_of(1024) => {
+                format!("{:#x} ({})", size, format_size(size))
+            }
+            Some(size) => format!("{:#x}", size),
+            None => n.to_string(),
+        },
+        _ => format_input(kind, value),
+    }
+}
+
+// How a value is prefilled when it is edited
+pub fn format_input(kind: &FieldKind, value: &Value) -> String {
+    match (kind, value) {
+        (FieldKind::Address | FieldKind::Size, Value::Number(n)) => match n.as_u64() {
+            Some(v) => format!("{:#x}", v),
+            None => n.to_string(),
+        },
+        (_, Value::String(s)) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+fn format_size(size: u64) -> String {
+    if size.is_multiple_of(1 << 30) {
+        format!("{} GiB", size >> 30)
+    } else if size.is_multiple_of(1 << 20) {
+        format!("{} MiB", size >> 20)
+    } else {
+        format!("{} KiB", size >> 10)
+    }
+}
+
+pub fn parse_input(kind: &FieldKind, input: &str) -> Result<Value, String> {
+    let input = input.trim();
+    match kind {
+        FieldKind::Section => Err("Sections are edited through their fields".to_string()),
+        FieldKind::Bool => match input.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(Value::Bool(true)),
+            "false" | "no" | "off" | "0" => Ok(Value::Bool(false)),
+            _ => Err(format!("Expected true or false, got {}", input)),
+        },
+        FieldKind::Address => parse_int(input).map(Value::from),
+        FieldKind::Size => parse_size(input).map(Value::from),
+        FieldKind::Integer => match input.strip_prefix('-') {
+            Some(abs) => parse_int(abs)
+                .ok()
+                .and_then(|v| i64::try_from(v).ok())
+                .map(|v| Value::from(-v))
+                .ok_or_else(|| format!("Invalid number: {}", input)),
+            None => parse_int(input).map(Value::from),
+        },
+        FieldKind::Float => input
+            .parse::<f64>()
+            .map_err(|_| format!("Invalid number: {}", input))
+            .and_then(|f| {
+                serde_json::Number::from_f64(f)
+                    .map(Value::Number)
+                    .ok_or_else(|| format!("Invalid number: {}", input))
+            }),
+        FieldKind::Text => Ok(Value::from(input)),
+        #[cfg(feature = "for_fuzzer")]
+        FieldKind::Enum(variants) => variants
+            .iter()
+            .find(|v| v.eq_ignore_ascii_case(input))
+            .map(|v| Value::from(v.as_str()))
+            .ok_or_else(|| format!("Expected one of {}", variants.join(", "))),
+        FieldKind::Json => {
+            serde_json::from_str(input).map_err(|e| format!("Invalid JSON value: {}", e))
+        }
+    }
+}
+
+// Decimal, 0x hex, 0o octal or 0b binary, with optional _ separators
+pub fn parse_int(input: &str) -> Result<u64, String> {
+    let s = input.trim().replace('_', "");
+    let lower = s.to_ascii_lowercase();
+    let res = if let Some(hex) = lower.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(oct) = lower.strip_prefix("0o") {
+        u64::from_str_radix(oct, 8)
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        u64::from_str_radix(bin, 2)
+    } else {
+        lower.parse::<u64>()
+    };
+    res.map_err(|_| format!("Invalid number: {}", input))
+}
+
+// Like parse_int, with a binary k/M/G suffix
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let s = input.trim();
+    let (num, shift) = match s.chars().last() {
+        Some('k') | Some('K') => (&s[..s.len() - 1], 10),
+        Some('m') | Some('M') => (&s[..s.len() - 1], 20),
+        Some('g') | Some('G') => (&s[..s.len() - 1], 30),
+        _ => (s, 0),
+    };
+    parse_int(num)?
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("Size too large: {}", input))
+}
+
+// An error in a config, pinned to the field it is about when that is known
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub path: Option<String>,
+    pub message: String,
+}
+
+// Parse the config the way the emulator will and pin the error to a field
+#[cfg(feature = "for_fuzzer")]
+pub fn check_emu_opts(value: &Value) -> Result<(), FieldError> {
+    locate_error(value, |s| {
+        EmuOpts::from_json(s).map(|_| ()).map_err(|e| e.to_string())
+    })
+}
+
+#[cfg(not(feature = "for_fuzzer"))]
+pub fn check_emu_opts(_value: &Value) -> Result<(), FieldError> {
+    Ok(())
+}
+
+// The config as the emulator reads it: parsed into EmuOpts and written back
+// out, so keys it does not know are gone and its defaults are filled in
+#[cfg(feature = "for_fuzzer")]
+pub fn read_emu_opts(value: &Value) -> Result<Value, String> {
+    let opts = EmuOpts::from_json(value.to_string()).map_err(|e| e.to_string())?;
+    serde_json::to_value(opts).map_err(|e| e.to_string())
+}
+
+// Run `parse` on the pretty printed value and use the position serde puts
+// into its messages to find the field the error is about
+#[cfg(feature = "for_fuzzer")]
+pub fn locate_error(
+    value: &Value,
+    parse: impl Fn(String) -> Result<(), String>,
+) -> Result<(), FieldError> {
+    let pretty = serde_json::to_string_pretty(value).map_err(|e| FieldError {
+        path: None,
+        message: e.to_string(),
+    })?;
+    let Err(message) = parse(pretty.clone()) else {
+        return Ok(());
+    };
+    let paths = line_paths(&pretty);
+    let mut path = error_line(&message)
+        .and_then(|l| paths.get(l.checked_sub(1)?))
+        .cloned();
+    // Missing fields are reported at the end of the object they belong in
+    if let Some(missing) = quoted_after(&message, "missing field") {
+        path = Some(match path.as_deref() {
+            Some("") | None => missing,
+            Some(parent) => format!("{}.{}", parent, missing),
+        });
+    }
+    Err(FieldError {
+        path: path.filter(|p| !p.is_empty()),
+        message: strip_position(&message),
+    })
+}
+
+// serde_json ends its messages with "at line L column C"
+#[cfg(feature = "for_fuzzer")]
+pub fn error_line(message: &str) -> Option<usize> {
+    let rest = &message[message.rfind(" at line ")? + " at line ".len()..];
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+#[cfg(feature = "for_fuzzer")]
+fn strip_position(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(i) => message[..i].to_string(),
+        None => message.to_string(),
+    }
+}
+
+// The first `quoted` name after `marker` in a serde error
+pub fn quoted_after(message: &str, marker: &str) -> Option<String> {
+    let rest = &message[message.find(marker)? + marker.len()..];
+    let rest = &rest[rest.find('`')? + 1..];
+    Some(rest[..rest.find('`')?].to_string())
+}
+
+// The JSON path each line of serde's pretty output belongs to, e.g.
+// `flash.flash_base` or `regions[1].size`. Closing lines belong to the
+// object or list they close.
+#[cfg(feature = "for_fuzzer")]
+pub fn line_paths(pretty: &str) -> Vec<String> {
+    // Open objects and lists, with the index of the next element for lists
+    let mut open: Vec<(String, Option<usize>)> = vec![];
+    let mut ret = vec![];
+    for line in pretty.lines() {
+        let t = line.trim().trim_end_matches(',');
+        if t == "}" || t == "]" {
+            ret.push(open.pop().map(|(p, _)| p).unwrap_or_default());
+            continue;
+        }
+        let parent = open.last().map(|(p, _)| p.clone()).unwrap_or_default();
+        let (path, rest) = match split_key(t) {
+            Some((key, rest)) if parent.is_empty() => (key, rest),
+            Some((key, rest)) => (format!("{}.{}", parent, key), rest),
+            None => match open.last_mut() {
+                Some((_, Some(idx))) => {
+                    *idx += 1;
+                    (format!("{}[{}]", parent, *idx - 1), t)
+                }
+                _ => (parent, t),
+            },
+        };
+        match rest {
+            "{" => open.push((path.clone(), None)),
+            "[" => open.push((path.clone(), Some(0))),
+            _ => {}
+        }
+        ret.push(path);
+    }
+    ret
+}
+
+// `"key": rest` into the key and the rest
+#[cfg(feature = "for_fuzzer")]
+fn split_key(t: &str) -> Option<(String, &str)> {
+    let body = t.strip_prefix('"')?;
+    let mut escaped = false;
+    for (i, c) in body.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => {
+                let rest = body[i + 1..].strip_prefix(':')?;
+                let key = serde_json::from_str(&t[..i + 2]).ok()?;
+                return Some((key, rest.trim()));
+            }
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_schema() -> Schema {
+        Schema::from_defaults(json!({
+            "entry": 0,
+            "debug": false,
+            "arch": "CortexM0",
+            "flash": {"flash_base": 0, "flash_size": 0},
+            "regions": [],
+            "exit_addr": null,
+        }))
+    }
+
+    #[test]
+    fn test_schema_from_defaults() {
+        let schema = test_schema();
+        let kinds: Vec<(String, FieldKind)> = schema
+            .fields
+            .iter()
+            .map(|f| (f.dotted(), f.kind.clone()))
+            .collect();
+        assert!(kinds.contains(&("entry".into(), FieldKind::Address)));
+        assert!(kinds.contains(&("debug".into(), FieldKind::Bool)));
+        assert!(kinds.contains(&("flash".into(), FieldKind::Section)));
+        assert!(kinds.contains(&("flash.flash_size".into(), FieldKind::Size)));
+        assert!(kinds.contains(&("regions".into(), FieldKind::Json)));
+        assert!(kinds.contains(&("exit_addr".into(), FieldKind::Json)));
+        assert_eq!(
+            schema.field_for_path("regions[1].size"),
+            schema.field_for_path("regions")
+        );
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    #[test]
+    fn test_probe_enums() {
+        let mut schema = test_schema();
+        schema.probe_enums(|v| match v["arch"].as_str() {
+            Some("CortexM0") => None,
+            _ => Some("unknown variant `x`, expected `CortexM0` or `CortexM4`".to_string()),
+        });
+        let arch = &schema.fields[schema.field_for_path("arch").unwrap()];
+        assert_eq!(
+            arch.kind,
+            FieldKind::Enum(vec!["CortexM0".into(), "CortexM4".into()])
+        );
+    }
+
+    #[test]
+    fn test_parse_input() {
+        assert_eq!(
+            parse_input(&FieldKind::Address, "0x0800_0000"),
+            Ok(json!(0x0800_0000))
+        );
+        assert_eq!(parse_input(&FieldKind::Size, "64k"), Ok(json!(65536)));
+        assert_eq!(parse_input(&FieldKind::Integer, "-3"), Ok(json!(-3)));
+        assert_eq!(parse_input(&FieldKind::Bool, "yes"), Ok(json!(true)));
+        assert_eq!(parse_input(&FieldKind::Json, "[1, 2]"), Ok(json!([1, 2])));
+        assert!(parse_input(&FieldKind::Address, "0xzz").is_err());
+        assert_eq!(
+            format_value(&FieldKind::Size, &json!(65536)),
+            "0x10000 (64 KiB)"
+        );
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    #[test]
+    fn test_locate_error() {
+        let value = json!({"flash": {"flash_base": 1}, "regions": [{"size": "big"}]});
+        let err = locate_error(&value, |s| {
+            serde_json::from_str::<Value>(&s).unwrap();
+            if s.contains("big") {
+                let line = s.lines().position(|l| l.contains("big")).unwrap() + 1;
+                return Err(format!(
+                    "invalid type: string \"big\" at line {} column 5",
+                    line
+                ));
+            }
+            Ok(())
+        })
+        .unwrap_err();
+        assert_eq!(err.path.as_deref(), Some("regions[0].size"));
+        assert_eq!(err.message, "invalid type: string \"big\"");
+
+        let err = locate_error(&value, |s| {
+            let line = s.lines().position(|l| l.trim() == "},").unwrap() + 1;
+            Err(format!(
+                "missing field `flash_size` at line {} column 3",
+                line
+            ))
+        })
+        .unwrap_err();
+        assert_eq!(err.path.as_deref(), Some("flash.flash_size"));
+    }
+}
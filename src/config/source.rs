@@ -0,0 +1,307 @@
+// config/source.rs
+use std::fs;
+use std::io::{self, Read};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use super::sandbox::Sandbox;
+
+pub const SCRIPT_TIMEOUT: Duration = Duration::from_secs(10);
+// More than any config needs, and bounded so a runaway script cannot eat memory
+const MAX_SCRIPT_OUTPUT: u64 = 4 << 20;
+const SCRIPT_MEMORY_LIMIT: libc::rlim_t = 4 << 30;
+
+// Where a configuration can be loaded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    Json,
+    Toml,
+    // An executable that prints the configuration as JSON. It runs in a
+    // sandbox, and the TUI still asks before running one.
+    Script,
+}
+
+impl ConfigSource {
+    pub fn of(path: &Path) -> Result<Self, String> {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("json") => return Ok(ConfigSource::Json),
+            Some("toml") => return Ok(ConfigSource::Toml),
+            _ => {}
+        }
+        let meta =
+            fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if meta.is_file() && meta.permissions().mode() & 0o111 != 0 {
+            Ok(ConfigSource::Script)
+        } else {
+            Err(format!(
+                "{} is not a .json or .toml file or an executable script",
+                path.display()
+            ))
+        }
+    }
+}
+
+// Read a configuration from a file or script. Scripts run in the project
+// directory and are told the selected binary through FUZZER_TUI_BINARY.
+pub fn load_config_source(path: &Path, binary: Option<&Path>) -> Result<Value, String> {
+    match ConfigSource::of(path)? {
+        ConfigSource::Json => {
+            let s = read(path)?;
+            serde_json::from_str(&s)
+                .map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))
+        }
+        ConfigSource::Toml => {
+            let s = read(path)?;
+            toml::from_str(&s).map_err(|e| format!("Invalid TOML in {}: {}", path.display(), e))
+        }
+        ConfigSource::Script => {
+            let out = run_script(path, binary, SCRIPT_TIMEOUT)?;
+            serde_json::from_str(&out).map_err(|e| format!("Script output is not JSON: {}", e))
+        }
+    }
+}
+
+// A configuration loading on a thread, so a slow script does not freeze the
+// interface. Poll it on every frame.
+pub struct PendingSource {
+    pub path: PathBuf,
+    started: Instant,
+    rx: Receiver<Result<Value, String>>,
+}
+
+impl PendingSource {
+    pub fn start(path: PathBuf, binary: Option<PathBuf>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let thread_path = path.clone();
+        thread::spawn(move || {
+            let _ = tx.send(load_config_source(&thread_path, binary.as_deref()));
+        });
+        Self {
+            path,
+            started: Instant::now(),
+            rx,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    // The result once the load is done
+    pub fn poll(&self) -> Option<Result<Value, String>> {
+        match self.rx.try_recv() {
+            Ok(res) => Some(res),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(format!(
+                "Loading {} failed unexpectedly",
+                self.path.display()
+            ))),
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+// Run a config script and return its stdout. The script runs in a Sandbox
+// from the current directory: it can read the project, the binary and the
+// system directories but change nothing and reach no network. It gets no
+// stdin, a bare environment and no privileges it did not start with, and the
+// CPU, memory and file size limits keep a runaway script from taking the
+// machine down. It and everything it started are killed once the timeout is
+// up. Without user namespaces the script is not run at all.
+pub fn run_script(path: &Path, binary: Option<&Path>, timeout: Duration) -> Result<String, String> {
+    // The sandbox has its own root, so the script is run by its full path
+    let path = &path
+        .canonicalize()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let workdir = std::env::current_dir()
+        .map_err(|e| format!("Failed to read the current directory: {}", e))?;
+    let mut visible = vec![path.as_path()];
+    visible.extend(binary);
+    let sandbox = Sandbox::new(&workdir, &visible)?;
+
+    let mut cmd = Command::new(path);
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .env_clear();
+    for var in ["PATH", "LANG"] {
+        if let Some(v) = std::env::var_os(var) {
+            cmd.env(var, v);
+        }
+    }
+    if let Some(binary) = binary {
+        cmd.env("FUZZER_TUI_BINARY", binary);
+    }
+    let cpu_secs = timeout.as_secs() + 1;
+    unsafe {
+        cmd.pre_exec(move || {
+            libc::setpgid(0, 0);
+            libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0);
+            libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+            sandbox.enter()?;
+            set_limit(libc::RLIMIT_CPU, cpu_secs);
+            set_limit(libc::RLIMIT_AS, SCRIPT_MEMORY_LIMIT);
+            set_limit(libc::RLIMIT_FSIZE, 0);
+            set_limit(libc::RLIMIT_CORE, 0);
+            Ok(())
+        });
+    }
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to run {} in a sandbox: {}", path.display(), e))?;
+    let pid = child.id() as libc::pid_t;
+    let stdout = capture(child.stdout.take());
+    let stderr = capture(child.stderr.take());
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= timeout => {
+                unsafe {
+                    libc::kill(-pid, libc::SIGKILL);
+                }
+                let _ = child.wait();
+                return Err(format!("Script timed out after {:?}", timeout));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(format!("Failed to wait for script: {}", e)),
+        }
+    };
+    // Anything the script left running would keep the pipes open
+    unsafe {
+        libc::kill(-pid, libc::SIGKILL);
+    }
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        let reason = match status.code() {
+            Some(code) => format!("exited with status {}", code),
+            None => "was killed by a signal".to_string(),
+        };
+        let last = stderr.lines().last().unwrap_or("").trim();
+        return Err(if last.is_empty() {
+            format!("Script {}", reason)
+        } else {
+            format!("Script {}: {}", reason, last)
+        });
+    }
+    Ok(stdout)
+}
+
+// glibc declares setrlimit with its own enum, everyone else with an int
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type Resource = libc::c_int;
+
+unsafe fn set_limit(resource: Resource, value: u64) {
+    let limit = libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    };
+    libc::setrlimit(resource, &limit);
+}
+
+// Collect a pipe on a thread, keeping at most MAX_SCRIPT_OUTPUT bytes
+fn capture<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let Some(mut pipe) = pipe else {
+            return String::new();
+        };
+        let mut buf = vec![];
+        let _ = (&mut pipe).take(MAX_SCRIPT_OUTPUT).read_to_end(&mut buf);
+        let _ = io::copy(&mut pipe, &mut io::sink());
+        String::from_utf8_lossy(&buf).to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn script(dir: &Path, name: &str, body: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml_path = dir.path().join("opts.toml");
+        fs::write(&toml_path, "entry = 4096\n[flash]\nsize = 65536\n").unwrap();
+        assert_eq!(
+            load_config_source(&toml_path, None),
+            Ok(json!({"entry": 4096, "flash": {"size": 65536}}))
+        );
+
+        let ok = script(
+            dir.path(),
+            "gen",
+            "echo \"{\\\"binary\\\": \\\"$FUZZER_TUI_BINARY\\\"}\"",
+        );
+        assert_eq!(
+            load_config_source(&ok, Some(Path::new("fw.elf"))),
+            Ok(json!({"binary": "fw.elf"}))
+        );
+
+        let failing = script(dir.path(), "fail", "echo broken >&2; exit 3");
+        assert_eq!(
+            load_config_source(&failing, None),
+            Err("Script exited with status 3: broken".to_string())
+        );
+
+        // Sandboxed, it cannot leave anything behind
+        let writer = script(dir.path(), "writer", "echo x > \"$0.out\" && echo {}");
+        assert!(load_config_source(&writer, None).is_err());
+        assert!(!dir.path().join("writer.out").exists());
+
+        let plain = dir.path().join("notes.txt");
+        fs::write(&plain, "hi").unwrap();
+        assert!(ConfigSource::of(&plain).is_err());
+    }
+
+    #[test]
+    fn test_pending_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let slow = script(dir.path(), "slow", "sleep 0.2; echo '{\"port\": 1}'");
+        let pending = PendingSource::start(slow, None);
+        // Started without waiting for the script
+        assert_eq!(pending.poll(), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let res = loop {
+            if let Some(res) = pending.poll() {
+                break res;
+            }
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(20));
+        };
+        assert_eq!(res, Ok(json!({"port": 1})));
+    }
+
+    #[test]
+    fn test_script_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let slow = script(dir.path(), "slow", "sleep 5; echo {}");
+        let started = Instant::now();
+        let res = run_script(&slow, None, Duration::from_millis(300));
+        assert_eq!(res, Err("Script timed out after 300ms".to_string()));
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,528 @@
+// config/validate.rs
+// Checks that only make sense for firmware: memory layout, entry point,
+// stack and interrupts. With the emulator built in the config is read as
+// EmuOpts first, so only settings the emulator actually has are checked.
+// They are found by the names they usually go by, and a layout that cannot
+// be read that way is a warning, not a reason to refuse the config.
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::Value;
+
+#[cfg(feature = "for_fuzzer")]
+use super::schema::read_emu_opts;
+use super::schema::{check_emu_opts, parse_int};
+
+// Granularity the emulator maps memory in
+pub const PAGE_SIZE: u64 = 0x1000;
+
+pub const START_KEYS: &[&str] = &["base", "start", "address", "addr", "origin"];
+pub const SIZE_KEYS: &[&str] = &["size", "length", "len"];
+const ENTRY_KEYS: &[&str] = &["entry", "entry_point", "entrypoint", "reset_handler"];
+const SP_KEYS: &[&str] = &[
+    "sp",
+    "initial_sp",
+    "stack_pointer",
+    "initial_stack_pointer",
+    "msp",
+];
+pub const IRQ_KEYS: &[&str] = &["irq", "number", "num", "irqn", "id", "vector"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    // The config must not be saved or fuzzed with
+    Error,
+    // Worth a look, but nothing is refused for it
+    Warning,
+}
+
+// One problem in a config, at the JSON path it is about. An empty path is
+// the config as a whole.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub path: String,
+    pub message: String,
+    pub severity: Severity,
+}
+
+impl Issue {
+    pub fn new(path: &str, message: String) -> Self {
+        Self {
+            path: path.to_string(),
+            message,
+            severity: Severity::Error,
+        }
+    }
+
+    pub fn warning(path: &str, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::new(path, message)
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+// The issues that have to be fixed before saving or fuzzing
+pub fn errors(issues: &[Issue]) -> Vec<&Issue> {
+    issues.iter().filter(|i| i.is_error()).collect()
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+    Flash,
+    Ram,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub path: String,
+    pub name: String,
+    pub kind: RegionKind,
+    pub start: u64,
+    pub size: u64,
+}
+
+impl Region {
+    pub fn end(&self) -> u64 {
+        self.start.saturating_add(self.size)
+    }
+
+    pub fn contains(&self, addr: u64) -> bool {
+        addr >= self.start && addr < self.end()
+    }
+
+    fn describe(&self) -> String {
+        format!("'{}' ({:#x}-{:#x})", self.name, self.start, self.end())
+    }
+}
+
+// Everything wrong with a config, for a binary of `binary_len` bytes if one
+// is selected. Empty means the config is good to fuzz with.
+pub fn validate(value: &Value, binary_len: Option<u64>) -> Vec<Issue> {
+    let mut issues = vec![];
+    if let Err(e) = check_emu_opts(value) {
+        issues.push(Issue::new(e.path.as_deref().unwrap_or(""), e.message));
+    }
+    let emulator_value = emulator_view(value);
+    let value = emulator_value.as_ref().unwrap_or(value);
+
+    let regions = find_regions(value);
+    // Nothing below can be checked then, so say so rather than pass silently
+    if regions.is_empty() {
+        issues.push(Issue::warning(
+            "",
+            format!(
+                "Could not find memory regions, the layout was not checked: expected objects with a start ({}) and a size ({} or end), or <name>_base and <name>_size keys",
+                START_KEYS.join("/"),
+                SIZE_KEYS.join("/")
+            ),
+        ));
+    }
+    for (i, r) in regions.iter().enumerate() {
+        if r.start % PAGE_SIZE != 0 || r.size % PAGE_SIZE != 0 {
+            issues.push(Issue::new(
+                &r.path,
+                format!(
+                    "Region {} is not aligned to {:#x}, start and size must be multiples of it",
+                    r.describe(),
+                    PAGE_SIZE
+                ),
+            ));
+        }
+        for other in &regions[..i] {
+            if r.start < other.end() && other.start < r.end() {
+                issues.push(Issue::new(
+                    &r.path,
+                    format!("Region {} overlaps {}", r.describe(), other.describe()),
+                ));
+            }
+        }
+    }
+
+    let flash: Vec<&Region> = of_kind(&regions, RegionKind::Flash);
+    let ram: Vec<&Region> = of_kind(&regions, RegionKind::Ram);
+    for (path, entry) in find_numbers(value, "", ENTRY_KEYS) {
+        // Thumb code sets the lowest bit of code addresses
+        let addr = entry & !1;
+        if !flash.is_empty() && !flash.iter().any(|r| r.contains(addr)) {
+            issues.push(Issue::new(
+                &path,
+                format!("Entry point {:#x} is not in mapped flash", entry),
+            ));
+        }
+    }
+    for (path, sp) in find_numbers(value, "", SP_KEYS) {
+        // The stack grows down, so the initial SP may sit right past the end of RAM
+        if !ram.is_empty() && !ram.iter().any(|r| sp > r.start && sp <= r.end()) {
+            issues.push(Issue::new(
+                &path,
+                format!("Stack pointer {:#x} is not in RAM", sp),
+            ));
+        }
+    }
+
+    if let Some(len) = binary_len {
+        if let Some(r) = load_region(value, &flash) {
+            if len > r.size {
+                issues.push(Issue::new(
+                    &r.path,
+                    format!(
+                        "Binary is {:#x} bytes, more than flash region {} holds",
+                        len,
+                        r.describe()
+                    ),
+                ));
+            }
+        }
+    }
+
+    issues.extend(check_interrupts(value, ""));
+    issues
+}
+
+// The config as EmuOpts reads it: settings the emulator does not have are
+// gone and the rest go by its own field names. None when it does not parse,
+// which check_emu_opts already reported.
+#[cfg(feature = "for_fuzzer")]
+fn emulator_view(value: &Value) -> Option<Value> {
+    read_emu_opts(value).ok()
+}
+
+#[cfg(not(feature = "for_fuzzer"))]
+fn emulator_view(_value: &Value) -> Option<Value> {
+    None
+}
+
+// The flash region the binary is loaded into: the one holding the entry
+// point, or the first
+fn load_region<'r>(value: &Value, flash: &[&'r Region]) -> Option<&'r Region> {
+    let entry = find_numbers(value, "", ENTRY_KEYS)
+        .first()
+        .map(|(_, e)| e & !1);
+    flash
+        .iter()
+        .find(|r| entry.is_some_and(|e| r.contains(e)))
+        .or(flash.first())
+        .copied()
+}
+
+// Where the emulator puts the first byte of the binary
+pub fn flash_base(value: &Value) -> Option<u64> {
+    let regions = find_regions(value);
+    load_region(value, &of_kind(&regions, RegionKind::Flash)).map(|r| r.start)
+}
+
+fn of_kind(regions: &[Region], kind: RegionKind) -> Vec<&Region> {
+    let matching: Vec<&Region> = regions.iter().filter(|r| r.kind == kind).collect();
+    // Without names to go by, any region may be flash or RAM
+    if matching.is_empty() {
+        regions
+            .iter()
+            .filter(|r| r.kind == RegionKind::Other)
+            .collect()
+    } else {
+        matching
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+// Addresses may be numbers or strings like "0x08000000"
+pub fn as_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => parse_int(s).ok(),
+        _ => None,
+    }
+}
+
+fn region_kind(name: &str) -> RegionKind {
+    let name = name.to_ascii_lowercase();
+    if ["flash", "rom", "text", "code"]
+        .iter()
+        .any(|k| name.contains(k))
+    {
+        RegionKind::Flash
+    } else if ["ram", "data", "stack", "heap"]
+        .iter()
+        .any(|k| name.contains(k))
+    {
+        RegionKind::Ram
+    } else {
+        RegionKind::Other
+    }
+}
+
+fn last_segment(path: &str) -> &str {
+    let seg = path.rsplit('.').next().unwrap_or(path);
+    seg.split('[').next().unwrap_or(seg)
+}
+
+// Memory regions anywhere in the config. A region is either an object with
+// a base and a size, like `{"name": "flash", "base": .., "size": ..}`, or
+// a pair of keys like `flash_base` and `flash_size`. Regions without a size
+// are taken as not set up and left out.
+pub fn find_regions(value: &Value) -> Vec<Region> {
+    let mut ret = vec![];
+    walk_regions(value, "", &mut ret);
+    ret.retain(|r| r.size > 0);
+    ret
+}
+
+fn walk_regions(value: &Value, path: &str, out: &mut Vec<Region>) {
+    match value {
+        Value::Object(map) => {
+            let get = |keys: &[&str]| keys.iter().find_map(|k| map.get(*k).and_then(as_u64));
+            if let Some(start) = get(START_KEYS) {
+                let size =
+                    get(SIZE_KEYS).or_else(|| get(&["end"]).map(|e| e.saturating_sub(start)));
+                if let Some(size) = size {
+                    let name = ["name", "kind", "type"]
+                        .iter()
+                        .find_map(|k| map.get(*k).and_then(|v| v.as_str()))
+                        .unwrap_or(last_segment(path))
+                        .to_string();
+                    out.push(Region {
+                        path: path.to_string(),
+                        kind: region_kind(&name),
+                        name,
+                        start,
+                        size,
+                    });
+                }
+            }
+            for (key, v) in map {
+                let Some(prefix) = ["_base", "_start", "_addr"]
+                    .iter()
+                    .find_map(|s| key.strip_suffix(s))
+                else {
+                    continue;
+                };
+                let Some(start) = as_u64(v) else {
+                    continue;
+                };
+                let size = SIZE_KEYS
+                    .iter()
+                    .find_map(|s| map.get(&format!("{}_{}", prefix, s)).and_then(as_u64))
+                    .or_else(|| {
+                        map.get(&format!("{}_end", prefix))
+                            .and_then(as_u64)
+                            .map(|e| e.saturating_sub(start))
+                    });
+                if let Some(size) = size {
+                    out.push(Region {
+                        path: join(path, key),
+                        name: prefix.to_string(),
+                        kind: region_kind(prefix),
+                        start,
+                        size,
+                    });
+                }
+            }
+            for (key, v) in map {
+                walk_regions(v, &join(path, key), out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                walk_regions(v, &format!("{}[{}]", path, i), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn is_entry_key(key: &str) -> bool {
+    ENTRY_KEYS.contains(&key)
+}
+
+// Where the config sets the entry point, if it does
+pub fn entry_path(value: &Value) -> Option<String> {
+    find_numbers(value, "", ENTRY_KEYS)
+        .into_iter()
+        .next()
+        .map(|(path, _)| path)
+}
+
+// Numbers under any of `keys`, anywhere in the config
+fn find_numbers(value: &Value, path: &str, keys: &[&str]) -> Vec<(String, u64)> {
+    let mut ret = vec![];
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map {
+                let p = join(path, key);
+                if keys.contains(&key.as_str()) {
+                    if let Some(n) = as_u64(v) {
+                        ret.push((p.clone(), n));
+                    }
+                }
+                ret.extend(find_numbers(v, &p, keys));
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                ret.extend(find_numbers(v, &format!("{}[{}]", path, i), keys));
+            }
+        }
+        _ => {}
+    }
+    ret
+}
+
+// Lists of interrupts, as plain numbers or objects with an `irq`-like key,
+// must not name the same interrupt twice
+fn check_interrupts(value: &Value, path: &str) -> Vec<Issue> {
+    let mut issues = vec![];
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map {
+                let p = join(path, key);
+                let lower = key.to_ascii_lowercase();
+                if let (true, Value::Array(items)) =
+                    (lower.contains("interrupt") || lower.contains("irq"), v)
+                {
+                    let mut seen: HashMap<u64, String> = HashMap::new();
+                    for (i, item) in items.iter().enumerate() {
+                        let number = match item {
+                            Value::Object(m) => {
+                                IRQ_KEYS.iter().find_map(|k| m.get(*k).and_then(as_u64))
+                            }
+                            other => as_u64(other),
+                        };
+                        let Some(number) = number else {
+                            continue;
+                        };
+                        let item_path = format!("{}[{}]", p, i);
+                        match seen.get(&number) {
+                            Some(first) => issues.push(Issue::new(
+                                &item_path,
+                                format!("Interrupt {} is already listed at {}", number, first),
+                            )),
+                            None => {
+                                seen.insert(number, item_path);
+                            }
+                        }
+                    }
+                }
+                issues.extend(check_interrupts(v, &p));
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                issues.extend(check_interrupts(v, &format!("{}[{}]", path, i)));
+            }
+        }
+        _ => {}
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn messages(value: &Value, binary_len: Option<u64>) -> Vec<String> {
+        // Only the firmware checks, the schema depends on the emulator build
+        validate(value, binary_len)
+            .into_iter()
+            .filter(|i| {
+                !i.message.contains("unknown field") && !i.message.contains("missing field")
+            })
+            .map(|i| i.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_find_regions() {
+        let value = json!({
+            "flash_base": "0x08000000",
+            "flash_size": 65536,
+            "regions": [{"name": "sram", "start": 0x2000_0000u64, "end": 0x2000_8000u64}],
+        });
+        let regions = find_regions(&value);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].path, "flash_base");
+        assert_eq!(regions[0].kind, RegionKind::Flash);
+        assert_eq!(regions[1].path, "regions[0]");
+        assert_eq!(
+            (regions[1].kind, regions[1].size),
+            (RegionKind::Ram, 0x8000)
+        );
+    }
+
+    #[test]
+    fn test_validate_layout() {
+        let good = json!({
+            "entry": 0x0800_0101u64,
+            "initial_sp": 0x2000_8000u64,
+            "regions": [
+                {"name": "flash", "base": 0x0800_0000u64, "size": 0x10000},
+                {"name": "ram", "base": 0x2000_0000u64, "size": 0x8000},
+            ],
+            "interrupts": [{"irq": 5}, {"irq": 6}],
+        });
+        assert!(messages(&good, Some(0x8000)).is_empty());
+        assert_eq!(flash_base(&good), Some(0x0800_0000));
+        assert_eq!(flash_base(&json!({})), None);
+        // A layout we cannot read is reported, but does not hold the config up
+        let unread = validate(&json!({"mem": [{"from": 0, "to": 0x1000}]}), None);
+        let warnings: Vec<&Issue> = unread.iter().filter(|i| !i.is_error()).collect();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0]
+            .message
+            .starts_with("Could not find memory regions"));
+
+        let bad = json!({
+            "entry": 0x0900_0000u64,
+            "initial_sp": 0x3000_0000u64,
+            "regions": [
+                {"name": "flash", "base": 0x0800_0000u64, "size": 0x10000},
+                {"name": "ram", "base": 0x0800_8000u64, "size": 0x8100},
+            ],
+            "interrupts": [5, 6, 5],
+        });
+        assert_eq!(
+            messages(&bad, Some(0x20000)),
+            vec![
+                "regions[1]: Region 'ram' (0x8008000-0x8010100) is not aligned to 0x1000, start and size must be multiples of it",
+                "regions[1]: Region 'ram' (0x8008000-0x8010100) overlaps 'flash' (0x8000000-0x8010000)",
+                "entry: Entry point 0x9000000 is not in mapped flash",
+                "initial_sp: Stack pointer 0x30000000 is not in RAM",
+                "regions[0]: Binary is 0x20000 bytes, more than flash region 'flash' (0x8000000-0x8010000) holds",
+                "interrupts[2]: Interrupt 5 is already listed at interrupts[0]",
+            ]
+        );
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    #[test]
+    fn test_default_config_is_not_refused() {
+        use flashfuzzemu::opts::EmuOpts;
+
+        // What a new project is written with
+        let value = serde_json::to_value(EmuOpts::default()).unwrap();
+        assert!(errors(&validate(&value, None)).is_empty());
+    }
+}
@@ -0,0 +1,769 @@
+// firmware/elf.rs
+// Headers of an ELF file: what it loads where, its sections and symbols.
+// Enough to show a firmware file and lay out memory for it.
+use crate::config::memory_map::Perms;
+use crate::config::validate::PAGE_SIZE;
+
+pub const PT_LOAD: u32 = 1;
+#[cfg(feature = "for_fuzzer")]
+const PT_NOTE: u32 = 4;
+const SHT_SYMTAB: u32 = 2;
+#[cfg(feature = "for_fuzzer")]
+const SHT_NOTE: u32 = 7;
+#[cfg(feature = "for_fuzzer")]
+const NT_GNU_BUILD_ID: u32 = 3;
+const STT_FUNC: u8 = 2;
+const STB_GLOBAL: u8 = 1;
+const STB_WEAK: u8 = 2;
+const SHF_WRITE: u64 = 1;
+const SHF_ALLOC: u64 = 2;
+const SHF_EXECINSTR: u64 = 4;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+const EM_ARM: u16 = 40;
+// The initial SP may sit this far past the data, where the linker script
+// puts the stack at the end of RAM
+const MAX_STACK_GAP: u64 = 0x10_0000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub p_type: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub vaddr: u64,
+    // The load address, where initialised data sits in flash
+    pub paddr: u64,
+    pub filesz: u64,
+    pub memsz: u64,
+}
+
+impl Segment {
+    pub fn is_load(&self) -> bool {
+        self.p_type == PT_LOAD
+    }
+
+    pub fn type_name(&self) -> String {
+        match self.p_type {
+            0 => "NULL".to_string(),
+            1 => "LOAD".to_string(),
+            2 => "DYNAMIC".to_string(),
+            3 => "INTERP".to_string(),
+            4 => "NOTE".to_string(),
+            6 => "PHDR".to_string(),
+            7 => "TLS".to_string(),
+            0x6474_e550 => "GNU_EH_FRAME".to_string(),
+            0x6474_e551 => "GNU_STACK".to_string(),
+            0x6474_e552 => "GNU_RELRO".to_string(),
+            0x7000_0001 => "ARM_EXIDX".to_string(),
+            t => format!("{:#x}", t),
+        }
+    }
+
+    pub fn perms(&self) -> Perms {
+        Perms {
+            read: self.flags & PF_R != 0,
+            write: self.flags & PF_W != 0,
+            exec: self.flags & PF_X != 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub sh_type: u32,
+    pub flags: u64,
+    pub addr: u64,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl Section {
+    pub fn type_name(&self) -> String {
+        match self.sh_type {
+            0 => "NULL".to_string(),
+            1 => "PROGBITS".to_string(),
+            2 => "SYMTAB".to_string(),
+            3 => "STRTAB".to_string(),
+            4 => "RELA".to_string(),
+            7 => "NOTE".to_string(),
+            8 => "NOBITS".to_string(),
+            9 => "REL".to_string(),
+            11 => "DYNSYM".to_string(),
+            14 => "INIT_ARRAY".to_string(),
+            15 => "FINI_ARRAY".to_string(),
+            0x7000_0001 => "ARM_EXIDX".to_string(),
+            0x7000_0003 => "ARM_ATTRIBUTES".to_string(),
+            t => format!("{:#x}", t),
+        }
+    }
+
+    // "WAX" as readelf prints it
+    pub fn flag_string(&self) -> String {
+        let mut s = String::new();
+        for (bit, c) in [(SHF_WRITE, 'W'), (SHF_ALLOC, 'A'), (SHF_EXECINSTR, 'X')] {
+            if self.flags & bit != 0 {
+                s.push(c);
+            }
+        }
+        s
+    }
+
+    pub fn is_alloc(&self) -> bool {
+        self.flags & SHF_ALLOC != 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub value: u64,
+    pub size: u64,
+    // STT_*, in the low nibble of st_info
+    pub kind: u8,
+    // STB_*, in the high nibble
+    pub binding: u8,
+}
+
+impl Symbol {
+    pub fn is_func(&self) -> bool {
+        self.kind == STT_FUNC
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.binding == STB_WEAK
+    }
+
+    // Where a function starts, without the Thumb bit
+    pub fn addr(&self) -> u64 {
+        if self.is_func() {
+            self.value & !1
+        } else {
+            self.value
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfFile {
+    pub is_64: bool,
+    pub big_endian: bool,
+    pub machine: u16,
+    pub entry: u64,
+    pub segments: Vec<Segment>,
+    pub sections: Vec<Section>,
+    // The symbol table, without the null symbol and unnamed entries
+    pub symbols: Vec<Symbol>,
+}
+
+pub fn is_elf(data: &[u8]) -> bool {
+    data.starts_with(b"\x7fELF")
+}
+
+// Reads fields in the file's byte order, failing past the end of the data
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+    is_64: bool,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, off: u64) -> Result<[u8; N], String> {
+        let start = usize::try_from(off).map_err(|_| "offset out of range".to_string())?;
+        let mut b: [u8; N] = self
+            .data
+            .get(start..start.saturating_add(N))
+            .and_then(|s| s.try_into().ok())
+            .ok_or_else(|| format!("truncated at {:#x}", off))?;
+        if !self.big_endian {
+            b.reverse();
+        }
+        Ok(b)
+    }
+
+    fn u16(&self, off: u64) -> Result<u16, String> {
+        self.bytes::<2>(off).map(u16::from_be_bytes)
+    }
+
+    fn u32(&self, off: u64) -> Result<u32, String> {
+        self.bytes::<4>(off).map(u32::from_be_bytes)
+    }
+
+    fn u64(&self, off: u64) -> Result<u64, String> {
+        self.bytes::<8>(off).map(u64::from_be_bytes)
+    }
+
+    // An address or offset, whose size depends on the class
+    fn word(&self, off: u64) -> Result<u64, String> {
+        if self.is_64 {
+            self.u64(off)
+        } else {
+            self.u32(off).map(u64::from)
+        }
+    }
+
+    fn c_str(&self, off: u64) -> String {
+        let start = usize::try_from(off).unwrap_or(usize::MAX);
+        let bytes = self.data.get(start..).unwrap_or_default();
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).to_string()
+    }
+}
+
+impl ElfFile {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if !is_elf(data) {
+            return Err("missing ELF magic".to_string());
+        }
+        let is_64 = match data.get(4) {
+            Some(1) => false,
+            Some(2) => true,
+            _ => return Err("unknown ELF class".to_string()),
+        };
+        let big_endian = match data.get(5) {
+            Some(1) => false,
+            Some(2) => true,
+            _ => return Err("unknown byte order".to_string()),
+        };
+        let r = Reader {
+            data,
+            big_endian,
+            is_64,
+        };
+        // Offsets of the header fields past e_entry move with the word size
+        let w = if is_64 { 8 } else { 4 };
+        let machine = r.u16(0x12)?;
+        let entry = r.word(0x18)?;
+        let phoff = r.word(0x18 + w)?;
+        let shoff = r.word(0x18 + 2 * w)?;
+        let tail = 0x18 + 3 * w + 4;
+        let phentsize = u64::from(r.u16(tail + 2)?);
+        let phnum = u64::from(r.u16(tail + 4)?);
+        let shentsize = u64::from(r.u16(tail + 6)?);
+        let shnum = u64::from(r.u16(tail + 8)?);
+        let shstrndx = u64::from(r.u16(tail + 10)?);
+        // Keeps the offsets below from overflowing on garbage headers
+        let len = data.len() as u64;
+        if (phnum > 0 && phoff > len) || (shnum > 0 && shoff > len) {
+            return Err("header table past the end of the file".to_string());
+        }
+
+        let mut segments = vec![];
+        if phnum > 0 && phentsize < if is_64 { 56 } else { 32 } {
+            return Err(format!("program headers of {} bytes", phentsize));
+        }
+        for i in 0..phnum {
+            let ph = phoff + i * phentsize;
+            segments.push(if is_64 {
+                Segment {
+                    p_type: r.u32(ph)?,
+                    flags: r.u32(ph + 4)?,
+                    offset: r.u64(ph + 8)?,
+                    vaddr: r.u64(ph + 16)?,
+                    paddr: r.u64(ph + 24)?,
+                    filesz: r.u64(ph + 32)?,
+                    memsz: r.u64(ph + 40)?,
+                }
+            } else {
+                Segment {
+                    p_type: r.u32(ph)?,
+                    offset: r.word(ph + 4)?,
+                    vaddr: r.word(ph + 8)?,
+                    paddr: r.word(ph + 12)?,
+                    filesz: r.word(ph + 16)?,
+                    memsz: r.word(ph + 20)?,
+                    flags: r.u32(ph + 24)?,
+                }
+            });
+        }
+
+        // Stripped files may have no section headers at all
+        let mut headers = vec![];
+        if shnum > 0 && shentsize < if is_64 { 64 } else { 40 } {
+            return Err(format!("section headers of {} bytes", shentsize));
+        }
+        for i in 0..shnum {
+            let sh = shoff + i * shentsize;
+            let name = u64::from(r.u32(sh)?);
+            let sh_type = r.u32(sh + 4)?;
+            let flags = r.word(sh + 8)?;
+            let addr = r.word(sh + 8 + w)?;
+            let offset = r.word(sh + 8 + 2 * w)?;
+            let size = r.word(sh + 8 + 3 * w)?;
+            let link = r.u32(sh + 8 + 4 * w)?;
+            let entsize = r.word(sh + 16 + 5 * w)?;
+            headers.push((name, sh_type, flags, addr, offset, size, link, entsize));
+        }
+        let names_at = headers.get(shstrndx as usize).map(|h| h.4);
+        let sections = headers
+            .iter()
+            .map(
+                |&(name, sh_type, flags, addr, offset, size, _, _)| Section {
+                    name: names_at.map_or(String::new(), |base| r.c_str(base.saturating_add(name))),
+                    sh_type,
+                    flags,
+                    addr,
+                    offset,
+                    size,
+                },
+            )
+            .collect();
+        let mut symbols = vec![];
+        for &(_, _, _, _, offset, size, link, entsize) in
+            headers.iter().filter(|h| h.1 == SHT_SYMTAB)
+        {
+            let entsize = if entsize > 0 { entsize } else { 4 * w };
+            let names_at = headers.get(link as usize).map_or(0, |h| h.4);
+            // Entry 0 is the null symbol
+            for i in 1..size / entsize {
+                let sym = offset.saturating_add(i * entsize);
+                // 64-bit entries put st_info before the value and size
+                let (value, size, info) = if is_64 {
+                    (r.u64(sym + 8)?, r.u64(sym + 16)?, r.bytes::<1>(sym + 4)?[0])
+                } else {
+                    (
+                        r.u32(sym + 4)?.into(),
+                        r.u32(sym + 8)?.into(),
+                        r.bytes::<1>(sym + 12)?[0],
+                    )
+                };
+                let name = r.c_str(names_at.saturating_add(u64::from(r.u32(sym)?)));
+                if !name.is_empty() {
+                    symbols.push(Symbol {
+                        name,
+                        value,
+                        size,
+                        kind: info & 0xf,
+                        binding: info >> 4,
+                    });
+                }
+            }
+        }
+
+        Ok(Self {
+            is_64,
+            big_endian,
+            machine,
+            entry,
+            segments,
+            sections,
+            symbols,
+        })
+    }
+
+    pub fn machine_name(&self) -> String {
+        match self.machine {
+            3 => "x86".to_string(),
+            8 => "MIPS".to_string(),
+            20 => "PowerPC".to_string(),
+            EM_ARM => "ARM".to_string(),
+            62 => "x86-64".to_string(),
+            83 => "AVR".to_string(),
+            94 => "Xtensa".to_string(),
+            183 => "AArch64".to_string(),
+            243 => "RISC-V".to_string(),
+            m => format!("machine {}", m),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "ELF{} {}-endian {}",
+            if self.is_64 { 64 } else { 32 },
+            if self.big_endian { "big" } else { "little" },
+            self.machine_name()
+        )
+    }
+
+    // Loaded segments that take up memory, in file order
+    pub fn loads(&self) -> impl Iterator<Item = &Segment> {
+        self.segments.iter().filter(|s| s.is_load() && s.memsz > 0)
+    }
+
+    // The file bytes of a segment
+    pub fn segment_data<'d>(&self, data: &'d [u8], seg: &Segment) -> Option<&'d [u8]> {
+        let start = usize::try_from(seg.offset).ok()?;
+        let len = usize::try_from(seg.filesz).ok()?;
+        data.get(start..start.checked_add(len)?)
+    }
+
+    // Functions that start at `addr`, strong definitions before weak ones
+    pub fn functions_at(&self, addr: u64) -> Vec<&Symbol> {
+        let mut ret: Vec<&Symbol> = self
+            .symbols
+            .iter()
+            .filter(|s| s.is_func() && s.addr() == addr)
+            .collect();
+        ret.sort_by_key(|s| (s.binding != STB_GLOBAL, s.is_weak(), s.name.clone()));
+        ret
+    }
+
+    // A symbol by name, e.g. the vector table of a startup file
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| s.name == name)
+    }
+
+    // The segment at the lowest load address, which holds the vector table
+    pub fn first_segment(&self) -> Option<&Segment> {
+        self.loads()
+            .filter(|s| s.filesz > 0)
+            .min_by_key(|s| s.paddr)
+    }
+
+    // The GNU build-id note the linker writes with --build-id, in hex. Looks
+    // in note sections first and note segments for stripped files.
+    #[cfg(feature = "for_fuzzer")]
+    pub fn build_id(&self, data: &[u8]) -> Option<String> {
+        let r = Reader {
+            data,
+            big_endian: self.big_endian,
+            is_64: self.is_64,
+        };
+        let notes = self
+            .sections
+            .iter()
+            .filter(|s| s.sh_type == SHT_NOTE)
+            .map(|s| (s.offset, s.size))
+            .chain(
+                self.segments
+                    .iter()
+                    .filter(|s| s.p_type == PT_NOTE)
+                    .map(|s| (s.offset, s.filesz)),
+            );
+        for (start, size) in notes {
+            let end = start.saturating_add(size);
+            let mut off = start;
+            // namesz, descsz and type, then the name and the descriptor,
+            // each padded to 4 bytes
+            while off.saturating_add(12) <= end {
+                let (Ok(namesz), Ok(descsz), Ok(n_type)) =
+                    (r.u32(off), r.u32(off + 4), r.u32(off + 8))
+                else {
+                    break;
+                };
+                let name = off + 12;
+                let desc = name + u64::from(namesz).next_multiple_of(4);
+                let next = desc + u64::from(descsz).next_multiple_of(4);
+                if next > end {
+                    break;
+                }
+                if n_type == NT_GNU_BUILD_ID
+                    && descsz > 0
+                    && data.get(name as usize..(name + 4) as usize) == Some(b"GNU\0")
+                {
+                    let id = data.get(desc as usize..(desc + u64::from(descsz)) as usize)?;
+                    return Some(id.iter().map(|b| format!("{:02x}", b)).collect());
+                }
+                off = next;
+            }
+        }
+        None
+    }
+
+    // Flash and RAM regions that hold what the program headers load, and
+    // where execution starts. Flash gets everything stored in the image,
+    // RAM what is written at run time.
+    pub fn propose_map(&self, data: &[u8]) -> MapProposal {
+        let mut flash = vec![];
+        let mut ram = vec![];
+        let mut ram_exec = false;
+        for s in self.loads() {
+            let writable = s.flags & PF_W != 0;
+            // Initialised data is stored in flash and copied to RAM at boot
+            if s.filesz > 0 && (!writable || s.paddr != s.vaddr) {
+                flash.push((s.paddr, s.paddr.saturating_add(s.filesz)));
+            }
+            if writable {
+                ram.push((s.vaddr, s.vaddr.saturating_add(s.memsz)));
+                ram_exec |= s.flags & PF_X != 0;
+            }
+        }
+
+        // On Cortex-M the image starts with the initial SP
+        let initial_sp = (self.machine == EM_ARM)
+            .then(|| self.first_segment())
+            .flatten()
+            .and_then(|s| self.segment_data(data, s))
+            .and_then(|b| b.get(..4))
+            .map(|b| {
+                let w = [b[0], b[1], b[2], b[3]];
+                u64::from(if self.big_endian {
+                    u32::from_be_bytes(w)
+                } else {
+                    u32::from_le_bytes(w)
+                })
+            })
+            .filter(|sp| *sp != 0);
+
+        let flash = page_ranges(flash);
+        let mut ram = page_ranges(ram);
+        let mut notes = vec![];
+        if let Some(sp) = initial_sp {
+            let covered = ram.iter().any(|(s, e)| sp > *s && sp <= *e);
+            let below = ram
+                .iter()
+                .rposition(|(_, e)| *e < sp && sp - *e <= MAX_STACK_GAP);
+            let in_the_way = |end: u64| {
+                flash
+                    .iter()
+                    .chain(ram.iter())
+                    .any(|(s, _)| *s >= end && *s < sp)
+            };
+            match below {
+                _ if covered => {}
+                Some(i) if !in_the_way(ram[i].1) => ram[i].1 = align_up(sp),
+                _ => notes.push(format!(
+                    "Initial SP {:#x} is not next to any RAM, add the stack by hand",
+                    sp
+                )),
+            }
+        }
+
+        let mut regions = named_regions(
+            "flash",
+            flash,
+            Perms {
+                read: true,
+                write: false,
+                exec: true,
+            },
+        );
+        regions.extend(named_regions(
+            "ram",
+            ram,
+            Perms {
+                read: true,
+                write: true,
+                exec: ram_exec,
+            },
+        ));
+        if regions.is_empty() {
+            notes.push("The file has no loadable segments".to_string());
+        }
+        MapProposal {
+            regions,
+            entry: (self.entry != 0).then_some(self.entry),
+            initial_sp,
+            notes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposedRegion {
+    pub name: String,
+    pub start: u64,
+    pub size: u64,
+    pub perms: Perms,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapProposal {
+    pub regions: Vec<ProposedRegion>,
+    pub entry: Option<u64>,
+    pub initial_sp: Option<u64>,
+    // What could not be worked out from the headers
+    pub notes: Vec<String>,
+}
+
+// flash, or flash_1, flash_2, ... when the ranges do not touch
+fn named_regions(base: &str, ranges: Vec<(u64, u64)>, perms: Perms) -> Vec<ProposedRegion> {
+    let count = ranges.len();
+    ranges
+        .into_iter()
+        .enumerate()
+        .map(|(i, (start, end))| ProposedRegion {
+            name: if count == 1 {
+                base.to_string()
+            } else {
+                format!("{}_{}", base, i + 1)
+            },
+            start,
+            size: end - start,
+            perms,
+        })
+        .collect()
+}
+
+fn align_up(addr: u64) -> u64 {
+    addr.div_ceil(PAGE_SIZE).saturating_mul(PAGE_SIZE)
+}
+
+// Grow address ranges out to whole pages and join those that then touch
+fn page_ranges(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort();
+    let mut ret: Vec<(u64, u64)> = vec![];
+    for (start, end) in ranges {
+        let (start, end) = (start - start % PAGE_SIZE, align_up(end));
+        match ret.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => ret.push((start, end)),
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(w: &[u32]) -> Vec<u8> {
+        w.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    // A Cortex-M image: code in flash, .data loaded from flash into RAM and
+    // .bss after it, with a .shstrtab and a symbol table of 3 functions, two
+    // of them at the same address
+    fn firmware() -> Vec<u8> {
+        let mut elf = vec![0u8; 0x34];
+        elf[..6].copy_from_slice(b"\x7fELF\x01\x01");
+        elf[0x12..0x14].copy_from_slice(&EM_ARM.to_le_bytes());
+        elf[0x18..0x1c].copy_from_slice(&0x0800_0009u32.to_le_bytes());
+        elf[0x1c..0x20].copy_from_slice(&0x34u32.to_le_bytes());
+        elf[0x2a..0x2c].copy_from_slice(&32u16.to_le_bytes());
+        elf[0x2c..0x2e].copy_from_slice(&2u16.to_le_bytes());
+        elf[0x2e..0x30].copy_from_slice(&40u16.to_le_bytes());
+        elf[0x30..0x32].copy_from_slice(&4u16.to_le_bytes());
+        elf[0x32..0x34].copy_from_slice(&2u16.to_le_bytes());
+        // Program headers at 0x34, data at 0x74
+        elf.extend(words(&[
+            PT_LOAD,
+            0x74,
+            0x0800_0000,
+            0x0800_0000,
+            0x10,
+            0x10,
+            PF_R | PF_X,
+            4,
+        ]));
+        elf.extend(words(&[
+            PT_LOAD,
+            0x84,
+            0x2000_0000,
+            0x0800_0010,
+            4,
+            0x800,
+            PF_R | PF_W,
+            4,
+        ]));
+        elf.extend(words(&[0x2000_8000, 0x0800_0009, 0, 0, 0x1234]));
+        // Section names at 0x88, then symbol names, the symbols and the
+        // section headers
+        let names = b"\0.text\0.symtab\0.shstrtab\0.strtab\0\0";
+        elf.extend(names);
+        let strtab = b"\0Reset_Handler\0Default_Handler\0USART1_IRQHandler\0";
+        let strtab_at = elf.len() as u32;
+        elf.extend(strtab);
+        elf.resize(elf.len().next_multiple_of(4), 0);
+        let symtab_at = elf.len() as u32;
+        elf.extend([0; 16]);
+        for (name, value, info) in [
+            (1, 0x0800_0009, 0x12),
+            (15, 0x0800_000d, 0x12),
+            (31, 0x0800_000d, 0x22),
+        ] {
+            elf.extend(words(&[name, value, 4, info]));
+        }
+        let shoff = elf.len() as u32;
+        elf[0x20..0x24].copy_from_slice(&shoff.to_le_bytes());
+        elf.extend(words(&[1, 1, 6, 0x0800_0000, 0x74, 0x10, 0, 0, 4, 0]));
+        elf.extend(words(&[7, SHT_SYMTAB, 0, 0, symtab_at, 64, 3, 1, 4, 16]));
+        elf.extend(words(&[15, 3, 0, 0, 0x88, names.len() as u32, 0, 0, 1, 0]));
+        elf.extend(words(&[
+            25,
+            3,
+            0,
+            0,
+            strtab_at,
+            strtab.len() as u32,
+            0,
+            0,
+            1,
+            0,
+        ]));
+        elf
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = firmware();
+        let elf = ElfFile::parse(&data).unwrap();
+        assert_eq!(elf.describe(), "ELF32 little-endian ARM");
+        assert_eq!(elf.entry, 0x0800_0009);
+        assert_eq!(elf.loads().count(), 2);
+        assert_eq!(elf.segments[1].perms().to_string(), "rw-");
+        let names: Vec<&str> = elf.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec![".text", ".symtab", ".shstrtab", ".strtab"]);
+        assert_eq!(elf.sections[0].flag_string(), "AX");
+        assert_eq!(elf.symbols.len(), 3);
+        assert_eq!(elf.symbol("Reset_Handler").unwrap().addr(), 0x0800_0008);
+        let names: Vec<&str> = elf
+            .functions_at(0x0800_000c)
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Default_Handler", "USART1_IRQHandler"]);
+        assert!(elf.functions_at(0x0800_000c)[1].is_weak());
+        assert_eq!(
+            elf.segment_data(&data, elf.first_segment().unwrap())
+                .unwrap()
+                .len(),
+            0x10
+        );
+
+        assert!(ElfFile::parse(&data[..0x40]).is_err());
+        assert!(ElfFile::parse(b"\x7fELF\x03\x01").is_err());
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    #[test]
+    fn test_build_id() {
+        let data = firmware();
+        assert_eq!(ElfFile::parse(&data).unwrap().build_id(&data), None);
+
+        // A stripped file with the note in a PT_NOTE segment only
+        let mut elf = vec![0u8; 0x34];
+        elf[..6].copy_from_slice(b"\x7fELF\x01\x01");
+        elf[0x1c..0x20].copy_from_slice(&0x34u32.to_le_bytes());
+        elf[0x2a..0x2c].copy_from_slice(&32u16.to_le_bytes());
+        elf[0x2c..0x2e].copy_from_slice(&1u16.to_le_bytes());
+        elf.extend(words(&[PT_NOTE, 0x54, 0, 0, 0x28, 0x28, PF_R, 4]));
+        // An unrelated note before the build-id
+        elf.extend(words(&[4, 0, 1]));
+        elf.extend(b"ABC\0");
+        elf.extend(words(&[4, 5, NT_GNU_BUILD_ID]));
+        elf.extend(b"GNU\0");
+        elf.extend([0xde, 0xad, 0xbe, 0xef, 0x01, 0, 0, 0]);
+        let parsed = ElfFile::parse(&elf).unwrap();
+        assert_eq!(parsed.build_id(&elf).as_deref(), Some("deadbeef01"));
+        assert_eq!(parsed.build_id(&elf[..0x70]), None);
+    }
+
+    #[test]
+    fn test_propose_map() {
+        let data = firmware();
+        let proposal = ElfFile::parse(&data).unwrap().propose_map(&data);
+        let regions: Vec<(&str, u64, u64, String)> = proposal
+            .regions
+            .iter()
+            .map(|r| (r.name.as_str(), r.start, r.size, r.perms.to_string()))
+            .collect();
+        // RAM reaches up to the initial SP
+        assert_eq!(
+            regions,
+            vec![
+                ("flash", 0x0800_0000, 0x1000, "r-x".to_string()),
+                ("ram", 0x2000_0000, 0x8000, "rw-".to_string()),
+            ]
+        );
+        assert_eq!(proposal.entry, Some(0x0800_0009));
+        assert_eq!(proposal.initial_sp, Some(0x2000_8000));
+        assert!(proposal.notes.is_empty());
+
+        assert_eq!(
+            page_ranges(vec![(0x3000, 0x3004), (0x1000, 0x1800), (0x1800, 0x2001)]),
+            vec![(0x1000, 0x4000)]
+        );
+    }
+}
@@ -0,0 +1,66 @@
+// firmware/fingerprint.rs
+// What identifies a build of the firmware: the SHA-256 of the file and the
+// linker's build-id when it has one
+#![cfg(feature = "for_fuzzer")]
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::elf::{is_elf, ElfFile};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinaryFingerprint {
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<String>,
+    pub size: u64,
+}
+
+impl BinaryFingerprint {
+    pub fn of(data: &[u8]) -> Self {
+        let build_id = if is_elf(data) {
+            ElfFile::parse(data).ok().and_then(|elf| elf.build_id(data))
+        } else {
+            None
+        };
+        Self {
+            sha256: to_hex(&sha256(data)),
+            build_id,
+            size: data.len() as u64,
+        }
+    }
+
+    // Enough of the hash to tell builds apart in a list or a directory name
+    pub fn short(&self) -> &str {
+        &self.sha256[..self.sha256.len().min(12)]
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.build_id {
+            Some(id) => format!("sha256 {}, build-id {}", self.short(), id),
+            None => format!("sha256 {}, no build-id", self.short()),
+        }
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        let fp = BinaryFingerprint::of(b"abc");
+        assert_eq!(fp.short(), "ba7816bf8f01");
+        assert_eq!(fp.size, 3);
+        assert_eq!(fp.build_id, None);
+        assert_eq!(fp.describe(), "sha256 ba7816bf8f01, no build-id");
+        assert_ne!(BinaryFingerprint::of(b"abd"), fp);
+    }
+}
@@ -0,0 +1,422 @@
+// firmware/loader.rs
+// Firmware files as the memory they fill: ELF program headers, Intel HEX
+// and Motorola S-record images decoded into address-tagged segments, and
+// raw binaries placed at an address given by the user.
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::elf::{is_elf, ElfFile};
+use crate::config::validate::flash_base;
+
+// Gaps between segments are filled like erased flash
+const FILL: u8 = 0xff;
+// Larger flattened images are almost certainly segments far apart, e.g. a
+// HEX file that also initialises RAM
+const MAX_FLAT_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Elf,
+    IntelHex,
+    SRecord,
+    Raw,
+}
+
+impl ImageFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageFormat::Elf => "ELF",
+            ImageFormat::IntelHex => "Intel HEX",
+            ImageFormat::SRecord => "Motorola S-record",
+            ImageFormat::Raw => "raw binary",
+        }
+    }
+
+    // By content, the extension only decides for files that are empty or
+    // could be either
+    pub fn detect(path: &Path, data: &[u8]) -> Self {
+        if is_elf(data) {
+            return ImageFormat::Elf;
+        }
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        let first = data
+            .split(|b| *b == b'\n')
+            .map(|l| l.trim_ascii())
+            .find(|l| !l.is_empty());
+        let text = data
+            .iter()
+            .all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace());
+        match first {
+            Some([b':', ..]) if text => ImageFormat::IntelHex,
+            Some([b'S', d, ..]) if text && d.is_ascii_digit() => ImageFormat::SRecord,
+            None if ["hex", "ihex", "ihx"].contains(&ext.as_str()) => ImageFormat::IntelHex,
+            None if ["s19", "s28", "s37", "srec", "mot"].contains(&ext.as_str()) => {
+                ImageFormat::SRecord
+            }
+            _ => ImageFormat::Raw,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageSegment {
+    pub addr: u64,
+    pub data: Vec<u8>,
+}
+
+impl ImageSegment {
+    pub fn end(&self) -> u64 {
+        self.addr + self.data.len() as u64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirmwareImage {
+    pub format: ImageFormat,
+    // By address, contiguous data joined into one segment
+    pub segments: Vec<ImageSegment>,
+    // Start address from the file, if it has one
+    pub entry: Option<u64>,
+}
+
+impl FirmwareImage {
+    fn new(
+        format: ImageFormat,
+        mut chunks: Vec<ImageSegment>,
+        entry: Option<u64>,
+    ) -> Result<Self, String> {
+        chunks.retain(|c| !c.data.is_empty());
+        // Every end() below is known not to overflow from here on
+        if let Some(c) = chunks
+            .iter()
+            .find(|c| c.addr.checked_add(c.data.len() as u64).is_none())
+        {
+            return Err(format!(
+                "{} bytes at {:#x} run past the end of the address space",
+                c.data.len(),
+                c.addr
+            ));
+        }
+        chunks.sort_by_key(|c| c.addr);
+        let mut segments: Vec<ImageSegment> = vec![];
+        for c in chunks {
+            match segments.last_mut() {
+                // Later records win where they overlap, as when flashing them
+                Some(last) if c.addr <= last.end() => {
+                    let at = (c.addr - last.addr) as usize;
+                    let keep = last.data.len().max(at + c.data.len());
+                    last.data.resize(keep, FILL);
+                    last.data[at..at + c.data.len()].copy_from_slice(&c.data);
+                }
+                _ => segments.push(c),
+            }
+        }
+        Ok(Self {
+            format,
+            segments,
+            entry,
+        })
+    }
+
+    pub fn start(&self) -> Option<u64> {
+        self.segments.first().map(|s| s.addr)
+    }
+
+    pub fn end(&self) -> Option<u64> {
+        self.segments.last().map(|s| s.end())
+    }
+
+    pub fn describe(&self) -> String {
+        match (self.start(), self.end()) {
+            (Some(start), Some(end)) => format!(
+                "{}, {} segment(s) in {:#x}-{:#x}",
+                self.format.name(),
+                self.segments.len(),
+                start,
+                end
+            ),
+            _ => format!("{}, empty", self.format.name()),
+        }
+    }
+
+    // The image as one blob loaded at `base`, which is how the emulator
+    // takes it
+    pub fn flatten(&self, base: u64) -> Result<Vec<u8>, String> {
+        let (Some(start), Some(end)) = (self.start(), self.end()) else {
+            return Err("The image has no data".to_string());
+        };
+        if start < base {
+            return Err(format!(
+                "The image starts at {:#x}, below the load address {:#x}",
+                start, base
+            ));
+        }
+        if end - base > MAX_FLAT_SIZE {
+            return Err(format!(
+                "The image spans {:#x}-{:#x}, too far apart to load as one from {:#x}",
+                start, end, base
+            ));
+        }
+        let mut out = vec![FILL; (end - base) as usize];
+        for s in &self.segments {
+            let at = (s.addr - base) as usize;
+            out[at..at + s.data.len()].copy_from_slice(&s.data);
+        }
+        Ok(out)
+    }
+}
+
+// What the emulator gets: the image as one blob from the flash base of
+// `config`, or from the image's lowest address when the config maps no flash
+pub fn emulator_contents(image: &FirmwareImage, config: &Value) -> Result<Vec<u8>, String> {
+    let base = flash_base(config).or(image.start()).unwrap_or(0);
+    image.flatten(base)
+}
+
+// The file's contents as the emulator gets them. Everything that checks the
+// binary against the flash size goes through here, so the length it sees is
+// the one that is loaded.
+pub fn emulator_image(
+    path: &Path,
+    data: &[u8],
+    load_address: Option<u64>,
+    config: &Value,
+) -> Result<Vec<u8>, String> {
+    emulator_contents(&decode(path, data, load_address)?, config)
+}
+
+// Raw binaries carry no addresses, they are placed at `load_address`
+
+pub fn decode(
+    path: &Path,
+    data: &[u8],
+    load_address: Option<u64>,
+) -> Result<FirmwareImage, String> {
+    let format = ImageFormat::detect(path, data);
+    let bad = |e: String| {
+        format!(
+            "{} is not a valid {} file: {}",
+            path.display(),
+            format.name(),
+            e
+        )
+    };
+    match format {
+        ImageFormat::Elf => decode_elf(data).map_err(bad),
+        ImageFormat::IntelHex => decode_ihex(&String::from_utf8_lossy(data)).map_err(bad),
+        ImageFormat::SRecord => decode_srec(&String::from_utf8_lossy(data)).map_err(bad),
+        ImageFormat::Raw => {
+            let addr = load_address.ok_or_else(|| {
+                format!(
+                    "{} is a raw binary and needs a load address",
+                    path.display()
+                )
+            })?;
+            FirmwareImage::new(
+                format,
+                vec![ImageSegment {
+                    addr,
+                    data: data.to_vec(),
+                }],
+                None,
+            )
+            .map_err(|e| format!("{} cannot be loaded at {:#x}: {}", path.display(), addr, e))
+        }
+    }
+}
+
+// What the program headers put in memory, at their load addresses
+fn decode_elf(data: &[u8]) -> Result<FirmwareImage, String> {
+    let elf = ElfFile::parse(data)?;
+    let mut chunks = vec![];
+    for s in elf.loads().filter(|s| s.filesz > 0) {
+        let bytes = elf
+            .segment_data(data, s)
+            .ok_or("segment runs past the end of the file")?;
+        chunks.push(ImageSegment {
+            addr: s.paddr,
+            data: bytes.to_vec(),
+        });
+    }
+    FirmwareImage::new(
+        ImageFormat::Elf,
+        chunks,
+        (elf.entry != 0).then_some(elf.entry),
+    )
+}
+
+// The bytes of one text record, with its checksum checked by `check`
+fn record_bytes(hex: &str, line: usize, check: impl Fn(&[u8]) -> bool) -> Result<Vec<u8>, String> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("line {}: not a record", line));
+    }
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect();
+    if !check(&bytes) {
+        return Err(format!("line {}: bad checksum", line));
+    }
+    Ok(bytes)
+}
+
+fn be_number(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |n, b| n << 8 | u64::from(*b))
+}
+
+fn decode_ihex(text: &str) -> Result<FirmwareImage, String> {
+    let mut chunks = vec![];
+    let mut entry = None;
+    // Upper address bits from record types 02 and 04
+    let mut base = 0u64;
+    let mut ended = false;
+    for (i, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        if line.is_empty() {
+            continue;
+        }
+        if ended {
+            return Err(format!("line {}: data after the end-of-file record", i));
+        }
+        let hex = line
+            .strip_prefix(':')
+            .ok_or_else(|| format!("line {}: records start with ':'", i))?;
+        let bytes = record_bytes(hex, i, |b| {
+            b.iter().fold(0u8, |s, b| s.wrapping_add(*b)) == 0
+        })?;
+        if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+            return Err(format!("line {}: wrong record length", i));
+        }
+        let offset = be_number(&bytes[1..3]);
+        let payload = &bytes[4..bytes.len() - 1];
+        match bytes[3] {
+            0x00 => chunks.push(ImageSegment {
+                addr: base + offset,
+                data: payload.to_vec(),
+            }),
+            0x01 => ended = true,
+            0x02 if payload.len() == 2 => base = be_number(payload) << 4,
+            0x04 if payload.len() == 2 => base = be_number(payload) << 16,
+            // CS:IP for 8086 images, EIP for everything since
+            0x03 if payload.len() == 4 => {
+                entry = Some((be_number(&payload[..2]) << 4) + be_number(&payload[2..]))
+            }
+            0x05 if payload.len() == 4 => entry = Some(be_number(payload)),
+            t => return Err(format!("line {}: unsupported record type {:02x}", i, t)),
+        }
+    }
+    if !ended {
+        return Err("missing the end-of-file record".to_string());
+    }
+    FirmwareImage::new(ImageFormat::IntelHex, chunks, entry)
+}
+
+fn decode_srec(text: &str) -> Result<FirmwareImage, String> {
+    let mut chunks = vec![];
+    let mut entry = None;
+    for (i, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        if line.is_empty() {
+            continue;
+        }
+        let (kind, hex) = match line.as_bytes() {
+            [b'S', k, ..] if k.is_ascii_digit() => (k - b'0', &line[2..]),
+            _ => return Err(format!("line {}: records start with S and a digit", i)),
+        };
+        let bytes = record_bytes(hex, i, |b| {
+            b.iter().fold(0u8, |s, b| s.wrapping_add(*b)) == 0xff
+        })?;
+        if bytes.is_empty() || bytes.len() != bytes[0] as usize + 1 {
+            return Err(format!("line {}: wrong record length", i));
+        }
+        let addr_len = match kind {
+            0 | 1 | 5 | 9 => 2,
+            2 | 6 | 8 => 3,
+            3 | 7 => 4,
+            _ => return Err(format!("line {}: unsupported record type S{}", i, kind)),
+        };
+        if bytes.len() < addr_len + 2 {
+            return Err(format!("line {}: record too short", i));
+        }
+        let addr = be_number(&bytes[1..1 + addr_len]);
+        let payload = &bytes[1 + addr_len..bytes.len() - 1];
+        match kind {
+            1..=3 => chunks.push(ImageSegment {
+                addr,
+                data: payload.to_vec(),
+            }),
+            7..=9 => entry = Some(addr),
+            // Header and record counts
+            _ => {}
+        }
+    }
+    FirmwareImage::new(ImageFormat::SRecord, chunks, entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_ihex() {
+        let hex = ":020000040800F2\n\
+                   :04000000008000205C\n\
+                   :0400040009000008E7\n\
+                   :04001000AABBCCDDDE\n\
+                   :0400000508000009E6\n\
+                   :00000001FF\n";
+        let path = Path::new("fw.hex");
+        let image = decode(path, hex.as_bytes(), None).unwrap();
+        assert_eq!(image.format, ImageFormat::IntelHex);
+        assert_eq!(image.entry, Some(0x0800_0009));
+        assert_eq!(image.segments.len(), 2);
+        assert_eq!(image.start(), Some(0x0800_0000));
+        let flat = image.flatten(0x0800_0000).unwrap();
+        assert_eq!(&flat[..8], &[0, 0x80, 0, 0x20, 9, 0, 0, 8]);
+        assert_eq!(&flat[8..16], &[FILL; 8]);
+        assert_eq!(&flat[16..], &[0xaa, 0xbb, 0xcc, 0xdd]);
+        assert!(image.flatten(0x0800_0004).is_err());
+
+        let broken = hex.replace(":0400040009000008E7", ":0400040009000008E8");
+        let err = decode(path, broken.as_bytes(), None).unwrap_err();
+        assert!(err.contains("line 3: bad checksum"), "{}", err);
+        assert!(decode(path, b":04000000008000205C\n", None).is_err());
+    }
+
+    #[test]
+    fn test_decode_srec() {
+        let srec = "S00600004844521B\n\
+                    S30908000000008000204E\n\
+                    S3090800000409000008D9\n\
+                    S70508000009E9\n";
+        let image = decode(Path::new("fw.s37"), srec.as_bytes(), None).unwrap();
+        assert_eq!(image.format, ImageFormat::SRecord);
+        assert_eq!(image.entry, Some(0x0800_0009));
+        assert_eq!(image.segments.len(), 1);
+        assert_eq!(
+            image.flatten(0x0800_0000).unwrap(),
+            vec![0, 0x80, 0, 0x20, 9, 0, 0, 8]
+        );
+    }
+
+    #[test]
+    fn test_decode_raw() {
+        let path = Path::new("fw.bin");
+        let data = [0u8, 0x80, 0, 0x20, 0x3a];
+        assert_eq!(ImageFormat::detect(path, &data), ImageFormat::Raw);
+        assert!(decode(path, &data, None).is_err());
+        let image = decode(path, &data, Some(0x0800_1000)).unwrap();
+        let flat = image.flatten(0x0800_0000).unwrap();
+        assert_eq!(flat.len(), 0x1005);
+        assert_eq!(&flat[0x1000..], &data);
+        // Raw images near the top of the address space do not wrap around
+        assert!(decode(path, &[0; 16], Some(0xffff_ffff_ffff_fffb)).is_err());
+        assert!(decode(path, &[0; 4], Some(0xffff_ffff_ffff_fffb)).is_ok());
+        assert_eq!(
+            ImageFormat::detect(Path::new("e.s19"), b""),
+            ImageFormat::SRecord
+        );
+    }
+}
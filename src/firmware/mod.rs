@@ -0,0 +1,5 @@
+// firmware/mod.rs
+pub mod elf;
+pub mod fingerprint;
+pub mod loader;
+pub mod vector_table;
@@ -0,0 +1,422 @@
+// firmware/vector_table.rs
+use std::path::Path;
+
+use super::elf::{is_elf, ElfFile};
+use super::loader::decode;
+
+// Exception number of IRQ 0, the first 16 slots are the core's own
+pub const FIRST_IRQ: usize = 16;
+// 16 core exceptions and up to 240 external interrupts
+const MAX_ENTRIES: usize = 256;
+// Core exceptions by number, empty for the reserved slots
+const EXCEPTIONS: [&str; FIRST_IRQ] = [
+    "Initial SP",
+    "Reset",
+    "NMI",
+    "HardFault",
+    "MemManage",
+    "BusFault",
+    "UsageFault",
+    "SecureFault",
+    "",
+    "",
+    "",
+    "SVCall",
+    "DebugMonitor",
+    "",
+    "PendSV",
+    "SysTick",
+];
+// What startup files call the table, whose size then gives its length
+const TABLE_SYMBOLS: &[&str] = &[
+    "g_pfnVectors",
+    "__isr_vector",
+    "__Vectors",
+    "__vector_table",
+    "_vectors",
+    "vector_table",
+];
+const TABLE_SECTIONS: &[&str] = &[".isr_vector", ".vectors", ".vector_table", ".intvecs"];
+// Catch-all handlers of the usual startup files
+const DEFAULT_NAMES: &[&str] = &["default", "dummy", "unused"];
+
+pub fn exception_name(exception: usize) -> String {
+    match EXCEPTIONS.get(exception) {
+        Some(&"") => "Reserved".to_string(),
+        Some(name) => name.to_string(),
+        None => format!("IRQ {}", exception - FIRST_IRQ),
+    }
+}
+
+// The Cortex-M vector table at the start of a firmware image: the initial
+// stack pointer followed by one handler address per exception
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorTable {
+    // Indexed by exception number, entries[0] is the initial SP
+    pub entries: Vec<u32>,
+}
+
+impl VectorTable {
+    pub fn parse(image: &[u8]) -> Result<Self, String> {
+        let entries: Vec<u32> = image
+            .chunks_exact(4)
+            .take(MAX_ENTRIES)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+        if entries.len() < 2 {
+            return Err("Image is too short to hold a vector table".to_string());
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn initial_sp(&self) -> u32 {
+        self.entries[0]
+    }
+
+    pub fn reset(&self) -> u32 {
+        self.entries[1]
+    }
+
+    // The slot of an external interrupt, None past the end of the image
+    pub fn irq_slot(&self, irq: u64) -> Option<u32> {
+        let idx = usize::try_from(irq).ok()?.checked_add(FIRST_IRQ)?;
+        self.entries.get(idx).copied()
+    }
+
+    // How many entries are the table and not the code after it. ELF files
+    // say so with the size of the table symbol or section. Otherwise the
+    // table ends at the first word that is not empty or a Thumb address
+    // near the reset handler.
+    pub fn table_len(&self, elf: Option<&ElfFile>) -> usize {
+        let sized = elf.and_then(|elf| {
+            TABLE_SYMBOLS
+                .iter()
+                .find_map(|name| elf.symbol(name).map(|s| s.size))
+                .filter(|size| *size > 0)
+                .or_else(|| {
+                    elf.sections
+                        .iter()
+                        .find(|s| TABLE_SECTIONS.contains(&s.name.as_str()))
+                        .map(|s| s.size)
+                })
+        });
+        if let Some(size) = sized {
+            return (size / 4).min(self.entries.len() as u64) as usize;
+        }
+        let region = self.reset() & 0xff00_0000;
+        let len = self
+            .entries
+            .iter()
+            .skip(1)
+            .position(|&h| h != 0 && (h & 1 == 0 || h & 0xff00_0000 != region))
+            .map_or(self.entries.len(), |i| i + 1);
+        // Unused slots at the end are padding, the core exceptions stay
+        let used = self.entries[..len]
+            .iter()
+            .rposition(|&h| h != 0)
+            .map_or(0, |i| i + 1);
+        used.max(FIRST_IRQ).min(self.entries.len())
+    }
+
+    // Each exception and interrupt with its handler, named from the ELF
+    // symbols when there are any
+    pub fn handlers(&self, elf: Option<&ElfFile>) -> Vec<VectorEntry> {
+        let slots = &self.entries[1..self.table_len(elf).max(2)];
+        slots
+            .iter()
+            .enumerate()
+            .map(|(i, &handler)| {
+                let addr = u64::from(handler & !1);
+                let functions = elf.map_or(vec![], |elf| elf.functions_at(addr));
+                let symbol = functions.first().map(|s| s.name.clone());
+                let shared = slots
+                    .iter()
+                    .filter(|&&h| h != 0 && h & !1 == handler & !1)
+                    .count()
+                    .saturating_sub(1);
+                let looks_default = symbol.as_ref().is_some_and(|name| {
+                    let name = name.to_ascii_lowercase();
+                    DEFAULT_NAMES.iter().any(|d| name.contains(d))
+                });
+                let kind = if handler == 0 {
+                    HandlerKind::Empty
+                } else if looks_default {
+                    HandlerKind::Default
+                } else if !functions.is_empty() && functions.iter().all(|s| s.is_weak()) {
+                    HandlerKind::Weak
+                } else if shared >= 2 {
+                    // Three or more vectors on one handler is a catch-all
+                    HandlerKind::Default
+                } else {
+                    HandlerKind::Defined
+                };
+                VectorEntry {
+                    exception: i + 1,
+                    handler,
+                    symbol,
+                    kind,
+                    shared,
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandlerKind {
+    // A reserved or unused slot
+    Empty,
+    // The firmware's own handler
+    Defined,
+    // Only weak symbols start there, nothing overrode the startup file
+    Weak,
+    // A catch-all, named like one or shared by several vectors
+    Default,
+}
+
+impl HandlerKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            HandlerKind::Empty => "empty",
+            HandlerKind::Defined => "",
+            HandlerKind::Weak => "weak",
+            HandlerKind::Default => "default",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorEntry {
+    pub exception: usize,
+    // As stored, with the Thumb bit
+    pub handler: u32,
+    pub symbol: Option<String>,
+    pub kind: HandlerKind,
+    // How many other vectors have the same handler
+    pub shared: usize,
+}
+
+impl VectorEntry {
+    pub fn name(&self) -> String {
+        exception_name(self.exception)
+    }
+
+    pub fn irq(&self) -> Option<u64> {
+        self.exception.checked_sub(FIRST_IRQ).map(|n| n as u64)
+    }
+
+    pub fn addr(&self) -> u64 {
+        u64::from(self.handler & !1)
+    }
+
+    // Interrupts worth raising: those the firmware handles itself
+    pub fn is_interrupt_handler(&self) -> bool {
+        self.irq().is_some() && self.kind == HandlerKind::Defined
+    }
+}
+
+// The vector table of a binary and what its entries point to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorReport {
+    pub table: VectorTable,
+    pub entries: Vec<VectorEntry>,
+    // Handlers are named, the binary is an ELF file with symbols
+    pub has_symbols: bool,
+}
+
+impl VectorReport {
+    // IRQ numbers and handler addresses for an interrupts list
+    pub fn interrupt_handlers(&self) -> Vec<(u64, u64)> {
+        self.entries
+            .iter()
+            .filter(|e| e.is_interrupt_handler())
+            .filter_map(|e| Some((e.irq()?, e.addr())))
+            .collect()
+    }
+
+    pub fn summary(&self) -> String {
+        let count = |kind: HandlerKind| {
+            self.entries
+                .iter()
+                .filter(|e| e.irq().is_some() && e.kind == kind)
+                .count()
+        };
+        format!(
+            "{} IRQ slots: {} handled, {} weak, {} default, {} empty{}",
+            self.entries.iter().filter(|e| e.irq().is_some()).count(),
+            count(HandlerKind::Defined),
+            count(HandlerKind::Weak),
+            count(HandlerKind::Default),
+            count(HandlerKind::Empty),
+            if self.has_symbols { "" } else { ", no symbols" }
+        )
+    }
+}
+
+// The bytes a firmware file puts at its lowest load address. ELF files are
+// reduced to their first loaded segment, HEX and S-record images to their
+// first contiguous run of data, and raw images are taken whole.
+pub fn load_image(path: &Path) -> Result<Vec<u8>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    image_start(path, &data)
+}
+
+fn image_start(path: &Path, data: &[u8]) -> Result<Vec<u8>, String> {
+    if is_elf(data) {
+        return elf_first_segment(data)
+            .map_err(|e| format!("{} is not a usable ELF file: {}", path.display(), e));
+    }
+    // Where a raw image goes does not matter for its first bytes
+    let image = decode(path, data, Some(0))?;
+    image
+        .segments
+        .into_iter()
+        .next()
+        .map(|s| s.data)
+        .ok_or_else(|| format!("{} holds no data", path.display()))
+}
+
+pub fn read_vector_table(path: &Path) -> Result<VectorTable, String> {
+    VectorTable::parse(&load_image(path)?)
+}
+
+pub fn analyze_vectors(path: &Path) -> Result<VectorReport, String> {
+    let data = std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let table = VectorTable::parse(&image_start(path, &data)?)?;
+    // The table already came out of the file, so it parses
+    let elf = is_elf(&data).then(|| ElfFile::parse(&data).ok()).flatten();
+    Ok(VectorReport {
+        entries: table.handlers(elf.as_ref()),
+        has_symbols: elf.as_ref().is_some_and(|e| !e.symbols.is_empty()),
+        table,
+    })
+}
+
+fn elf_first_segment(data: &[u8]) -> Result<Vec<u8>, String> {
+    let elf = ElfFile::parse(data)?;
+    let first = elf.first_segment().ok_or("no loadable segment")?;
+    elf.segment_data(data, first)
+        .map(|s| s.to_vec())
+        .ok_or_else(|| "segment runs past the end of the file".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firmware::elf::{Symbol, PT_LOAD};
+
+    fn words(w: &[u32]) -> Vec<u8> {
+        w.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_vector_table() {
+        let mut table = vec![0x2000_8000, 0x0800_0101];
+        table.extend([0; 14]);
+        table.extend([0x0800_0201, 0]);
+        let vt = VectorTable::parse(&words(&table)).unwrap();
+        assert_eq!((vt.initial_sp(), vt.reset()), (0x2000_8000, 0x0800_0101));
+        assert_eq!(vt.irq_slot(0), Some(0x0800_0201));
+        assert_eq!(vt.irq_slot(1), Some(0));
+        assert_eq!(vt.irq_slot(2), None);
+        assert!(VectorTable::parse(&[0; 6]).is_err());
+    }
+
+    #[test]
+    fn test_handlers() {
+        // Reset, NMI and HardFault, then IRQ 0 with its own handler, IRQs 1-3
+        // on the default handler and IRQ 4 on a weak one, then code
+        let mut table = vec![0x2000_8000, 0x0800_0101, 0x0800_0201, 0x0800_0201];
+        table.extend([0; 12]);
+        table.extend([0x0800_0301, 0x0800_0201, 0x0800_0201, 0, 0x0800_0401]);
+        table.extend([0, 0, 0x4770_b580]);
+        let vt = VectorTable::parse(&words(&table)).unwrap();
+        assert_eq!(vt.table_len(None), 21);
+
+        // Without symbols the shared handler gives the default away
+        let kinds: Vec<HandlerKind> = vt.handlers(None).iter().map(|e| e.kind).collect();
+        assert_eq!(kinds[0], HandlerKind::Defined);
+        assert_eq!(kinds[1], HandlerKind::Default);
+        assert_eq!(
+            kinds[15..],
+            [
+                HandlerKind::Defined,
+                HandlerKind::Default,
+                HandlerKind::Default,
+                HandlerKind::Empty,
+                HandlerKind::Defined,
+            ]
+        );
+
+        let func = |name: &str, value: u64, binding: u8| Symbol {
+            name: name.to_string(),
+            value,
+            size: 4,
+            kind: 2,
+            binding,
+        };
+        let mut elf = ElfFile {
+            is_64: false,
+            big_endian: false,
+            machine: 40,
+            entry: 0x0800_0101,
+            segments: vec![],
+            sections: vec![],
+            symbols: vec![
+                func("Reset_Handler", 0x0800_0101, 1),
+                func("Default_Handler", 0x0800_0201, 1),
+                func("USART1_IRQHandler", 0x0800_0301, 1),
+                func("TIM2_IRQHandler", 0x0800_0401, 2),
+            ],
+        };
+        let entries = vt.handlers(Some(&elf));
+        assert_eq!(entries[0].name(), "Reset");
+        assert_eq!(entries[2].name(), "HardFault");
+        assert_eq!(entries[7].name(), "Reserved");
+        assert_eq!(entries[19].name(), "IRQ 4");
+        assert_eq!(entries[15].symbol.as_deref(), Some("USART1_IRQHandler"));
+        assert_eq!(entries[16].kind, HandlerKind::Default);
+        assert_eq!(entries[16].shared, 3);
+        assert_eq!(entries[19].kind, HandlerKind::Weak);
+        let report = VectorReport {
+            table: vt.clone(),
+            entries,
+            has_symbols: true,
+        };
+        assert_eq!(report.interrupt_handlers(), vec![(0, 0x0800_0300)]);
+        assert_eq!(
+            report.summary(),
+            "5 IRQ slots: 1 handled, 1 weak, 2 default, 1 empty"
+        );
+
+        // The size of the table symbol wins over the guess
+        elf.symbols.push(Symbol {
+            name: "g_pfnVectors".to_string(),
+            value: 0x0800_0000,
+            size: 18 * 4,
+            kind: 1,
+            binding: 1,
+        });
+        assert_eq!(vt.table_len(Some(&elf)), 18);
+    }
+
+    #[test]
+    fn test_elf_first_segment() {
+        // ELF header, two program headers, then the segments
+        let mut elf = vec![0u8; 0x34];
+        elf[..6].copy_from_slice(b"\x7fELF\x01\x01");
+        elf[0x1c..0x20].copy_from_slice(&0x34u32.to_le_bytes());
+        elf[0x2a..0x2c].copy_from_slice(&32u16.to_le_bytes());
+        elf[0x2c..0x2e].copy_from_slice(&2u16.to_le_bytes());
+        for (offset, paddr) in [(0x74u32, 0x2000_0000u32), (0x78, 0x0800_0000)] {
+            elf.extend(words(&[PT_LOAD, offset, paddr, paddr, 4, 4, 5, 4]));
+        }
+        elf.extend(words(&[0xdead, 0x2000_1000]));
+        assert_eq!(
+            elf_first_segment(&elf).unwrap(),
+            0x2000_1000u32.to_le_bytes()
+        );
+        elf[4] = 2;
+        assert!(elf_first_segment(&elf).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+// grammar/generate.rs
+// Random inputs from a grammar, to see what the fuzzer will start from
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::rules::{min_costs, production_cost, Grammar, Symbol};
+use crate::campaign::persist::unix_time;
+
+// Past this depth only the shortest way to finish is taken
+pub const MAX_DEPTH: usize = 12;
+// Inputs are cut off here, wide grammars grow fast even when shallow
+const MAX_LEN: usize = 64 * 1024;
+
+// xorshift64*, plenty for picking productions
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(1, |d| d.as_nanos() as u64);
+        Self::new(nanos)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) % n.max(1) as u64) as usize
+    }
+}
+
+pub fn generate_samples(
+    grammar: &Grammar,
+    count: usize,
+    rng: &mut Rng,
+) -> Result<Vec<String>, String> {
+    let start = grammar.start().ok_or("The grammar has no rules")?;
+    let parsed = grammar.parsed();
+    let costs = min_costs(&parsed);
+    if !costs.contains_key(start) {
+        return Err(format!(
+            "{} never produces a finite input, fix the grammar first",
+            start
+        ));
+    }
+    let gen = Generator {
+        parsed: &parsed,
+        costs: &costs,
+    };
+    Ok((0..count)
+        .map(|_| {
+            let mut out = String::new();
+            gen.expand(start, 0, rng, &mut out);
+            out
+        })
+        .collect())
+}
+
+struct Generator<'a, 'g> {
+    parsed: &'a HashMap<&'g str, Vec<Vec<Symbol>>>,
+    costs: &'a HashMap<&'g str, usize>,
+}
+
+impl Generator<'_, '_> {
+    fn expand(&self, nt: &str, depth: usize, rng: &mut Rng, out: &mut String) {
+        // Only non-terminals that finish get here, and those all have rules
+        let Some(prods) = self.parsed.get(nt) else {
+            return;
+        };
+        let finishing: Vec<&Vec<Symbol>> = prods
+            .iter()
+            .filter(|p| production_cost(p, self.costs).is_some())
+            .collect();
+        let prod = if depth < MAX_DEPTH {
+            finishing[rng.below(finishing.len())]
+        } else {
+            finishing
+                .iter()
+                .min_by_key(|p| production_cost(p, self.costs))
+                .unwrap()
+        };
+        for s in prod {
+            if out.len() >= MAX_LEN {
+                return;
+            }
+            match s {
+                Symbol::Text(t) => out.push_str(t),
+                Symbol::NonTerminal(n) => self.expand(n, depth + 1, rng, out),
+            }
+        }
+    }
+}
+
+// Write samples into the corpus directory, one file each
+pub fn export_samples(samples: &[String], corpus_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    fs::create_dir_all(corpus_dir)
+        .map_err(|e| format!("Failed to create {}: {}", corpus_dir.display(), e))?;
+    let stamp = unix_time();
+    samples
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let path = corpus_dir.join(format!("grammar-{}-{:04}", stamp, i));
+            fs::write(&path, s)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_samples() {
+        let grammar = Grammar::from_json(
+            r#"[["START", "{EXPR}"], ["EXPR", "({EXPR}+{EXPR})"], ["EXPR", "{NUM}"], ["NUM", "1"], ["NUM", "2"]]"#,
+        )
+        .unwrap();
+        let mut rng = Rng::new(42);
+        let samples = generate_samples(&grammar, 20, &mut rng).unwrap();
+        assert_eq!(samples.len(), 20);
+        for s in &samples {
+            assert!(s.chars().all(|c| "()+12".contains(c)), "{}", s);
+            assert_eq!(s.matches('(').count(), s.matches(')').count());
+        }
+        assert!(samples.iter().any(|s| s.contains('+')));
+
+        let stuck = Grammar::from_json(r#"[["START", "a{START}"]]"#).unwrap();
+        assert!(generate_samples(&stuck, 1, &mut rng).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let written = export_samples(&samples[..2], &dir.path().join("corpus")).unwrap();
+        assert_eq!(fs::read_to_string(&written[1]).unwrap(), samples[1]);
+    }
+}
@@ -0,0 +1,1128 @@
+// grammar/import.rs
+// ABNF (RFC 5234) and EBNF (ISO 14977 or the W3C `::=` style) into
+// grammar.json rules. Those rules are only alternatives of text and
+// references, so options, groups and repetitions become helper
+// non-terminals named after their rule, and anything without an
+// equivalent is left out and reported.
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::rules::{escape_text, Grammar, Production};
+
+// Wider character ranges keep only their first values
+const MAX_RANGE: u32 = 256;
+// Bounded repetitions with more counts than this become unbounded
+const MAX_BOUNDED: usize = 16;
+
+// RFC 5234 appendix B, added when a grammar uses them without defining them
+const ABNF_CORE_RULES: &str = r#"
+ALPHA  = %x41-5A / %x61-7A
+BIT    = "0" / "1"
+CHAR   = %x01-7F
+CR     = %x0D
+CRLF   = CR LF
+CTL    = %x00-1F / %x7F
+DIGIT  = %x30-39
+DQUOTE = %x22
+HEXDIG = DIGIT / "A" / "B" / "C" / "D" / "E" / "F"
+HTAB   = %x09
+LF     = %x0A
+LWSP   = *(WSP / CRLF WSP)
+OCTET  = %x00-FF
+SP     = %x20
+VCHAR  = %x21-7E
+WSP    = SP / HTAB
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrammarSyntax {
+    Abnf,
+    Ebnf,
+}
+
+impl GrammarSyntax {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GrammarSyntax::Abnf => "ABNF",
+            GrammarSyntax::Ebnf => "EBNF",
+        }
+    }
+
+    // By extension, otherwise by what the text looks like
+    pub fn detect(path: &Path, text: &str) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("abnf") => GrammarSyntax::Abnf,
+            Some("ebnf" | "bnf") => GrammarSyntax::Ebnf,
+            _ => {
+                let ebnf = text.contains("::=")
+                    || text.contains("(*")
+                    || text.contains('|')
+                    || text.lines().any(|l| l.trim_end().ends_with(';'));
+                if ebnf {
+                    GrammarSyntax::Ebnf
+                } else {
+                    GrammarSyntax::Abnf
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportReport {
+    pub syntax: GrammarSyntax,
+    pub grammar: Grammar,
+    // Constructs that were left out or approximated
+    pub notes: Vec<String>,
+}
+
+pub fn import_grammar(path: &Path) -> Result<ImportReport, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    import_str(&text, GrammarSyntax::detect(path, &text))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn import_str(text: &str, syntax: GrammarSyntax) -> Result<ImportReport, String> {
+    let mut notes = Notes::default();
+    let mut defs = match syntax {
+        GrammarSyntax::Abnf => parse_abnf(text, &mut notes)?,
+        GrammarSyntax::Ebnf => parse_ebnf(text, &mut notes)?,
+    };
+    if defs.is_empty() {
+        return Err(format!("No {} rules found", syntax.name()));
+    }
+    let case_insensitive = syntax == GrammarSyntax::Abnf;
+    if case_insensitive {
+        add_core_rules(&mut defs, &mut notes);
+    }
+    let grammar = lower(&defs, case_insensitive, &mut notes);
+    Ok(ImportReport {
+        syntax,
+        grammar,
+        notes: notes.0,
+    })
+}
+
+#[derive(Debug, Default)]
+struct Notes(Vec<String>);
+
+impl Notes {
+    fn add(&mut self, note: String) {
+        if !self.0.contains(&note) {
+            self.0.push(note);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Text(String),
+    Ref(String),
+    Seq(Vec<Expr>),
+    Alt(Vec<Expr>),
+    Repeat(Box<Expr>, usize, Option<usize>),
+    // Something that has no equivalent, reported where it was parsed
+    Empty,
+}
+
+impl Expr {
+    fn seq(mut items: Vec<Expr>) -> Self {
+        match items.len() {
+            0 => Expr::Empty,
+            1 => items.remove(0),
+            _ => Expr::Seq(items),
+        }
+    }
+
+    fn alt(mut items: Vec<Expr>) -> Self {
+        match items.len() {
+            1 => items.remove(0),
+            _ => Expr::Alt(items),
+        }
+    }
+
+    fn refs<'e>(&'e self, out: &mut Vec<&'e str>) {
+        match self {
+            Expr::Ref(n) => out.push(n),
+            Expr::Seq(xs) | Expr::Alt(xs) => xs.iter().for_each(|x| x.refs(out)),
+            Expr::Repeat(x, _, _) => x.refs(out),
+            Expr::Text(_) | Expr::Empty => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RuleDef {
+    name: String,
+    expr: Expr,
+}
+
+fn char_value(v: u32, line: usize, notes: &mut Notes) -> Option<char> {
+    if v > 0x7f {
+        notes.add("Values above 0x7f are written as UTF-8 characters, not single bytes".into());
+    }
+    let c = char::from_u32(v);
+    if c.is_none() {
+        notes.add(format!(
+            "line {}: {:#x} is not a character, left out",
+            line, v
+        ));
+    }
+    c
+}
+
+fn char_range(lo: u32, hi: u32, line: usize, notes: &mut Notes) -> Expr {
+    if hi < lo {
+        notes.add(format!("line {}: empty range {:#x}-{:#x}", line, lo, hi));
+        return Expr::Empty;
+    }
+    let last = if hi - lo >= MAX_RANGE {
+        notes.add(format!(
+            "line {}: range {:#x}-{:#x} is too wide, only its first {} values are kept",
+            line, lo, hi, MAX_RANGE
+        ));
+        lo + MAX_RANGE - 1
+    } else {
+        hi
+    };
+    Expr::alt(
+        (lo..=last)
+            .filter_map(|v| char_value(v, line, notes))
+            .map(|c| Expr::Text(c.to_string()))
+            .collect(),
+    )
+}
+
+// ABNF
+
+fn parse_abnf(text: &str, notes: &mut Notes) -> Result<Vec<RuleDef>, String> {
+    // Rules continue on indented lines
+    let mut rules: Vec<(usize, String)> = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = strip_abnf_comment(line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        match rules.last_mut() {
+            Some((_, rule)) if line.starts_with([' ', '\t']) => {
+                rule.push(' ');
+                rule.push_str(line.trim());
+            }
+            _ => rules.push((i + 1, line.trim().to_string())),
+        }
+    }
+    let mut defs: Vec<RuleDef> = vec![];
+    for (line, rule) in rules {
+        let mut p = AbnfParser {
+            chars: rule.chars().collect(),
+            pos: 0,
+            line,
+        };
+        let name = p.rulename();
+        if name.is_empty() {
+            return Err(format!("line {}: expected a rule name", line));
+        }
+        p.skip_ws();
+        let incremental = if p.eat("=/") {
+            true
+        } else if p.eat("=") {
+            false
+        } else {
+            return Err(format!("line {}: expected = after {}", line, name));
+        };
+        let expr = p.alternation(notes)?;
+        p.skip_ws();
+        if let Some(c) = p.peek() {
+            return Err(format!("line {}: unexpected '{}'", line, c));
+        }
+        let defined = defs.iter().any(|d| d.name.eq_ignore_ascii_case(&name));
+        if defined && !incremental {
+            notes.add(format!(
+                "line {}: {} is defined again, both definitions are kept as alternatives",
+                line, name
+            ));
+        }
+        defs.push(RuleDef { name, expr });
+    }
+    Ok(defs)
+}
+
+// Comments start at `;` outside of strings and prose
+fn strip_abnf_comment(line: &str) -> &str {
+    let mut closing = None;
+    for (i, c) in line.char_indices() {
+        match (closing, c) {
+            (None, ';') => return &line[..i],
+            (None, '"') => closing = Some('"'),
+            (None, '<') => closing = Some('>'),
+            (Some(end), c) if c == end => closing = None,
+            _ => {}
+        }
+    }
+    line
+}
+
+struct AbnfParser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl AbnfParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        let matches = s
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c));
+        if matches {
+            self.pos += s.chars().count();
+        }
+        matches
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&f) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn rulename(&mut self) -> String {
+        if !self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            return String::new();
+        }
+        self.take_while(|c| c.is_ascii_alphanumeric() || c == '-')
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_ws();
+        if self.eat(&c.to_string()) {
+            Ok(())
+        } else {
+            Err(format!("line {}: expected '{}'", self.line, c))
+        }
+    }
+
+    fn alternation(&mut self, notes: &mut Notes) -> Result<Expr, String> {
+        let mut alts = vec![self.concatenation(notes)?];
+        loop {
+            self.skip_ws();
+            if !self.eat("/") {
+                return Ok(Expr::alt(alts));
+            }
+            alts.push(self.concatenation(notes)?);
+        }
+    }
+
+    fn concatenation(&mut self, notes: &mut Notes) -> Result<Expr, String> {
+        let mut items = vec![];
+        loop {
+            self.skip_ws();
+            match self.peek() {
+                None | Some(')' | ']' | '/') => return Ok(Expr::seq(items)),
+                _ => items.push(self.repetition(notes)?),
+            }
+        }
+    }
+
+    fn repetition(&mut self, notes: &mut Notes) -> Result<Expr, String> {
+        let number = |s: String, line| {
+            s.parse::<usize>()
+                .map_err(|_| format!("line {}: bad repeat count {}", line, s))
+        };
+        let min = self.take_while(|c| c.is_ascii_digit());
+        let (min, max) = if self.eat("*") {
+            let max = self.take_while(|c| c.is_ascii_digit());
+            (
+                if min.is_empty() {
+                    0
+                } else {
+                    number(min, self.line)?
+                },
+                if max.is_empty() {
+                    None
+                } else {
+                    Some(number(max, self.line)?)
+                },
+            )
+        } else if min.is_empty() {
+            return self.element(notes);
+        } else {
+            let n = number(min, self.line)?;
+            (n, Some(n))
+        };
+        Ok(Expr::Repeat(Box::new(self.element(notes)?), min, max))
+    }
+
+    fn element(&mut self, notes: &mut Notes) -> Result<Expr, String> {
+        let line = self.line;
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() => Ok(Expr::Ref(self.rulename())),
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.alternation(notes)?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some('[') => {
+                self.pos += 1;
+                let expr = self.alternation(notes)?;
+                self.expect(']')?;
+                Ok(Expr::Repeat(Box::new(expr), 0, Some(1)))
+            }
+            Some('"') => {
+                let s = self.quoted()?;
+                if s.chars().any(|c| c.is_ascii_alphabetic()) {
+                    notes.add(
+                        "Quoted strings match any case in ABNF, only the spelling as written \
+                         is generated"
+                            .into(),
+                    );
+                }
+                Ok(Expr::Text(s))
+            }
+            Some('%') => {
+                self.pos += 1;
+                match self.peek().map(|c| c.to_ascii_lowercase()) {
+                    Some('s') => {
+                        self.pos += 1;
+                        Ok(Expr::Text(self.quoted()?))
+                    }
+                    Some('i') => {
+                        self.pos += 1;
+                        let s = self.quoted()?;
+                        notes.add(format!(
+                            "line {}: %i\"{}\" matches any case, only the spelling as written \
+                             is generated",
+                            line, s
+                        ));
+                        Ok(Expr::Text(s))
+                    }
+                    Some(base @ ('x' | 'd' | 'b')) => {
+                        self.pos += 1;
+                        self.num_val(base, notes)
+                    }
+                    _ => Err(format!("line {}: expected %x, %d, %b, %s or %i", line)),
+                }
+            }
+            Some('<') => {
+                let prose = self.take_while(|c| c != '>');
+                self.expect('>')?;
+                notes.add(format!(
+                    "line {}: prose {}> cannot be generated, left empty",
+                    line, prose
+                ));
+                Ok(Expr::Empty)
+            }
+            Some(c) => Err(format!("line {}: unexpected '{}'", line, c)),
+            None => Err(format!("line {}: unexpected end of rule", line)),
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let s = self.take_while(|c| c != '"');
+        self.expect('"')?;
+        Ok(s)
+    }
+
+    // %x41, %x41.42.43 or %x30-39, after the base letter
+    fn num_val(&mut self, base: char, notes: &mut Notes) -> Result<Expr, String> {
+        let radix = match base {
+            'x' => 16,
+            'd' => 10,
+            _ => 2,
+        };
+        let line = self.line;
+        let number = |p: &mut Self| {
+            let digits = p.take_while(|c| c.is_digit(radix));
+            u32::from_str_radix(&digits, radix)
+                .map_err(|_| format!("line {}: bad %{} value", line, base))
+        };
+        let first = number(self)?;
+        if self.eat("-") {
+            let last = number(self)?;
+            return Ok(char_range(first, last, line, notes));
+        }
+        let mut text: String = char_value(first, line, notes).into_iter().collect();
+        while self.eat(".") {
+            text.extend(char_value(number(self)?, line, notes));
+        }
+        Ok(Expr::Text(text))
+    }
+}
+
+// Rules from the core set that the grammar uses but does not define
+fn add_core_rules(defs: &mut Vec<RuleDef>, notes: &mut Notes) {
+    // Each with the notes its own parse gave, e.g. OCTET going past 0x7f
+    let core: Vec<(RuleDef, Notes)> = ABNF_CORE_RULES
+        .lines()
+        .filter_map(|line| {
+            let mut core_notes = Notes::default();
+            let def = parse_abnf(line, &mut core_notes).ok()?.pop()?;
+            Some((def, core_notes))
+        })
+        .collect();
+    loop {
+        let mut used = vec![];
+        defs.iter().for_each(|d| d.expr.refs(&mut used));
+        let missing: Vec<&(RuleDef, Notes)> = core
+            .iter()
+            .filter(|(c, _)| {
+                used.iter().any(|u| u.eq_ignore_ascii_case(&c.name))
+                    && !defs.iter().any(|d| d.name.eq_ignore_ascii_case(&c.name))
+            })
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        for (def, core_notes) in missing {
+            core_notes.0.iter().for_each(|n| notes.add(n.clone()));
+            defs.push(def.clone());
+        }
+    }
+}
+
+// EBNF
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Text(String),
+    Define,
+    End,
+    Alt,
+    Comma,
+    Open(char),
+    Close(char),
+    Question,
+    Star,
+    Plus,
+    Minus,
+    Number(usize),
+    // ISO `? ... ?`
+    Special(String),
+    // W3C `[a-z]`
+    Class(String),
+}
+
+fn tokenize_ebnf(text: &str, w3c: bool) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut i = 0;
+    // Up to `end`, counting lines on the way
+    let until = |i: &mut usize, line: &mut usize, end: &str| -> Result<String, String> {
+        let end: Vec<char> = end.chars().collect();
+        let start = *i;
+        while *i < chars.len() {
+            if chars[*i..].starts_with(&end) {
+                let s = chars[start..*i].iter().collect();
+                *i += end.len();
+                return Ok(s);
+            }
+            if chars[*i] == '\n' {
+                *line += 1;
+            }
+            *i += 1;
+        }
+        Err(format!(
+            "line {}: missing closing {}",
+            line,
+            end.iter().collect::<String>()
+        ))
+    };
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let at = line;
+        let token = match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+                continue;
+            }
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' if next == Some('*') => {
+                i += 2;
+                until(&mut i, &mut line, "*)")?;
+                continue;
+            }
+            '/' if next == Some('*') => {
+                i += 2;
+                until(&mut i, &mut line, "*/")?;
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                loop {
+                    while i < chars.len() && (chars[i].is_alphanumeric() || "_-".contains(chars[i]))
+                    {
+                        name.push(chars[i]);
+                        i += 1;
+                    }
+                    // ISO names may have spaces in them, its rules need commas
+                    let mut j = i;
+                    while !w3c && j < chars.len() && (chars[j] == ' ' || chars[j] == '\t') {
+                        j += 1;
+                    }
+                    if j > i && chars.get(j).is_some_and(|c| c.is_alphabetic()) {
+                        name.push('_');
+                        i = j;
+                    } else {
+                        break;
+                    }
+                }
+                Token::Ident(name)
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let n: String = chars[start..i].iter().collect();
+                Token::Number(
+                    n.parse()
+                        .map_err(|_| format!("line {}: bad number {}", at, n))?,
+                )
+            }
+            '<' => {
+                i += 1;
+                Token::Ident(until(&mut i, &mut line, ">")?.trim().to_string())
+            }
+            '"' | '\'' => {
+                i += 1;
+                Token::Text(until(&mut i, &mut line, &c.to_string())?)
+            }
+            '#' if next == Some('x') => {
+                i += 2;
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_hexdigit() {
+                    i += 1;
+                }
+                let hex: String = chars[start..i].iter().collect();
+                let v = u32::from_str_radix(&hex, 16)
+                    .map_err(|_| format!("line {}: bad #x value", at))?;
+                Token::Text(char::from_u32(v).map(String::from).unwrap_or_default())
+            }
+            ':' if chars[i..].starts_with(&[':', ':', '=']) => {
+                i += 3;
+                Token::Define
+            }
+            ':' if next == Some('=') => {
+                i += 2;
+                Token::Define
+            }
+            '[' if w3c => {
+                i += 1;
+                Token::Class(until(&mut i, &mut line, "]")?)
+            }
+            '?' if !w3c => {
+                i += 1;
+                Token::Special(until(&mut i, &mut line, "?")?.trim().to_string())
+            }
+            c => {
+                i += 1;
+                match c {
+                    '=' => Token::Define,
+                    ';' | '.' => Token::End,
+                    '|' | '/' | '!' => Token::Alt,
+                    ',' => Token::Comma,
+                    '(' | '[' | '{' => Token::Open(c),
+                    ')' | ']' | '}' => Token::Close(c),
+                    '?' => Token::Question,
+                    '*' => Token::Star,
+                    '+' => Token::Plus,
+                    '-' => Token::Minus,
+                    c => return Err(format!("line {}: unexpected '{}'", at, c)),
+                }
+            }
+        };
+        tokens.push((token, at));
+    }
+    Ok(tokens)
+}
+
+fn parse_ebnf(text: &str, notes: &mut Notes) -> Result<Vec<RuleDef>, String> {
+    let mut p = EbnfParser {
+        tokens: tokenize_ebnf(text, text.contains("::="))?,
+        pos: 0,
+    };
+    let mut defs = vec![];
+    while p.pos < p.tokens.len() {
+        let line = p.line();
+        let name = match p.next() {
+            Some(Token::Ident(name)) => name,
+            _ => return Err(format!("line {}: expected a rule name", line)),
+        };
+        if p.next() != Some(Token::Define) {
+            return Err(format!("line {}: expected = after {}", line, name));
+        }
+        let expr = p.alternation(notes)?;
+        if p.peek() == Some(&Token::End) {
+            p.pos += 1;
+        } else if !p.at_rule_start() {
+            return Err(format!(
+                "line {}: expected ; at the end of {}",
+                p.line(),
+                name
+            ));
+        }
+        defs.push(RuleDef { name, expr });
+    }
+    Ok(defs)
+}
+
+struct EbnfParser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl EbnfParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).map(|(t, _)| t.clone());
+        self.pos += 1;
+        t
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(0, |(_, l)| *l)
+    }
+
+    // W3C rules have no terminator, the next one starts at `name ::=`
+    fn at_rule_start(&self) -> bool {
+        matches!(self.peek(), None | Some(Token::Ident(_)))
+            && matches!(
+                self.tokens.get(self.pos + 1),
+                None | Some((Token::Define, _))
+            )
+    }
+
+    fn alternation(&mut self, notes: &mut Notes) -> Result<Expr, String> {
+        let mut alts = vec![self.sequence(notes)?];
+        while self.peek() == Some(&Token::Alt) {
+            self.pos += 1;
+            alts.push(self.sequence(notes)?);
+        }
+        Ok(Expr::alt(alts))
+    }
+
+    fn sequence(&mut self, notes: &mut Notes) -> Result<Expr, String> {
+        let mut items = vec![];
+        loop {
+            match self.peek() {
+                None | Some(Token::End | Token::Alt | Token::Close(_)) => break,
+                Some(Token::Ident(_)) if self.at_rule_start() => break,
+                Some(Token::Comma) => self.pos += 1,
+                _ => items.push(self.term(notes)?),
+            }
+        }
+        Ok(Expr::seq(items))
+    }
+
+    fn term(&mut self, notes: &mut Notes) -> Result<Expr, String> {
+        let line = self.line();
+        // ISO `3 * "ab"`
+        if let (Some(Token::Number(n)), Some((Token::Star, _))) =
+            (self.peek().cloned(), self.tokens.get(self.pos + 1))
+        {
+            self.pos += 2;
+            return Ok(Expr::Repeat(Box::new(self.factor(notes)?), n, Some(n)));
+        }
+        let mut expr = self.factor(notes)?;
+        loop {
+            let (min, max) = match self.peek() {
+                Some(Token::Question) => (0, Some(1)),
+                Some(Token::Star) => (0, None),
+                Some(Token::Plus) => (1, None),
+                _ => break,
+            };
+            self.pos += 1;
+            expr = Expr::Repeat(Box::new(expr), min, max);
+        }
+        if self.peek() == Some(&Token::Minus) {
+            self.pos += 1;
+            self.factor(notes)?;
+            notes.add(format!(
+                "line {}: exceptions (a - b) cannot be represented, what they exclude is \
+                 still generated",
+                line
+            ));
+        }
+        Ok(expr)
+    }
+
+    fn factor(&mut self, notes: &mut Notes) -> Result<Expr, String> {
+        let line = self.line();
+        let close = |p: &mut Self, c: char| match p.next() {
+            Some(Token::Close(x)) if x == c => Ok(()),
+            _ => Err(format!("line {}: expected '{}'", p.line(), c)),
+        };
+        match self.next() {
+            Some(Token::Ident(name)) => Ok(Expr::Ref(name)),
+            Some(Token::Text(s)) => Ok(Expr::Text(s)),
+            Some(Token::Open(c)) => {
+                let expr = self.alternation(notes)?;
+                match c {
+                    '(' => {
+                        close(self, ')')?;
+                        Ok(expr)
+                    }
+                    '[' => {
+                        close(self, ']')?;
+                        Ok(Expr::Repeat(Box::new(expr), 0, Some(1)))
+                    }
+                    _ => {
+                        close(self, '}')?;
+                        Ok(Expr::Repeat(Box::new(expr), 0, None))
+                    }
+                }
+            }
+            Some(Token::Special(s)) => {
+                notes.add(format!(
+                    "line {}: special sequence ? {} ? cannot be generated, left empty",
+                    line, s
+                ));
+                Ok(Expr::Empty)
+            }
+            Some(Token::Class(class)) => Ok(char_class(&class, line, notes)),
+            Some(t) => Err(format!("line {}: unexpected {:?}", line, t)),
+            None => Err(format!("line {}: unexpected end of file", line)),
+        }
+    }
+}
+
+// W3C `[a-zA-Z_]` or `[#x30-#x39]`
+fn char_class(class: &str, line: usize, notes: &mut Notes) -> Expr {
+    if class.starts_with('^') {
+        notes.add(format!(
+            "line {}: negated class [{}] cannot be represented, left empty",
+            line, class
+        ));
+        return Expr::Empty;
+    }
+    let mut values = vec![];
+    let mut chars = class.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '#' && chars.peek() == Some(&'x') {
+            chars.next();
+            let mut hex = String::new();
+            while let Some(h) = chars.next_if(|h| h.is_ascii_hexdigit()) {
+                hex.push(h);
+            }
+            values.push(u32::from_str_radix(&hex, 16).unwrap_or(0));
+        } else {
+            values.push(c as u32);
+        }
+    }
+    // Turn `a - z` triples into ranges
+    let mut alts = vec![];
+    let mut i = 0;
+    while i < values.len() {
+        if values.get(i + 1) == Some(&('-' as u32)) && i + 2 < values.len() {
+            alts.push(char_range(values[i], values[i + 2], line, notes));
+            i += 3;
+        } else {
+            alts.push(char_range(values[i], values[i], line, notes));
+            i += 1;
+        }
+    }
+    Expr::alt(alts)
+}
+
+// Into productions
+
+fn sanitize_name(raw: &str) -> String {
+    let name: String = raw
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "_".to_string()
+    } else {
+        name
+    }
+}
+
+fn lower(defs: &[RuleDef], case_insensitive: bool, notes: &mut Notes) -> Grammar {
+    let key = |n: &str| {
+        if case_insensitive {
+            n.to_ascii_lowercase()
+        } else {
+            n.to_string()
+        }
+    };
+    // Rules merge by name, in the order they were first defined
+    let mut order: Vec<String> = vec![];
+    let mut merged: HashMap<String, Vec<&Expr>> = HashMap::new();
+    let mut names: HashMap<String, String> = HashMap::new();
+    for def in defs {
+        let k = key(&def.name);
+        if !merged.contains_key(&k) {
+            order.push(k.clone());
+            names.insert(k.clone(), sanitize_name(&def.name));
+        }
+        merged.entry(k).or_default().push(&def.expr);
+    }
+    let mut lowering = Lowering {
+        rules: vec![],
+        helpers: vec![],
+        taken: names.values().cloned().collect(),
+        counters: HashMap::new(),
+        names: &names,
+        key: &key,
+        notes,
+    };
+    for k in &order {
+        let name = names[k].clone();
+        for expr in &merged[k] {
+            for rhs in lowering.alternatives(&name, expr) {
+                lowering.rules.push(Production {
+                    lhs: name.clone(),
+                    rhs,
+                });
+            }
+            // Helpers go after the rule they belong to, so the start stays first
+            let helpers = std::mem::take(&mut lowering.helpers);
+            lowering.rules.extend(helpers);
+        }
+    }
+    Grammar {
+        rules: lowering.rules,
+    }
+}
+
+struct Lowering<'a> {
+    rules: Vec<Production>,
+    helpers: Vec<Production>,
+    taken: HashSet<String>,
+    counters: HashMap<String, usize>,
+    names: &'a HashMap<String, String>,
+    key: &'a dyn Fn(&str) -> String,
+    notes: &'a mut Notes,
+}
+
+impl Lowering<'_> {
+    fn push(&mut self, lhs: &str, rhs: String) {
+        self.helpers.push(Production {
+            lhs: lhs.to_string(),
+            rhs,
+        });
+    }
+
+    fn fresh(&mut self, owner: &str) -> String {
+        let counter = self.counters.entry(owner.to_string()).or_default();
+        loop {
+            *counter += 1;
+            let name = format!("{}_{}", owner, counter);
+            if self.taken.insert(name.clone()) {
+                return name;
+            }
+        }
+    }
+
+    fn helper(&mut self, owner: &str, alts: Vec<String>) -> String {
+        let name = self.fresh(owner);
+        for rhs in alts {
+            self.push(&name, rhs);
+        }
+        format!("{{{}}}", name)
+    }
+
+    fn alternatives(&mut self, owner: &str, expr: &Expr) -> Vec<String> {
+        match expr {
+            Expr::Alt(xs) => {
+                let mut ret = vec![];
+                for x in xs {
+                    ret.extend(self.alternatives(owner, x));
+                }
+                ret
+            }
+            x => vec![self.fragment(owner, x)],
+        }
+    }
+
+    // Production text for an expression, with a helper if it has choices
+    fn fragment(&mut self, owner: &str, expr: &Expr) -> String {
+        match expr {
+            Expr::Text(t) => escape_text(t),
+            Expr::Ref(n) => {
+                let name = self
+                    .names
+                    .get(&(self.key)(n))
+                    .cloned()
+                    .unwrap_or_else(|| sanitize_name(n));
+                format!("{{{}}}", name)
+            }
+            Expr::Empty => String::new(),
+            Expr::Seq(xs) => xs.iter().map(|x| self.fragment(owner, x)).collect(),
+            Expr::Alt(_) => {
+                let mut alts = self.alternatives(owner, expr);
+                if alts.len() == 1 {
+                    alts.remove(0)
+                } else {
+                    self.helper(owner, alts)
+                }
+            }
+            Expr::Repeat(x, min, max) => self.repeat(owner, x, *min, *max),
+        }
+    }
+
+    fn repeat(&mut self, owner: &str, expr: &Expr, min: usize, max: Option<usize>) -> String {
+        let item = self.fragment(owner, expr);
+        if item.is_empty() {
+            return item;
+        }
+        match max {
+            Some(max) if max <= min => item.repeat(max),
+            Some(max) if max - min <= MAX_BOUNDED => {
+                let alts = (min..=max).map(|n| item.repeat(n)).collect();
+                self.helper(owner, alts)
+            }
+            _ => {
+                if let Some(max) = max {
+                    self.notes.add(format!(
+                        "{}: {}*{} repetition is generated as {}* (no upper bound)",
+                        owner, min, max, min
+                    ));
+                }
+                let name = self.fresh(owner);
+                self.push(&name, String::new());
+                self.push(&name, format!("{}{{{}}}", item, name));
+                format!("{}{{{}}}", item.repeat(min), name)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(report: &ImportReport) -> Vec<(&str, &str)> {
+        report
+            .grammar
+            .rules
+            .iter()
+            .map(|p| (p.lhs.as_str(), p.rhs.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_import_abnf() {
+        let text = r#"
+; a tiny request line
+request = method SP path [ "?" query ] CRLF
+method  = "GET" / %s"POST"
+method  =/ %x50.55.54
+path    = 1*( "/" segment )
+segment = 1*3DIGIT
+query   = <anything>
+"#;
+        let report = import_str(text, GrammarSyntax::Abnf).unwrap();
+        assert_eq!(
+            rules(&report),
+            vec![
+                ("request", "{method}{SP}{path}{request_1}{CRLF}"),
+                ("request_1", ""),
+                ("request_1", "?{query}"),
+                ("method", "GET"),
+                ("method", "POST"),
+                ("method", "PUT"),
+                ("path", "/{segment}{path_1}"),
+                ("path_1", ""),
+                ("path_1", "/{segment}{path_1}"),
+                ("segment", "{segment_1}"),
+                ("segment_1", "{DIGIT}"),
+                ("segment_1", "{DIGIT}{DIGIT}"),
+                ("segment_1", "{DIGIT}{DIGIT}{DIGIT}"),
+                ("query", ""),
+                ("CRLF", "{CR}{LF}"),
+                ("DIGIT", "0"),
+                ("DIGIT", "1"),
+                ("DIGIT", "2"),
+                ("DIGIT", "3"),
+                ("DIGIT", "4"),
+                ("DIGIT", "5"),
+                ("DIGIT", "6"),
+                ("DIGIT", "7"),
+                ("DIGIT", "8"),
+                ("DIGIT", "9"),
+                ("SP", " "),
+                ("CR", "\r"),
+                ("LF", "\n"),
+            ]
+        );
+        assert_eq!(report.notes.len(), 2, "{:?}", report.notes);
+        assert!(report.notes[0].starts_with("Quoted strings match any case"));
+        assert!(report.notes[1].starts_with("line 8: prose <anything>"));
+        assert!(report.grammar.check().is_empty());
+        assert!(import_str("x = (a", GrammarSyntax::Abnf).is_err());
+    }
+
+    #[test]
+    fn test_import_ebnf() {
+        let iso = r#"
+(* ISO style *)
+list = "[", [ item, { ",", item } ], "]" ;
+item = digit | "x" - "y" | ? any byte ? ;
+digit = "0" | "1" ;
+"#;
+        let report = import_str(iso, GrammarSyntax::Ebnf).unwrap();
+        assert_eq!(
+            rules(&report),
+            vec![
+                ("list", "[{list_2}]"),
+                ("list_1", ""),
+                ("list_1", ",{item}{list_1}"),
+                ("list_2", ""),
+                ("list_2", "{item}{list_1}"),
+                ("item", "{digit}"),
+                ("item", "x"),
+                ("item", ""),
+                ("digit", "0"),
+                ("digit", "1"),
+            ]
+        );
+        assert_eq!(report.notes.len(), 2, "{:?}", report.notes);
+
+        let w3c = "Number ::= [0-2]+ ('.' [#x30-#x31])?\n";
+        let report = import_str(w3c, GrammarSyntax::Ebnf).unwrap();
+        assert_eq!(report.grammar.start(), Some("Number"));
+        assert!(report.notes.is_empty(), "{:?}", report.notes);
+        assert_eq!(report.grammar.rules.len(), 1 + 3 + 2 + 2 + 2);
+        assert!(report.grammar.check().is_empty());
+    }
+}
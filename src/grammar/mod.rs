@@ -0,0 +1,4 @@
+// grammar/mod.rs
+pub mod generate;
+pub mod import;
+pub mod rules;
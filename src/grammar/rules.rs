@@ -0,0 +1,416 @@
+// grammar/rules.rs
+// grammar.json as `grammar_rules` reads it: a list of [non-terminal,
+// production] pairs, where `{NAME}` in a production refers to another
+// non-terminal and everything else is literal. The first rule's
+// non-terminal is where inputs start.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Production {
+    pub lhs: String,
+    pub rhs: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Symbol {
+    Text(String),
+    NonTerminal(String),
+}
+
+// Split a production into literal text and references. `\{`, `\}` and
+// `\\` are literal braces and backslashes.
+pub fn parse_rhs(rhs: &str) -> Result<Vec<Symbol>, String> {
+    let mut ret = vec![];
+    let mut text = String::new();
+    let mut chars = rhs.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(e @ ('{' | '}' | '\\')) => text.push(e),
+                Some(e) => {
+                    text.push('\\');
+                    text.push(e);
+                }
+                None => text.push('\\'),
+            },
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("Unclosed {{ in \"{}\"", rhs)),
+                    }
+                }
+                if !is_valid_name(&name) {
+                    return Err(format!("Invalid non-terminal name {{{}}}", name));
+                }
+                if !text.is_empty() {
+                    ret.push(Symbol::Text(std::mem::take(&mut text)));
+                }
+                ret.push(Symbol::NonTerminal(name));
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        ret.push(Symbol::Text(text));
+    }
+    Ok(ret)
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+// Escape literal text for a production
+pub fn escape_text(text: &str) -> String {
+    let mut ret = String::new();
+    for c in text.chars() {
+        if matches!(c, '{' | '}' | '\\') {
+            ret.push('\\');
+        }
+        ret.push(c);
+    }
+    ret
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrammarIssue {
+    pub nonterminal: String,
+    pub message: String,
+}
+
+impl fmt::Display for GrammarIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.nonterminal, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Grammar {
+    pub rules: Vec<Production>,
+}
+
+impl Grammar {
+    // A new project's grammar.json is empty, that is a grammar without rules
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let pairs: Vec<(String, String)> = serde_json::from_str(json).map_err(|e| {
+            format!(
+                "grammar.json must be a list of [\"NonTerminal\", \"production\"] pairs: {}",
+                e
+            )
+        })?;
+        Ok(Self {
+            rules: pairs
+                .into_iter()
+                .map(|(lhs, rhs)| Production { lhs, rhs })
+                .collect(),
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let pairs: Vec<[&str; 2]> = self
+            .rules
+            .iter()
+            .map(|p| [p.lhs.as_str(), p.rhs.as_str()])
+            .collect();
+        // One rule per line keeps the file diffable
+        let lines: Vec<String> = pairs
+            .iter()
+            .map(|p| format!("  {}", serde_json::to_string(p).unwrap_or_default()))
+            .collect();
+        format!("[\n{}\n]\n", lines.join(",\n"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_json(&json)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_json())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn start(&self) -> Option<&str> {
+        self.rules.first().map(|p| p.lhs.as_str())
+    }
+
+    // Non-terminals with rules, in the order they are first defined
+    pub fn nonterminals(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.rules
+            .iter()
+            .map(|p| p.lhs.as_str())
+            .filter(|nt| seen.insert(*nt))
+            .collect()
+    }
+
+    // Indices of the rules for a non-terminal
+    pub fn productions_of(&self, nt: &str) -> Vec<usize> {
+        (0..self.rules.len())
+            .filter(|i| self.rules[*i].lhs == nt)
+            .collect()
+    }
+
+    // Productions split into symbols, leaving out the ones that do not parse
+    pub(crate) fn parsed(&self) -> HashMap<&str, Vec<Vec<Symbol>>> {
+        let mut ret: HashMap<&str, Vec<Vec<Symbol>>> = HashMap::new();
+        for p in &self.rules {
+            if let Ok(symbols) = parse_rhs(&p.rhs) {
+                ret.entry(p.lhs.as_str()).or_default().push(symbols);
+            }
+        }
+        ret
+    }
+
+    pub fn check(&self) -> Vec<GrammarIssue> {
+        let mut issues = vec![];
+        let issue = |nt: &str, message: String| GrammarIssue {
+            nonterminal: nt.to_string(),
+            message,
+        };
+        for p in &self.rules {
+            if !is_valid_name(&p.lhs) {
+                issues.push(issue(&p.lhs, "Invalid non-terminal name".to_string()));
+            }
+            if let Err(e) = parse_rhs(&p.rhs) {
+                issues.push(issue(&p.lhs, e));
+            }
+        }
+        let parsed = self.parsed();
+        let refs = |nt: &str| -> Vec<String> {
+            parsed.get(nt).map_or(vec![], |prods| {
+                prods
+                    .iter()
+                    .flatten()
+                    .filter_map(|s| match s {
+                        Symbol::NonTerminal(n) => Some(n.clone()),
+                        Symbol::Text(_) => None,
+                    })
+                    .collect()
+            })
+        };
+
+        let nts = self.nonterminals();
+        let mut undefined: Vec<String> = vec![];
+        let mut reported = HashSet::new();
+        for nt in &nts {
+            for r in refs(nt) {
+                if !parsed.contains_key(r.as_str()) && reported.insert((nt.to_string(), r.clone()))
+                {
+                    issues.push(issue(nt, format!("{{{}}} is not defined", r)));
+                    undefined.push(r);
+                }
+            }
+        }
+
+        // Reachable from the start
+        if let Some(start) = self.start() {
+            let mut reachable: HashSet<String> = HashSet::from([start.to_string()]);
+            let mut todo = vec![start.to_string()];
+            while let Some(nt) = todo.pop() {
+                for r in refs(&nt) {
+                    if reachable.insert(r.clone()) {
+                        todo.push(r);
+                    }
+                }
+            }
+            for nt in &nts {
+                if !reachable.contains(*nt) {
+                    issues.push(issue(nt, format!("Not reachable from {}", start)));
+                }
+            }
+        }
+
+        // Undefined references are reported already, count them as text so
+        // everything using them is not reported again
+        let mut finishing = parsed.clone();
+        for u in &undefined {
+            finishing.insert(u.as_str(), vec![vec![]]);
+        }
+        let costs = min_costs(&finishing);
+        for nt in &nts {
+            if !costs.contains_key(nt) {
+                issues.push(issue(nt, "Never produces a finite input".to_string()));
+            }
+        }
+
+        for nt in left_recursive(&parsed, &nts) {
+            issues.push(issue(
+                &nt,
+                "Left recursive, it can expand to itself without consuming input".to_string(),
+            ));
+        }
+        issues
+    }
+}
+
+// Least expansion depth each non-terminal needs to become plain text.
+// Missing entries never finish.
+pub(crate) fn min_costs<'g>(
+    parsed: &HashMap<&'g str, Vec<Vec<Symbol>>>,
+) -> HashMap<&'g str, usize> {
+    let mut costs: HashMap<&str, usize> = HashMap::new();
+    loop {
+        let mut changed = false;
+        for (nt, prods) in parsed {
+            let best = prods
+                .iter()
+                .filter_map(|p| production_cost(p, &costs))
+                .min();
+            if let Some(best) = best {
+                if costs.get(nt).is_none_or(|c| best < *c) {
+                    costs.insert(nt, best);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return costs;
+        }
+    }
+}
+
+pub(crate) fn production_cost(symbols: &[Symbol], costs: &HashMap<&str, usize>) -> Option<usize> {
+    let mut deepest = 0;
+    for s in symbols {
+        if let Symbol::NonTerminal(n) = s {
+            deepest = deepest.max(*costs.get(n.as_str())?);
+        }
+    }
+    Some(deepest + 1)
+}
+
+// Non-terminals that can reach themselves through the leftmost symbol,
+// looking through non-terminals that can produce nothing
+fn left_recursive(parsed: &HashMap<&str, Vec<Vec<Symbol>>>, nts: &[&str]) -> Vec<String> {
+    let mut nullable: HashSet<&str> = HashSet::new();
+    loop {
+        let before = nullable.len();
+        for (nt, prods) in parsed {
+            if prods.iter().any(|p| {
+                p.iter().all(|s| match s {
+                    Symbol::Text(t) => t.is_empty(),
+                    Symbol::NonTerminal(n) => nullable.contains(n.as_str()),
+                })
+            }) {
+                nullable.insert(nt);
+            }
+        }
+        if nullable.len() == before {
+            break;
+        }
+    }
+    let leftmost = |nt: &str| -> Vec<String> {
+        let mut ret = vec![];
+        for p in parsed.get(nt).into_iter().flatten() {
+            for s in p {
+                match s {
+                    Symbol::Text(t) if !t.is_empty() => break,
+                    Symbol::Text(_) => {}
+                    Symbol::NonTerminal(n) => {
+                        ret.push(n.clone());
+                        if !nullable.contains(n.as_str()) {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    };
+    nts.iter()
+        .filter(|nt| {
+            let mut seen = HashSet::new();
+            let mut todo = leftmost(nt);
+            while let Some(n) = todo.pop() {
+                if n == **nt {
+                    return true;
+                }
+                if seen.insert(n.clone()) {
+                    todo.extend(leftmost(&n));
+                }
+            }
+            false
+        })
+        .map(|nt| nt.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grammar(rules: &[(&str, &str)]) -> Grammar {
+        Grammar {
+            rules: rules
+                .iter()
+                .map(|(l, r)| Production {
+                    lhs: l.to_string(),
+                    rhs: r.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_rhs() {
+        assert_eq!(
+            parse_rhs(r"GET {PATH} \{x\}").unwrap(),
+            vec![
+                Symbol::Text("GET ".into()),
+                Symbol::NonTerminal("PATH".into()),
+                Symbol::Text(" {x}".into()),
+            ]
+        );
+        assert!(parse_rhs("{OPEN").is_err());
+        assert!(parse_rhs("{a b}").is_err());
+        assert_eq!(escape_text("{a}"), r"\{a\}");
+    }
+
+    #[test]
+    fn test_check() {
+        let g = grammar(&[
+            ("START", "{EXPR}"),
+            ("EXPR", "{EXPR}+{NUM}"),
+            ("EXPR", "{NUM}"),
+            ("NUM", "{DIGIT}"),
+            ("LOOP", "x{LOOP}"),
+            ("OPT", ""),
+            ("HIDDEN", "{OPT}{HIDDEN}"),
+        ]);
+        let issues: Vec<String> = g.check().iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            issues,
+            vec![
+                "NUM: {DIGIT} is not defined",
+                "LOOP: Not reachable from START",
+                "OPT: Not reachable from START",
+                "HIDDEN: Not reachable from START",
+                "LOOP: Never produces a finite input",
+                "HIDDEN: Never produces a finite input",
+                "EXPR: Left recursive, it can expand to itself without consuming input",
+                "HIDDEN: Left recursive, it can expand to itself without consuming input",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let g = grammar(&[("START", "a{B}"), ("B", "b")]);
+        assert_eq!(Grammar::from_json(&g.to_json()).unwrap(), g);
+        assert_eq!(Grammar::from_json("").unwrap(), Grammar::default());
+        assert!(Grammar::from_json("{}").is_err());
+    }
+}
@@ -1,7 +1,13 @@
 // lib.rs
 //
 pub mod app;
+pub mod campaign;
+pub mod cli;
+pub mod config;
+pub mod firmware;
+pub mod grammar;
 pub mod popup;
+pub mod state;
 pub mod utils;
 pub mod window;
 pub mod windows;
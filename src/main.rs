@@ -1,7 +1,13 @@
 // main.rs
 // TODO: Option<Vec<Request>> is the stupidest thing i have done
 mod app;
+mod campaign;
+mod cli;
+mod config;
+mod firmware;
+mod grammar;
 mod popup;
+mod state;
 mod utils;
 mod window;
 mod windows;
@@ -15,14 +21,31 @@ use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let tui_args = match cli::parse_args(&args) {
+        Ok(cli::Cli {
+            command: cli::Command::Tui(a),
+            ..
+        }) => a,
+        Ok(c) => std::process::exit(cli::run(c)),
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let mut app = App::new();
+    // Fail before the terminal is taken over, so the error stays readable
+    if let Err(e) = app.open_project(&tui_args) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new();
-
     loop {
         terminal.draw(|f| app.render(f))?;
 
@@ -1,4 +1,5 @@
 use crate::app::Request;
+use crate::state::AppState;
 use crate::window::Window; // Removed WindowTransition
 use crossterm::event::KeyEvent;
 use ratatui::widgets::{BorderType, Clear, Wrap};
@@ -61,7 +62,12 @@ impl Window for Popup {
         self.popup_type.title()
     }
 
-    fn render(&mut self, f: &mut Frame, popup_area: Rect) -> Option<Vec<Request>> {
+    fn render(
+        &mut self,
+        f: &mut Frame,
+        popup_area: Rect,
+        _state: &AppState,
+    ) -> Option<Vec<Request>> {
         f.render_widget(Clear, popup_area);
         let ret = None;
         let block = Block::default()
@@ -80,7 +86,7 @@ impl Window for Popup {
         ret
     }
 
-    fn handle_input(&mut self, _key: KeyEvent) -> Option<Vec<Request>> {
+    fn handle_input(&mut self, _key: KeyEvent, _state: &AppState) -> Option<Vec<Request>> {
         // always close
         Some(vec![Request::PopWindow])
     }
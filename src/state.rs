@@ -0,0 +1,228 @@
+// state.rs
+use std::path::PathBuf;
+
+#[cfg(feature = "for_fuzzer")]
+use std::sync::{RwLock, Weak};
+
+#[cfg(feature = "for_fuzzer")]
+use crate::campaign::runner::CampaignCtx;
+use crate::config::manifest::ProjectManifest;
+use crate::config::profiles::{active_profile, DEFAULT_PROFILE};
+#[cfg(feature = "for_fuzzer")]
+use flashfuzzemu::opts::EmuOpts;
+
+pub const DEFAULT_PORT: u16 = 1337;
+
+// Everything windows share. Windows get it by reference on every render and
+// key press and change it through `Request::Update`.
+pub struct AppState {
+    // Absolute path of the open project, which is also the current directory
+    pub project: Option<PathBuf>,
+    // The configuration profile the project fuzzes with
+    pub profile: String,
+    pub binary_path: Option<PathBuf>,
+    // Where a raw binary goes, other formats carry their own addresses
+    pub load_address: Option<u64>,
+    pub port: u16,
+    // No cores means a single client that is not pinned
+    pub cores: Vec<usize>,
+    #[cfg(feature = "for_fuzzer")]
+    pub emu_opts: Option<EmuOpts>,
+    // The campaign of the Fuzzing window. Weak, so leaving the window still
+    // stops the fuzzers.
+    #[cfg(feature = "for_fuzzer")]
+    pub campaign: Option<Weak<RwLock<CampaignCtx>>>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            project: None,
+            profile: DEFAULT_PROFILE.to_string(),
+            binary_path: None,
+            load_address: None,
+            port: DEFAULT_PORT,
+            cores: vec![],
+            #[cfg(feature = "for_fuzzer")]
+            emu_opts: None,
+            #[cfg(feature = "for_fuzzer")]
+            campaign: None,
+        }
+    }
+}
+
+// A change to the AppState, sent by a window
+pub enum StateUpdate {
+    Project(PathBuf),
+    // What project.json of the project just opened remembers
+    Manifest(ProjectManifest),
+    Profile(String),
+    BinaryPath(PathBuf),
+    LoadAddress(u64),
+    Port(u16),
+    Cores(Vec<usize>),
+    #[cfg(feature = "for_fuzzer")]
+    EmuOpts(EmuOpts),
+    #[cfg(feature = "for_fuzzer")]
+    Campaign(Weak<RwLock<CampaignCtx>>),
+}
+
+impl StateUpdate {
+    // Whether the change is remembered in project.json
+    pub fn is_persisted(&self) -> bool {
+        matches!(
+            self,
+            StateUpdate::BinaryPath(_)
+                | StateUpdate::LoadAddress(_)
+                | StateUpdate::Port(_)
+                | StateUpdate::Cores(_)
+        )
+    }
+}
+
+// Something a window needs before it can do its job, with what to do about it
+#[cfg(feature = "for_fuzzer")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Missing {
+    Binary,
+    LoadAddress,
+    EmuOpts,
+}
+
+#[cfg(feature = "for_fuzzer")]
+impl Missing {
+    pub fn prompt(&self) -> &'static str {
+        match self {
+            Missing::Binary => "No binary selected, pick one with Config > Select Binary.",
+            Missing::LoadAddress => {
+                "The binary is a raw image, select it again with Config > Select Binary to give it a load address."
+            }
+            Missing::EmuOpts => {
+                "The emulator configuration was not loaded, check it in Config and press Done."
+            }
+        }
+    }
+}
+
+impl AppState {
+    pub fn apply(&mut self, update: StateUpdate) {
+        match update {
+            StateUpdate::Project(p) => {
+                self.profile = active_profile(&p);
+                self.project = Some(p);
+            }
+            StateUpdate::Manifest(m) => {
+                self.binary_path = self.project.as_ref().and_then(|p| m.binary_path(p));
+                self.load_address = m.load_address;
+                self.port = m.port.unwrap_or(DEFAULT_PORT);
+                self.cores = m.cores;
+            }
+            StateUpdate::Profile(p) => self.profile = p,
+            StateUpdate::BinaryPath(p) => {
+                self.binary_path = Some(p);
+                self.load_address = None;
+            }
+            StateUpdate::LoadAddress(a) => self.load_address = Some(a),
+            StateUpdate::Port(p) => self.port = p,
+            StateUpdate::Cores(c) => self.cores = c,
+            #[cfg(feature = "for_fuzzer")]
+            StateUpdate::EmuOpts(o) => self.emu_opts = Some(o),
+            #[cfg(feature = "for_fuzzer")]
+            StateUpdate::Campaign(c) => self.campaign = Some(c),
+        }
+    }
+
+    // Write the binary, port and cores into project.json
+    pub fn save_manifest(&self) -> Result<(), String> {
+        let Some(project) = self.project.as_ref() else {
+            return Ok(());
+        };
+        ProjectManifest::update(project, |m| {
+            m.binary = None;
+            if let Some(b) = self.binary_path.as_ref() {
+                m.set_binary(project, b);
+            }
+            m.load_address = self.load_address;
+            m.port = Some(self.port);
+            m.cores = self.cores.clone();
+        })
+    }
+
+    pub fn project_name(&self) -> Option<String> {
+        let project = self.project.as_ref()?;
+        Some(
+            project
+                .file_name()
+                .map_or(project.display().to_string(), |n| {
+                    n.to_string_lossy().to_string()
+                }),
+        )
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    pub fn require_binary(&self) -> Result<&PathBuf, Missing> {
+        self.binary_path.as_ref().ok_or(Missing::Binary)
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    pub fn require_emu_opts(&self) -> Result<&EmuOpts, Missing> {
+        self.emu_opts.as_ref().ok_or(Missing::EmuOpts)
+    }
+
+    // Short campaign status for the footer, None without a live campaign
+    #[cfg(feature = "for_fuzzer")]
+    pub fn campaign_status(&self) -> Option<String> {
+        let ctx = self.campaign.as_ref()?.upgrade()?;
+        let ctx = ctx.read().ok()?;
+        let alive = ctx.clients.iter().filter(|c| c.is_alive()).count();
+        if alive == 0 {
+            return None;
+        }
+        Some(format!(
+            "fuzzing, {} execs, {} crashes",
+            crate::campaign::stats::format_count(ctx.stats.executions),
+            ctx.stats.crashes
+        ))
+    }
+
+    #[cfg(not(feature = "for_fuzzer"))]
+    pub fn campaign_status(&self) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_updates() {
+        let mut state = AppState::default();
+        assert_eq!(state.port, DEFAULT_PORT);
+        assert_eq!(state.binary_path, None);
+        state.apply(StateUpdate::Port(4000));
+        state.apply(StateUpdate::BinaryPath(PathBuf::from("/tmp/fw.elf")));
+        state.apply(StateUpdate::Project(PathBuf::from("/home/me/proj")));
+        assert_eq!(state.port, 4000);
+        assert_eq!(state.binary_path, Some(PathBuf::from("/tmp/fw.elf")));
+        assert_eq!(state.project_name().as_deref(), Some("proj"));
+        state.apply(StateUpdate::LoadAddress(0x0800_0000));
+        assert_eq!(state.load_address, Some(0x0800_0000));
+        // A new binary needs its own load address
+        state.apply(StateUpdate::BinaryPath(PathBuf::from("/tmp/fw.bin")));
+        assert_eq!(state.load_address, None);
+
+        state.apply(StateUpdate::Manifest(ProjectManifest {
+            binary: Some(PathBuf::from("build/fw.hex")),
+            cores: vec![2],
+            ..Default::default()
+        }));
+        assert_eq!(
+            state.binary_path,
+            Some(PathBuf::from("/home/me/proj/build/fw.hex"))
+        );
+        assert_eq!((state.port, state.cores.clone()), (DEFAULT_PORT, vec![2]));
+        assert!(StateUpdate::Port(1).is_persisted());
+        assert!(!StateUpdate::Profile("x".to_string()).is_persisted());
+    }
+}
@@ -0,0 +1,333 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph, Wrap},
+};
+use serde_json::Value;
+
+use crate::config::schema::{
+    check_emu_opts, format_input, format_value, get_path, parse_input, set_path, FieldError,
+    FieldKind, Schema,
+};
+use crate::utils::centered_rect;
+use crate::utils::input_dialogue::{InputDialogue, InputDialogueResult};
+
+pub enum FormEvent {
+    // The key was not for the form
+    Ignored,
+    Handled,
+    // The config value changed
+    Changed,
+    // Input that does not fit the field, with why
+    Rejected(String),
+}
+
+// Form over a JSON config, one row per field of the schema
+pub struct ConfigForm<'a> {
+    schema: Schema,
+    value: Value,
+    list_state: ListState,
+    input: Option<(usize, InputDialogue<'a>)>,
+    error: Option<FieldError>,
+}
+
+impl<'a> ConfigForm<'a> {
+    pub fn new(schema: Schema, value: Value) -> Self {
+        let first = schema
+            .fields
+            .iter()
+            .position(|f| f.kind != FieldKind::Section);
+        let mut ret = Self {
+            schema,
+            value,
+            list_state: ListState::default().with_selected(first),
+            input: None,
+            error: None,
+        };
+        ret.check();
+        ret
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    pub fn set_value(&mut self, value: Value) {
+        self.value = value;
+        self.check();
+    }
+
+    // Select the field a JSON path is about, returns false if there is none
+    pub fn select_path(&mut self, path: &str) -> bool {
+        match self.schema.field_for_path(path) {
+            Some(i) => {
+                self.list_state.select(Some(i));
+                true
+            }
+            None => false,
+        }
+    }
+
+    // True while a field is being edited and the form wants every key
+    pub fn is_editing(&self) -> bool {
+        self.input.is_some()
+    }
+
+    fn check(&mut self) {
+        self.error = check_emu_opts(&self.value).err();
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.list_state
+            .selected()
+            .filter(|i| *i < self.schema.fields.len())
+    }
+
+    // Move the selection, skipping section headers
+    fn step(&mut self, forward: bool) {
+        let Some(mut i) = self.selected() else {
+            return;
+        };
+        loop {
+            i = match forward {
+                true if i + 1 < self.schema.fields.len() => i + 1,
+                false if i > 0 => i - 1,
+                _ => return,
+            };
+            if self.schema.fields[i].kind != FieldKind::Section {
+                self.list_state.select(Some(i));
+                return;
+            }
+        }
+    }
+
+    fn current(&self, idx: usize) -> &Value {
+        let field = &self.schema.fields[idx];
+        get_path(&self.value, &field.path).unwrap_or(&field.default)
+    }
+
+    fn set(&mut self, idx: usize, new: Value) -> FormEvent {
+        let path = self.schema.fields[idx].path.clone();
+        set_path(&mut self.value, &path, new);
+        self.check();
+        FormEvent::Changed
+    }
+
+    #[cfg(feature = "for_fuzzer")]
+    fn cycle_enum(&mut self, idx: usize, forward: bool) -> FormEvent {
+        let FieldKind::Enum(variants) = &self.schema.fields[idx].kind else {
+            return FormEvent::Handled;
+        };
+        let pos = variants
+            .iter()
+            .position(|v| Some(v.as_str()) == self.current(idx).as_str());
+        let next = match (pos, forward) {
+            (Some(p), true) => (p + 1) % variants.len(),
+            (Some(p), false) => (p + variants.len() - 1) % variants.len(),
+            (None, _) => 0,
+        };
+        let new = Value::from(variants[next].as_str());
+        self.set(idx, new)
+    }
+
+    fn toggle(&mut self, idx: usize) -> FormEvent {
+        let new = Value::Bool(!self.current(idx).as_bool().unwrap_or(false));
+        self.set(idx, new)
+    }
+
+    fn open_input(&mut self, idx: usize) {
+        let field = &self.schema.fields[idx];
+        let mut input = InputDialogue::new(field.dotted(), field.kind.describe());
+        input
+            .textarea
+            .insert_str(format_input(&field.kind, self.current(idx)));
+        self.input = Some((idx, input));
+    }
+
+    pub fn handle_input(&mut self, key: KeyEvent) -> FormEvent {
+        if let Some((idx, input)) = self.input.as_mut() {
+            let idx = *idx;
+            return match input.handle_input(key) {
+                InputDialogueResult::Continue => FormEvent::Handled,
+                InputDialogueResult::Cancel => {
+                    self.input = None;
+                    FormEvent::Handled
+                }
+                InputDialogueResult::Submit(s) => {
+                    self.input = None;
+                    match parse_input(&self.schema.fields[idx].kind, &s) {
+                        Ok(v) => self.set(idx, v),
+                        Err(e) => FormEvent::Rejected(e),
+                    }
+                }
+            };
+        }
+
+        let Some(idx) = self.selected() else {
+            return FormEvent::Ignored;
+        };
+        let kind = self.schema.fields[idx].kind.clone();
+        match (key.code, kind) {
+            (KeyCode::Up | KeyCode::Char('j'), _) => self.step(false),
+            (KeyCode::Down | KeyCode::Char('k'), _) => self.step(true),
+            (KeyCode::Enter | KeyCode::Char(' '), FieldKind::Bool) => return self.toggle(idx),
+            (KeyCode::Left | KeyCode::Right, FieldKind::Bool) => return self.toggle(idx),
+            #[cfg(feature = "for_fuzzer")]
+            (KeyCode::Right | KeyCode::Char(' '), FieldKind::Enum(_)) => {
+                return self.cycle_enum(idx, true)
+            }
+            #[cfg(feature = "for_fuzzer")]
+            (KeyCode::Left, FieldKind::Enum(_)) => return self.cycle_enum(idx, false),
+            (KeyCode::Enter, _) => self.open_input(idx),
+            (KeyCode::Delete, _) => {
+                let default = self.schema.fields[idx].default.clone();
+                return self.set(idx, default);
+            }
+            _ => return FormEvent::Ignored,
+        }
+        FormEvent::Handled
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(4)])
+            .split(area);
+
+        // Errors below a field, like in one element of a list, go to the field
+        let error_row = self
+            .error
+            .as_ref()
+            .and_then(|e| self.schema.field_for_path(e.path.as_deref()?));
+        let key_width = self
+            .schema
+            .fields
+            .iter()
+            .map(|f| f.depth() * 2 + f.key().len())
+            .max()
+            .unwrap_or(0);
+        let items: Vec<ListItem> = self
+            .schema
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let indent = "  ".repeat(field.depth());
+                if field.kind == FieldKind::Section {
+                    return ListItem::new(Line::from(format!("{}{}", indent, field.key()).bold()));
+                }
+                let current = get_path(&self.value, &field.path);
+                let shown = format_value(&field.kind, current.unwrap_or(&field.default));
+                let value_style = match current {
+                    // Left out of the config, so the default applies
+                    None => Style::default().fg(Color::DarkGray),
+                    Some(_) if Some(i) == error_row => Style::default().fg(Color::Red),
+                    Some(_) => Style::default().fg(Color::Cyan),
+                };
+                let mut lines = vec![Line::from(vec![
+                    Span::raw(format!(
+                        "{:<width$}  ",
+                        format!("{}{}", indent, field.key()),
+                        width = key_width
+                    )),
+                    Span::styled(shown, value_style),
+                ])];
+                if Some(i) == error_row {
+                    let msg = &self.error.as_ref().unwrap().message;
+                    lines.push(Line::from(format!("{}  ^ {}", indent, msg).fg(Color::Red)));
+                }
+                ListItem::new(lines)
+            })
+            .collect();
+        let border = if self.error.is_some() {
+            Color::Indexed(88)
+        } else {
+            Color::Indexed(2)
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(border))
+                    .title("Emulator Options")
+                    .title_bottom("[Enter] edit  [←/→] change  [Del] default"),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::REVERSED),
+            );
+        f.render_stateful_widget(list, chunks[0], &mut self.list_state);
+
+        // Help for the selected field, or an error no field could be found for
+        let help = match (&self.error, error_row) {
+            (Some(e), None) => Line::from(e.message.clone().fg(Color::Red)),
+            _ => Line::from(
+                self.selected()
+                    .map(|i| self.schema.fields[i].help())
+                    .unwrap_or_default(),
+            ),
+        };
+        let help = Paragraph::new(help).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title("Help"),
+        );
+        f.render_widget(help, chunks[1]);
+
+        if let Some((_, input)) = self.input.as_mut() {
+            let input_area = centered_rect::centered_rect(80, 30, area);
+            input.render(f, input_area);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+    use serde_json::json;
+
+    fn press(form: &mut ConfigForm, code: KeyCode) -> FormEvent {
+        form.handle_input(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_form_edits_round_trip() {
+        let schema = Schema::from_defaults(json!({"debug": false, "flash": {"flash_base": 0}}));
+        // `flash_base` is left out of the config and shown with its default
+        let mut form = ConfigForm::new(schema, json!({"debug": false}));
+        assert!(matches!(
+            press(&mut form, KeyCode::Char(' ')),
+            FormEvent::Changed
+        ));
+        assert_eq!(form.value()["debug"], json!(true));
+
+        // The section header is skipped
+        press(&mut form, KeyCode::Down);
+        press(&mut form, KeyCode::Enter);
+        assert!(form.is_editing());
+        // Prefilled with 0x0
+        for c in "800".chars() {
+            press(&mut form, KeyCode::Char(c));
+        }
+        assert!(matches!(
+            press(&mut form, KeyCode::Enter),
+            FormEvent::Changed
+        ));
+        assert_eq!(form.value()["flash"]["flash_base"], json!(0x800));
+
+        press(&mut form, KeyCode::Enter);
+        press(&mut form, KeyCode::Char('z'));
+        assert!(matches!(
+            press(&mut form, KeyCode::Enter),
+            FormEvent::Rejected(_)
+        ));
+    }
+}
@@ -0,0 +1,92 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+};
+
+use crate::config::diff::{diff_stats, line_diff, DiffLine};
+
+pub enum DiffViewResult {
+    Continue,
+    Accept,
+    Reject,
+}
+
+// Scrollable preview of a change, to be accepted or turned down
+pub struct DiffView {
+    title: String,
+    lines: Vec<DiffLine>,
+    hint: &'static str,
+    top: usize,
+    height: usize,
+}
+
+impl DiffView {
+    pub fn new(title: String, old: &str, new: &str) -> Self {
+        let lines = line_diff(old, new);
+        // Start at the first change
+        let top = lines
+            .iter()
+            .position(|l| !matches!(l, DiffLine::Same(_)))
+            .unwrap_or(0)
+            .saturating_sub(3);
+        Self {
+            title,
+            lines,
+            hint: "[Enter] replace current config  [Esc] keep it  [PgUp/PgDn] scroll",
+            top,
+            height: 0,
+        }
+    }
+
+    // Key help for what accepting does, the config by default
+    pub fn with_hint(mut self, hint: &'static str) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        diff_stats(&self.lines) == (0, 0)
+    }
+
+    pub fn handle_input(&mut self, key: KeyEvent) -> DiffViewResult {
+        let max_top = self.lines.len().saturating_sub(self.height);
+        let page = self.height.max(1);
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('y') => return DiffViewResult::Accept,
+            KeyCode::Esc | KeyCode::Char('n') => return DiffViewResult::Reject,
+            KeyCode::Up => self.top = self.top.saturating_sub(1),
+            KeyCode::Down => self.top = (self.top + 1).min(max_top),
+            KeyCode::PageUp => self.top = self.top.saturating_sub(page),
+            KeyCode::PageDown => self.top = (self.top + page).min(max_top),
+            _ => {}
+        }
+        DiffViewResult::Continue
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        self.height = area.height.saturating_sub(2) as usize;
+        let lines: Vec<Line> = self
+            .lines
+            .iter()
+            .skip(self.top)
+            .take(self.height)
+            .map(|l| match l {
+                DiffLine::Same(s) => Line::raw(format!("  {}", s)),
+                DiffLine::Added(s) => Line::styled(format!("+ {}", s), Color::Green),
+                DiffLine::Removed(s) => Line::styled(format!("- {}", s), Color::Red),
+            })
+            .collect();
+        let (added, removed) = diff_stats(&self.lines);
+        let para = Paragraph::new(lines).block(
+            Block::default()
+                .title(format!("{} (+{} -{})", self.title, added, removed))
+                .title_bottom(self.hint)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().bg(Color::Black)),
+        );
+        f.render_widget(Clear, area);
+        f.render_widget(para, area);
+    }
+}
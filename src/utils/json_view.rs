@@ -0,0 +1,132 @@
+use ratatui::{prelude::*, widgets::Paragraph};
+use tui_textarea::TextArea;
+
+use crate::config::highlight::{tokenize_line, SyntaxError, TokenKind};
+
+fn token_style(kind: TokenKind) -> Style {
+    match kind {
+        TokenKind::Key => Style::default().fg(Color::Cyan),
+        TokenKind::String => Style::default().fg(Color::Green),
+        TokenKind::Number => Style::default().fg(Color::Magenta),
+        TokenKind::Literal => Style::default().fg(Color::Yellow),
+        TokenKind::Punct => Style::default().fg(Color::Gray),
+        TokenKind::Space => Style::default(),
+        TokenKind::Invalid => Style::default().fg(Color::White).bg(Color::Red),
+    }
+}
+
+// Draws a TextArea holding JSON with highlighting, line numbers and the
+// syntax error marked in the gutter. The TextArea still does the editing,
+// this only keeps track of the part of it that is visible.
+#[derive(Default)]
+pub struct JsonView {
+    top: usize,
+    left: usize,
+}
+
+impl JsonView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn render(
+        &mut self,
+        f: &mut Frame,
+        area: Rect,
+        textarea: &TextArea,
+        error: Option<&SyntaxError>,
+        show_cursor: bool,
+    ) {
+        let block = textarea.block().cloned().unwrap_or_default();
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let lines = textarea.lines();
+        let gutter = lines.len().to_string().len() + 2;
+        let height = inner.height as usize;
+        let width = (inner.width as usize).saturating_sub(gutter);
+        let (row, col) = textarea.cursor();
+        // Keep the cursor in view
+        if row < self.top {
+            self.top = row;
+        } else if height > 0 && row >= self.top + height {
+            self.top = row + 1 - height;
+        }
+        if col < self.left {
+            self.left = col;
+        } else if width > 0 && col >= self.left + width {
+            self.left = col + 1 - width;
+        }
+
+        let selection = textarea.selection_range();
+        let error_pos = error.map(|e| e.position());
+        let comma_line = error.and_then(|e| e.missing_comma).map(|l| l - 1);
+        let mut out = vec![];
+        for (i, line) in lines.iter().enumerate().skip(self.top).take(height) {
+            let (marker, number_style) = if error_pos.map(|p| p.0) == Some(i) {
+                (
+                    '>',
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                )
+            } else if comma_line == Some(i) {
+                (
+                    ',',
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                (' ', Style::default().fg(Color::DarkGray))
+            };
+            let mut spans = vec![Span::styled(
+                format!("{}{:>w$} ", marker, i + 1, w = gutter - 2),
+                number_style,
+            )];
+
+            // Style every char, then merge runs of the same style into spans
+            let chars: Vec<char> = line.chars().collect();
+            let mut styles = vec![Style::default(); chars.len() + 1];
+            for t in tokenize_line(line) {
+                for s in styles.iter_mut().take(t.end).skip(t.start) {
+                    *s = token_style(t.kind);
+                }
+            }
+            if let Some(((sr, sc), (er, ec))) = selection {
+                for (c, s) in styles.iter_mut().enumerate() {
+                    if (i, c) >= (sr, sc) && (i, c) < (er, ec) {
+                        *s = s.bg(Color::DarkGray);
+                    }
+                }
+            }
+            if let Some((er, ec)) = error_pos {
+                if er == i {
+                    let ec = ec.min(chars.len());
+                    styles[ec] = styles[ec].bg(Color::Red).add_modifier(Modifier::UNDERLINED);
+                }
+            }
+            if show_cursor && i == row {
+                let c = col.min(chars.len());
+                styles[c] = styles[c].add_modifier(Modifier::REVERSED);
+            }
+
+            let mut run: Option<(String, Style)> = None;
+            for (c, style) in styles.iter().enumerate().skip(self.left).take(width) {
+                let ch = chars.get(c).copied().unwrap_or(' ');
+                match run.as_mut() {
+                    Some((text, s)) if s == style => text.push(ch),
+                    _ => {
+                        if let Some((text, s)) = run.take() {
+                            spans.push(Span::styled(text, s));
+                        }
+                        run = Some((ch.to_string(), *style));
+                    }
+                }
+            }
+            if let Some((text, s)) = run {
+                spans.push(Span::styled(text, s));
+            }
+            out.push(Line::from(spans));
+        }
+        f.render_widget(Paragraph::new(out), inner);
+    }
+}
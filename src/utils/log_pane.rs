@@ -0,0 +1,229 @@
+#![cfg(feature = "for_fuzzer")]
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Paragraph},
+};
+
+use crate::campaign::log::FuzzerLog;
+use crate::utils::input_dialogue::{InputDialogue, InputDialogueResult};
+
+// Scrollable view over a FuzzerLog with incremental search
+pub struct LogPane<'a> {
+    // None follows the tail of the log
+    top: Option<usize>,
+    height: usize,
+    query: Option<String>,
+    current_match: Option<usize>,
+    search_input: Option<InputDialogue<'a>>,
+}
+
+impl<'a> LogPane<'a> {
+    pub fn new() -> Self {
+        Self {
+            top: None,
+            height: 0,
+            query: None,
+            current_match: None,
+            search_input: None,
+        }
+    }
+
+    // True while the search prompt is open and wants every key
+    pub fn is_searching(&self) -> bool {
+        self.search_input.is_some()
+    }
+
+    pub fn reset(&mut self) {
+        self.top = None;
+        self.current_match = None;
+    }
+
+    // Returns true if the key was used by the pane
+    pub fn handle_input(&mut self, key: KeyEvent, log: &FuzzerLog) -> bool {
+        if let Some(input) = self.search_input.as_mut() {
+            match input.handle_input(key) {
+                InputDialogueResult::Continue => {}
+                InputDialogueResult::Submit(q) => {
+                    self.search_input = None;
+                    self.query = if q.is_empty() { None } else { Some(q) };
+                    self.current_match = None;
+                    self.jump_to_match(log, true);
+                }
+                InputDialogueResult::Cancel => self.search_input = None,
+            }
+            return true;
+        }
+
+        let len = log.len();
+        let max_top = len.saturating_sub(self.height);
+        let top = self.top.unwrap_or(max_top);
+        let page = self.height.max(1);
+        match key.code {
+            KeyCode::Up => self.scroll_to(top.saturating_sub(1), max_top),
+            KeyCode::Down => self.scroll_to(top + 1, max_top),
+            KeyCode::PageUp => self.scroll_to(top.saturating_sub(page), max_top),
+            KeyCode::PageDown => self.scroll_to(top + page, max_top),
+            KeyCode::Home => self.scroll_to(0, max_top),
+            KeyCode::End | KeyCode::Char('f') => self.top = None,
+            KeyCode::Char('/') => {
+                self.search_input = Some(InputDialogue::new(
+                    "Search log".to_string(),
+                    "Text to search for".to_string(),
+                ))
+            }
+            KeyCode::Char('n') => self.jump_to_match(log, true),
+            KeyCode::Char('N') => self.jump_to_match(log, false),
+            _ => return false,
+        }
+        true
+    }
+
+    fn scroll_to(&mut self, top: usize, max_top: usize) {
+        // Scrolling back to the bottom resumes following the tail
+        self.top = if top >= max_top { None } else { Some(top) };
+    }
+
+    fn jump_to_match(&mut self, log: &FuzzerLog, forward: bool) {
+        let Some(query) = self.query.as_ref() else {
+            return;
+        };
+        let from = self.current_match.unwrap_or_else(|| {
+            if forward {
+                log.len().saturating_sub(1)
+            } else {
+                0
+            }
+        });
+        if let Some(i) = log.search(query, from, forward) {
+            self.current_match = Some(i);
+            let max_top = log.len().saturating_sub(self.height);
+            self.scroll_to(i.saturating_sub(self.height / 2), max_top);
+        }
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect, log: &FuzzerLog) {
+        self.height = area.height.saturating_sub(2) as usize;
+        let len = log.len();
+        let max_top = len.saturating_sub(self.height);
+        let top = self.top.unwrap_or(max_top).min(max_top);
+
+        let lines: Vec<Line> = log
+            .lines()
+            .iter()
+            .enumerate()
+            .skip(top)
+            .take(self.height)
+            .map(|(i, l)| self.highlight(l, Some(i) == self.current_match))
+            .collect();
+
+        let mut title = format!("Fuzzer output ({} lines)", len);
+        if self.top.is_some() {
+            title.push_str(" - scrolled, [f] to follow");
+        }
+        let help = match self.query.as_ref() {
+            Some(q) => format!("[/] search  [n/N] next/prev \"{}\"  [PgUp/PgDn] scroll", q),
+            None => "[/] search  [PgUp/PgDn] scroll".to_string(),
+        };
+        let para = Paragraph::new(lines).block(
+            Block::default()
+                .title(title)
+                .title_bottom(help)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded),
+        );
+        f.render_widget(para, area);
+
+        if let Some(input) = self.search_input.as_mut() {
+            let h = 3.min(area.height);
+            let prompt = Rect::new(area.x, area.y + area.height - h, area.width, h);
+            input.render(f, prompt);
+        }
+    }
+
+    fn highlight<'l>(&self, line: &'l str, is_current: bool) -> Line<'l> {
+        let base = if is_current {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+        let query = match self.query.as_ref() {
+            Some(q) if !q.is_empty() => q,
+            _ => return Line::styled(line, base),
+        };
+        let mut spans = vec![];
+        let mut pos = 0;
+        for (start, end) in find_matches(line, query) {
+            spans.push(Span::styled(&line[pos..start], base));
+            spans.push(Span::styled(
+                &line[start..end],
+                base.fg(Color::Black).bg(Color::Yellow),
+            ));
+            pos = end;
+        }
+        spans.push(Span::styled(&line[pos..], base));
+        Line::from(spans)
+    }
+}
+
+impl Default for LogPane<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Byte ranges of `line` that match `query` ignoring case. Lowercasing can
+// change the length of a character, e.g. 'İ' becomes "i̇", so the match is
+// made on the lowercased characters and mapped back to whole characters of
+// the original line.
+fn find_matches(line: &str, query: &str) -> Vec<(usize, usize)> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    // Each lowercased char with the range of the char it came from, and
+    // whether it is the first one that char became
+    let folded: Vec<(char, usize, usize, bool)> = line
+        .char_indices()
+        .flat_map(|(i, c)| {
+            let end = i + c.len_utf8();
+            c.to_lowercase()
+                .enumerate()
+                .map(move |(n, l)| (l, i, end, n == 0))
+        })
+        .collect();
+    let mut ret = vec![];
+    if query.is_empty() {
+        return ret;
+    }
+    let mut i = 0;
+    while i + query.len() <= folded.len() {
+        let end = i + query.len();
+        let whole = folded[i].3 && folded.get(end).is_none_or(|f| f.3);
+        if whole && folded[i..end].iter().map(|f| f.0).eq(query.iter().copied()) {
+            ret.push((folded[i].1, folded[end - 1].2));
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_matches() {
+        assert_eq!(
+            find_matches("Corpus: 4, corpus: 5", "CORPUS"),
+            vec![(0, 6), (11, 17)]
+        );
+        // 'İ' lowercases to two chars, the offsets still line up
+        let line = "İΩ";
+        assert_eq!(find_matches(line, "ω"), vec![(2, 4)]);
+        assert_eq!(&line[2..4], "Ω");
+        assert_eq!(find_matches(line, "İ"), vec![(0, 2)]);
+        // Half of what a char lowercases to is not a match
+        assert!(find_matches(line, "i").is_empty());
+        assert!(find_matches("abc", "").is_empty());
+    }
+}
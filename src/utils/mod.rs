@@ -1,3 +1,8 @@
 pub mod centered_rect;
+pub mod config_form;
+pub mod diff_view;
 pub mod file_dialogue;
 pub mod input_dialogue;
+pub mod json_view;
+pub mod log_pane;
+pub mod stats_chart;
@@ -0,0 +1,145 @@
+use ratatui::{
+    prelude::*,
+    symbols::Marker,
+    widgets::{Axis, Block, BorderType, Borders, Chart, Dataset, GraphType, Paragraph},
+};
+
+use crate::campaign::history::{HistoryZoom, StatsSample};
+use crate::campaign::stats::format_count;
+
+// 2x2 grid of charts for execs/sec, unique BBs, corpus size and crashes
+pub fn render_history_charts(
+    f: &mut Frame,
+    area: Rect,
+    samples: &[StatsSample],
+    zoom: HistoryZoom,
+) {
+    if samples.is_empty() {
+        let para = Paragraph::new("No samples yet")
+            .alignment(Alignment::Center)
+            .block(chart_block(format!("History ({})", zoom.label())));
+        f.render_widget(para, area);
+        return;
+    }
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Ratio(1, 2); 2])
+        .split(area);
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 2); 2])
+        .split(rows[0]);
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 2); 2])
+        .split(rows[1]);
+
+    let series = |value: fn(&StatsSample) -> Option<f64>| -> Vec<(f64, f64)> {
+        samples
+            .iter()
+            .filter_map(|s| value(s).map(|v| (s.elapsed, v)))
+            .collect()
+    };
+    let x_bounds = [
+        samples.first().unwrap().elapsed,
+        samples
+            .last()
+            .unwrap()
+            .elapsed
+            .max(samples.first().unwrap().elapsed + 1.0),
+    ];
+
+    render_series(
+        f,
+        top[0],
+        format!("exec speed ({})", zoom.label()),
+        &series(|s| Some(s.execs_per_sec)),
+        x_bounds,
+        Color::Green,
+    );
+    render_series(
+        f,
+        top[1],
+        format!("unique bbs ({})", zoom.label()),
+        &series(|s| s.unique_bbs.map(|n| n as f64)),
+        x_bounds,
+        Color::Cyan,
+    );
+    render_series(
+        f,
+        bottom[0],
+        format!("corpus size ({})", zoom.label()),
+        &series(|s| Some(s.corpus_size as f64)),
+        x_bounds,
+        Color::Yellow,
+    );
+    render_series(
+        f,
+        bottom[1],
+        format!("crashes ({})", zoom.label()),
+        &series(|s| Some(s.crashes as f64)),
+        x_bounds,
+        Color::Red,
+    );
+}
+
+fn render_series(
+    f: &mut Frame,
+    area: Rect,
+    title: String,
+    points: &[(f64, f64)],
+    x_bounds: [f64; 2],
+    color: Color,
+) {
+    if points.is_empty() {
+        let para = Paragraph::new("not reported")
+            .alignment(Alignment::Center)
+            .block(chart_block(title));
+        f.render_widget(para, area);
+        return;
+    }
+    let y_max = points.iter().map(|p| p.1).fold(0.0, f64::max).max(1.0) * 1.1;
+    let dataset = Dataset::default()
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(points);
+    let chart = Chart::new(vec![dataset])
+        .block(chart_block(title))
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::DarkGray))
+                .bounds(x_bounds)
+                .labels(vec![
+                    Line::from(format_elapsed(x_bounds[0])),
+                    Line::from(format_elapsed(x_bounds[1])),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::DarkGray))
+                .bounds([0.0, y_max])
+                .labels(vec![
+                    Line::from("0"),
+                    Line::from(format_count(y_max as u64)),
+                ]),
+        );
+    f.render_widget(chart, area);
+}
+
+fn chart_block<'a>(title: String) -> Block<'a> {
+    Block::default()
+        .title(title)
+        .title_style(Style::default().fg(Color::Yellow))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+}
+
+fn format_elapsed(secs: f64) -> String {
+    let secs = secs as u64;
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, (secs / 60) % 60)
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
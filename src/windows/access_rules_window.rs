@@ -0,0 +1,387 @@
+use std::path::{Path, PathBuf};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{
+    Block, BorderType, Borders, Clear, Paragraph, Row, Table, TableState, Tabs,
+};
+use ratatui::Frame;
+use serde_json::Value;
+
+use crate::app::Request;
+use crate::campaign::violations::{read_violations, ViolationLog};
+use crate::config::access_rules::{parse_rule_spec, AccessRules};
+use crate::config::schema::{format_value, FieldKind};
+use crate::popup::{Popup, PopupType};
+use crate::utils::centered_rect;
+use crate::utils::input_dialogue::{InputDialogue, InputDialogueResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AccessTab {
+    Rules,
+    Violations,
+}
+
+// Edits the memory access rules, next to the violations the fuzzer has
+// reported so far. Opened from the configuration window, which takes the
+// rules back on close.
+pub struct AccessRulesWindow<'a> {
+    rules: AccessRules,
+    violations: Result<ViolationLog, String>,
+    project_path: PathBuf,
+    tab: AccessTab,
+    rules_state: TableState,
+    violations_state: TableState,
+    // The rule being edited, None when adding one
+    input: Option<(Option<usize>, InputDialogue<'a>)>,
+    changed: bool,
+    closed: bool,
+}
+
+impl<'a> AccessRulesWindow<'a> {
+    pub fn new(config: &Value, default_list: Option<&str>, project_path: &Path) -> Self {
+        Self {
+            rules: AccessRules::from_config(config, default_list),
+            violations: read_violations(project_path),
+            project_path: project_path.to_path_buf(),
+            tab: AccessTab::Rules,
+            rules_state: TableState::default().with_selected(Some(0)),
+            violations_state: TableState::default().with_selected(Some(0)),
+            input: None,
+            changed: false,
+            closed: false,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    // Write the rules into the config, returns false if nothing changed
+    pub fn apply(&self, config: &mut Value) -> bool {
+        if self.changed {
+            self.rules.apply(config);
+        }
+        self.changed
+    }
+
+    fn open_input(&mut self, idx: Option<usize>) {
+        let (title, prefill) = match idx.map(|i| &self.rules.rules[i]) {
+            Some(r) => (
+                "Edit rule".to_string(),
+                format!(
+                    "{:#x} {:#x} {} {}",
+                    r.start,
+                    r.size,
+                    r.allow,
+                    r.action.name()
+                ),
+            ),
+            None => ("Add rule".to_string(), String::new()),
+        };
+        let mut dialogue = InputDialogue::new(
+            title,
+            "start size allow [action], e.g. 0x40000000 4k rw- log".to_string(),
+        );
+        dialogue.textarea.insert_str(prefill);
+        self.input = Some((idx, dialogue));
+    }
+
+    // Turn the selected violation into an allow rule
+    fn allow_selected(&mut self) -> Result<String, String> {
+        let groups = &self.violations.as_ref().map_err(|e| e.clone())?.groups;
+        let group = self
+            .violations_state
+            .selected()
+            .and_then(|i| groups.get(i))
+            .ok_or("No violation selected")?;
+        let v = group.violation;
+        if self.rules.allows(v.addr, v.kind) {
+            return Err(format!(
+                "A rule already allows {} at {:#x}",
+                v.kind.name(),
+                v.addr
+            ));
+        }
+        let idx = self.rules.allow(v.addr, v.size, v.kind);
+        self.rules_state.select(Some(idx));
+        self.changed = true;
+        Ok(format!(
+            "Allowed {} of {} bytes at {:#x}",
+            v.kind.name(),
+            v.size,
+            v.addr
+        ))
+    }
+
+    pub fn handle_input(&mut self, key: KeyEvent) -> Option<Vec<Request>> {
+        let warn = |e: String| Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]);
+        if let Some((idx, ref mut dialogue)) = self.input {
+            match dialogue.handle_input(key) {
+                InputDialogueResult::Continue => {}
+                InputDialogueResult::Submit(text) => {
+                    self.input = None;
+                    let result = parse_rule_spec(&text).and_then(|r| self.rules.set(idx, r));
+                    match result {
+                        Ok(i) => {
+                            self.changed = true;
+                            self.rules_state.select(Some(i));
+                        }
+                        Err(e) => return warn(e),
+                    }
+                }
+                InputDialogueResult::Cancel => self.input = None,
+            }
+            return None;
+        }
+        let (state, len) = match self.tab {
+            AccessTab::Rules => (&mut self.rules_state, self.rules.rules.len()),
+            AccessTab::Violations => (
+                &mut self.violations_state,
+                self.violations.as_ref().map_or(0, |v| v.groups.len()),
+            ),
+        };
+        match key.code {
+            KeyCode::Up | KeyCode::Char('j') => state.select_previous(),
+            KeyCode::Down | KeyCode::Char('k') => {
+                let row = state
+                    .selected()
+                    .map_or(0, |r| (r + 1).min(len.saturating_sub(1)));
+                state.select(Some(row));
+            }
+            KeyCode::Tab | KeyCode::F(2) => {
+                self.tab = match self.tab {
+                    AccessTab::Rules => AccessTab::Violations,
+                    AccessTab::Violations => AccessTab::Rules,
+                }
+            }
+            KeyCode::Esc => self.closed = true,
+            code => match self.tab {
+                AccessTab::Rules => {
+                    let selected = state.selected().filter(|i| *i < len);
+                    match (code, selected) {
+                        (KeyCode::Char('a'), _) => self.open_input(None),
+                        (KeyCode::Enter, Some(i)) => self.open_input(Some(i)),
+                        (KeyCode::Char('c'), Some(i)) => {
+                            let rule = &mut self.rules.rules[i];
+                            rule.action = rule.action.next();
+                            self.changed = true;
+                        }
+                        (KeyCode::Char('d') | KeyCode::Delete, Some(i)) => {
+                            self.rules.delete(i);
+                            self.changed = true;
+                            if i + 1 >= len {
+                                self.rules_state.select_previous();
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                AccessTab::Violations => match code {
+                    KeyCode::Char('a') => {
+                        return Some(vec![match self.allow_selected() {
+                            Ok(msg) => Request::Popup(Popup::new(PopupType::Success, msg)),
+                            Err(e) => Request::Popup(Popup::new(PopupType::Warning, e)),
+                        }]);
+                    }
+                    KeyCode::Char('r') => self.violations = read_violations(&self.project_path),
+                    _ => {}
+                },
+            },
+        }
+        None
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        f.render_widget(Clear, area);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(format!(
+                "Memory access rules: {}{}",
+                self.rules.list_path,
+                if self.changed { " (changed)" } else { "" }
+            ))
+            .title_bottom(match self.tab {
+                AccessTab::Rules => {
+                    "[a] add  [Enter] edit  [c] action  [d] delete  [Tab] violations  [Esc] done"
+                }
+                AccessTab::Violations => {
+                    "[a] allow this access  [r] reload  [Tab] rules  [Esc] done"
+                }
+            });
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Min(3),
+            ])
+            .split(inner);
+
+        let violations = self.violations.as_ref().map_or(0, |v| v.groups.len());
+        let tabs = Tabs::new(vec![
+            format!("Rules ({})", self.rules.rules.len()),
+            format!("Violations ({})", violations),
+        ])
+        .select(match self.tab {
+            AccessTab::Rules => 0,
+            AccessTab::Violations => 1,
+        })
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .divider("|");
+        f.render_widget(tabs, chunks[0]);
+        if !cfg!(feature = "mem_access_rules") {
+            f.render_widget(
+                Paragraph::new("Built without mem_access_rules, the emulator ignores these rules")
+                    .style(Style::default().fg(Color::Red)),
+                chunks[1],
+            );
+        } else if let (AccessTab::Violations, Ok(log)) = (self.tab, &self.violations) {
+            if log.unrecognised > 0 {
+                f.render_widget(
+                    Paragraph::new(format!(
+                        "{} line(s) in the logs mention a violation but could not be read",
+                        log.unrecognised
+                    ))
+                    .style(Style::default().fg(Color::Yellow)),
+                    chunks[1],
+                );
+            }
+        }
+
+        let highlight = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::REVERSED);
+        let header_style = Style::default().add_modifier(Modifier::BOLD);
+        match self.tab {
+            AccessTab::Rules => {
+                let rows: Vec<Row> = self
+                    .rules
+                    .rules
+                    .iter()
+                    .map(|r| {
+                        Row::new(vec![
+                            format!("{:#010x}", r.start),
+                            format!("{:#010x}", r.end()),
+                            format_value(&FieldKind::Size, &Value::from(r.size)),
+                            r.allow.to_string(),
+                            r.action.name().to_string(),
+                        ])
+                    })
+                    .collect();
+                let table = Table::new(
+                    rows,
+                    [
+                        Constraint::Length(12),
+                        Constraint::Length(12),
+                        Constraint::Length(20),
+                        Constraint::Length(7),
+                        Constraint::Fill(1),
+                    ],
+                )
+                .header(
+                    Row::new(vec!["Start", "End", "Size", "Allow", "On violation"])
+                        .style(header_style),
+                )
+                .row_highlight_style(highlight);
+                f.render_stateful_widget(table, chunks[2], &mut self.rules_state);
+            }
+            AccessTab::Violations => match &self.violations {
+                Err(e) => f.render_widget(
+                    Paragraph::new(e.clone()).style(Style::default().fg(Color::Red)),
+                    chunks[2],
+                ),
+                Ok(log) if log.groups.is_empty() => f.render_widget(
+                    Paragraph::new("No violations in the fuzzer logs")
+                        .style(Style::default().fg(Color::DarkGray)),
+                    chunks[2],
+                ),
+                Ok(log) => {
+                    let rows: Vec<Row> = log
+                        .groups
+                        .iter()
+                        .map(|g| {
+                            let v = g.violation;
+                            let allowed = self.rules.allows(v.addr, v.kind);
+                            Row::new(vec![
+                                g.count.to_string(),
+                                format!("{:#010x}", v.pc),
+                                format!("{:#010x}", v.addr),
+                                v.kind.name().to_string(),
+                                v.size.to_string(),
+                                if allowed { "allowed" } else { "" }.to_string(),
+                            ])
+                            .style(if allowed {
+                                Style::default().fg(Color::DarkGray)
+                            } else {
+                                Style::default()
+                            })
+                        })
+                        .collect();
+                    let table = Table::new(
+                        rows,
+                        [
+                            Constraint::Length(8),
+                            Constraint::Length(12),
+                            Constraint::Length(12),
+                            Constraint::Length(6),
+                            Constraint::Length(5),
+                            Constraint::Fill(1),
+                        ],
+                    )
+                    .header(
+                        Row::new(vec!["Count", "PC", "Address", "Kind", "Size", ""])
+                            .style(header_style),
+                    )
+                    .row_highlight_style(highlight);
+                    f.render_stateful_widget(table, chunks[2], &mut self.violations_state);
+                }
+            },
+        }
+
+        if let Some((_, ref mut dialogue)) = self.input {
+            let centered = centered_rect::centered_rect(60, 20, area);
+            f.render_widget(Clear, centered);
+            dialogue.render(f, centered);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_allow_violation() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("logs")).unwrap();
+        std::fs::write(
+            dir.path().join("logs").join("fuzzer-1.log"),
+            "violation: write of 1 bytes to 0x40021018 at pc 0x08000a3c\n",
+        )
+        .unwrap();
+        let config = json!({"mem_access_rules": []});
+        let mut window = AccessRulesWindow::new(&config, None, dir.path());
+        window.handle_input(KeyEvent::from(KeyCode::Tab));
+        window.handle_input(KeyEvent::from(KeyCode::Char('a')));
+        // A second time there is nothing left to allow
+        let again = window.handle_input(KeyEvent::from(KeyCode::Char('a')));
+        assert!(matches!(again.as_deref(), Some([Request::Popup(_)])));
+
+        let mut value = config.clone();
+        assert!(window.apply(&mut value));
+        assert_eq!(
+            value["mem_access_rules"],
+            json!([{"start": 0x4002_1018u64, "size": 1, "allow": "-w-", "action": "crash"}])
+        );
+    }
+}
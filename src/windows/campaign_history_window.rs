@@ -0,0 +1,150 @@
+// windows/campaign_history_window.rs
+use crate::app::Request;
+use crate::campaign::history::{HistoryZoom, StatsHistory, StatsSample};
+use crate::campaign::persist::{list_campaigns, load_campaign, CampaignInfo, StatsRecord};
+use crate::campaign::stats::{format_count, format_duration};
+use crate::popup::{Popup, PopupType};
+use crate::state::AppState;
+use crate::utils::stats_chart::render_history_charts;
+use crate::window::Window;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use std::path::Path;
+
+struct LoadedCampaign {
+    history: StatsHistory,
+    last: Option<StatsRecord>,
+}
+
+pub struct CampaignHistoryWindow {
+    campaigns: Vec<CampaignInfo>,
+    list_state: ListState,
+    loaded: Option<LoadedCampaign>,
+    zoom: HistoryZoom,
+}
+
+impl CampaignHistoryWindow {
+    pub fn new() -> Self {
+        let mut ret = Self {
+            campaigns: vec![],
+            list_state: ListState::default(),
+            loaded: None,
+            zoom: HistoryZoom::WholeRun,
+        };
+        ret.refresh();
+        ret
+    }
+
+    fn refresh(&mut self) {
+        self.campaigns = list_campaigns(Path::new("."));
+        if self.campaigns.is_empty() {
+            self.list_state.select(None);
+        } else {
+            self.list_state.select(Some(0));
+        }
+        self.loaded = None;
+    }
+
+    fn load_selected(&mut self) -> Option<Vec<Request>> {
+        let info = self.campaigns.get(self.list_state.selected()?)?;
+        match load_campaign(&info.path) {
+            Ok(records) => {
+                let samples = records.iter().map(StatsSample::from).collect();
+                self.loaded = Some(LoadedCampaign {
+                    history: StatsHistory::from_samples(samples),
+                    last: records.last().cloned(),
+                });
+                None
+            }
+            Err(e) => {
+                self.loaded = None;
+                Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))])
+            }
+        }
+    }
+
+    fn render_summary(&self, f: &mut Frame, area: Rect) {
+        let text = match self.loaded.as_ref().and_then(|l| l.last.as_ref()) {
+            Some(r) => format!(
+                "Duration: {}  |  Execs: {}  |  Corpus: {}  |  Crashes: {}  |  Timeouts: {}",
+                format_duration(r.run_time()),
+                format_count(r.execs),
+                r.corpus_count,
+                r.crashes,
+                r.timeouts
+            ),
+            None => "Select a campaign and press Enter to load it".to_string(),
+        };
+        let para = Paragraph::new(text).alignment(Alignment::Center).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title("Summary"),
+        );
+        f.render_widget(para, area);
+    }
+}
+
+impl Default for CampaignHistoryWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Window for CampaignHistoryWindow {
+    fn name(&self) -> &str {
+        "Campaign History"
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect, _state: &AppState) -> Option<Vec<Request>> {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
+            .split(area);
+
+        let items: Vec<ListItem> = self
+            .campaigns
+            .iter()
+            .map(|c| ListItem::new(c.id.clone()))
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .title("Campaigns")
+                    .title_bottom("[Enter] load  [z] zoom  [r] rescan"),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::REVERSED),
+            );
+        f.render_stateful_widget(list, chunks[0], &mut self.list_state);
+
+        let right = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Fill(1)])
+            .split(chunks[1]);
+        self.render_summary(f, right[0]);
+        let samples = self
+            .loaded
+            .as_ref()
+            .map(|l| l.history.samples(self.zoom))
+            .unwrap_or_default();
+        render_history_charts(f, right[1], &samples, self.zoom);
+        None
+    }
+
+    fn handle_input(&mut self, key: KeyEvent, _state: &AppState) -> Option<Vec<Request>> {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.list_state.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.list_state.select_next(),
+            KeyCode::Enter => return self.load_selected(),
+            KeyCode::Char('z') => self.zoom = self.zoom.next(),
+            KeyCode::Char('r') => self.refresh(),
+            _ => {}
+        }
+        None
+    }
+}
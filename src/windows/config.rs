@@ -5,16 +5,45 @@ use std::path::PathBuf;
 
 use ratatui::layout::{Alignment, Constraint, Direction, Layout};
 use ratatui::style::{Color, Modifier, Style, Stylize};
-use ratatui::widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::widgets::{
+    Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph, Row, Table,
+    TableState, Tabs,
+};
 
-use tui_textarea::TextArea;
+use tui_textarea::{CursorMove, TextArea};
 
 use crate::app::Request;
+use crate::campaign::cores::{format_core_spec, parse_core_spec};
+use crate::campaign::limits::{CampaignLimits, LimitKind};
+use crate::config::access_rules::rules_list_field;
+use crate::config::highlight::{syntax_error, SyntaxError};
+use crate::config::interrupts::{
+    allowed_policies, compiled_modes, interrupt_list_field, InterruptList,
+};
+use crate::config::memory_map::{region_list_field, MemoryMap};
+use crate::config::profiles::{
+    active_profile, clone_profile, create_profile, delete_profile, list_profiles, profile_path,
+    rename_profile, set_active_profile, DEFAULT_PROFILE,
+};
+use crate::config::schema::{check_emu_opts, parse_int, set_path, Schema};
+use crate::config::source::{load_config_source, ConfigSource, PendingSource, SCRIPT_TIMEOUT};
+use crate::config::validate::{entry_path, errors, flash_base, is_entry_key, validate, Issue};
+use crate::firmware::elf::{ElfFile, MapProposal};
+use crate::firmware::loader::{decode, emulator_image, ImageFormat};
+use crate::firmware::vector_table::read_vector_table;
+use crate::grammar::import::{import_grammar, ImportReport};
 use crate::popup::{Popup, PopupType};
+use crate::state::{AppState, StateUpdate};
 use crate::utils::centered_rect;
+use crate::utils::config_form::{ConfigForm, FormEvent};
+use crate::utils::diff_view::{DiffView, DiffViewResult};
 use crate::utils::file_dialogue::{FileDialogue, FileDialogueResult};
 use crate::utils::input_dialogue::{self, InputDialogue, InputDialogueResult};
+use crate::utils::json_view::JsonView;
 use crate::window::Window;
+use crate::windows::access_rules_window::AccessRulesWindow;
+use crate::windows::interrupt_window::InterruptWindow;
+use crate::windows::memory_map_window::MemoryMapWindow;
 
 #[cfg(feature = "for_fuzzer")]
 use flashfuzzemu::opts::EmuOpts;
@@ -23,19 +52,77 @@ pub enum ConfigWindowState<'a> {
     Main(ListState),
     ManualConfig,
     FromScript(FileDialogue),
+    // A config script that only runs once the user says so
+    ConfirmScript(PathBuf),
+    // A config script running on its own thread
+    RunningScript(PendingSource),
+    // A loaded configuration waiting to replace the current one
+    PreviewConfig(String, DiffView),
     SelectBinary(FileDialogue),
+    // A selected ELF file, before it becomes the binary
+    BinaryPreview(Box<BinaryInfo>, TableState),
+    // A raw binary, which needs to be told where it goes
+    LoadAddress(PathBuf, InputDialogue<'a>),
     SetPort(InputDialogue<'a>),
+    SetCores(InputDialogue<'a>),
+    Limits(ListState),
+    EditLimit(LimitKind, InputDialogue<'a>),
+    // Problems found in the configuration
+    Issues(ListState, Vec<Issue>),
+    MemoryMap(MemoryMapWindow<'a>),
+    Interrupts(InterruptWindow<'a>),
+    AccessRules(AccessRulesWindow<'a>),
+    // The profiles, with the key that has to be pressed again to confirm
+    Profiles(ListState, Option<crossterm::event::KeyCode>),
+    EditProfile(ProfileAction, InputDialogue<'a>),
+    ImportGrammar(FileDialogue),
+    // An imported grammar waiting to replace grammar.json
+    PreviewGrammar(ImportReport, DiffView),
+}
+
+pub struct BinaryInfo {
+    path: PathBuf,
+    elf: ElfFile,
+    proposal: MapProposal,
+}
+
+// What a profile name is being asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileAction {
+    Create,
+    Clone(String),
+    Rename(String),
+}
+
+// How the configuration is shown and edited, switched with F2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigTab {
+    Form,
+    Raw,
 }
 
 pub struct ConfigWindow<'a> {
     state: ConfigWindowState<'a>,
+    project: PathBuf,
     config_file: Option<PathBuf>,
+    // The profile config_file belongs to
+    profile: String,
+    profiles: Vec<String>,
     config_tx: TextArea<'static>,
+    raw_view: JsonView,
+    config_error: Option<SyntaxError>,
+    form: ConfigForm<'a>,
+    tab: ConfigTab,
     options: Vec<String>,
+    limits: CampaignLimits,
 }
 
 impl<'a> ConfigWindow<'a> {
     pub fn new() -> Self {
+        Self::in_project(std::path::Path::new("."))
+    }
+
+    pub fn in_project(project: &std::path::Path) -> Self {
         let mut config_tx = TextArea::default();
         let tx_block = Block::new()
             .borders(Borders::ALL)
@@ -49,15 +136,32 @@ impl<'a> ConfigWindow<'a> {
         config_tx.insert_str("Configuration not read yet!");
         let mut ret = Self {
             state: ConfigWindowState::Main(ListState::default().with_selected(Some(0))),
+            project: project.to_path_buf(),
             config_file: None,
+            profile: DEFAULT_PROFILE.to_string(),
+            profiles: vec![],
             config_tx,
+            raw_view: JsonView::new(),
+            config_error: None,
+            form: ConfigForm::new(config_schema(), serde_json::Value::Null),
+            tab: ConfigTab::Form,
             options: vec![
+                "Profiles".into(),
                 "From script".into(),
                 "Manual configuration".into(),
+                "Memory Map".into(),
+                "Interrupts".into(),
+                "Access Rules".into(),
+                "Import Grammar".into(),
                 "Select Binary".into(),
                 "Set Port".into(),
+                "Set Cores".into(),
+                "Campaign Limits".into(),
+                "Validate".into(),
                 "Done".into(),
             ],
+            // Broken limits are reported when fuzzing starts
+            limits: CampaignLimits::load(project).unwrap_or_default(),
         };
         ret.load_config_file();
         ret.load_config_str();
@@ -65,9 +169,226 @@ impl<'a> ConfigWindow<'a> {
     }
 
     pub fn load_config_file(&mut self) {
-        let mut p = PathBuf::from("./");
-        p.set_file_name("config.json");
-        self.config_file = Some(p);
+        self.profile = active_profile(&self.project);
+        self.profiles = list_profiles(&self.project);
+        self.config_file = Some(profile_path(&self.project, &self.profile));
+    }
+
+    // Edits that Done has not written to the profile yet
+    fn has_unsaved_changes(&self) -> bool {
+        let Some(config_file) = self.config_file.as_ref() else {
+            return false;
+        };
+        read_to_string(config_file).ok() != Some(self.config_tx.lines().join("\n"))
+    }
+
+    // Back to the profile list, on the given profile
+    fn show_profiles(&mut self, name: &str) {
+        self.profiles = list_profiles(&self.project);
+        let idx = self.profiles.iter().position(|p| p == name);
+        self.state = ConfigWindowState::Profiles(ListState::default().with_selected(idx), None);
+    }
+
+    fn confirm_profile_key(&mut self, code: crossterm::event::KeyCode) {
+        if let ConfigWindowState::Profiles(_, ref mut confirm) = self.state {
+            *confirm = Some(code);
+        }
+    }
+
+    // Edit another profile and make it the one the project fuzzes with
+    fn switch_profile(&mut self, name: &str) -> Result<(), String> {
+        set_active_profile(&self.project, name)?;
+        self.load_config_file();
+        self.load_config_str();
+        Ok(())
+    }
+
+    fn submit_profile_name(&mut self, action: &ProfileAction, name: &str) -> Option<Vec<Request>> {
+        let project = self.project.clone();
+        let name = name.trim();
+        let result = match action {
+            ProfileAction::Create => create_profile(&project, name, &fresh_config()).map(|_| ()),
+            ProfileAction::Clone(from) => clone_profile(&project, from, name).map(|_| ()),
+            ProfileAction::Rename(from) => rename_profile(&project, from, name),
+        };
+        if let Err(e) = result {
+            let back = match action {
+                ProfileAction::Create => self.profile.clone(),
+                ProfileAction::Clone(from) | ProfileAction::Rename(from) => from.clone(),
+            };
+            self.show_profiles(&back);
+            return Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]);
+        }
+        self.show_profiles(name);
+        match action {
+            // Edits in the window carry over to the renamed file
+            ProfileAction::Rename(from) if *from == self.profile => {
+                self.profile = name.to_string();
+                self.config_file = Some(profile_path(&project, name));
+                Some(vec![Request::Update(StateUpdate::Profile(
+                    name.to_string(),
+                ))])
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_input_profiles(&mut self, key: crossterm::event::KeyEvent) -> Option<Vec<Request>> {
+        let (selected, confirming) = match self.state {
+            ConfigWindowState::Profiles(ref l, confirming) => (
+                l.selected().and_then(|i| self.profiles.get(i)).cloned(),
+                confirming == Some(key.code),
+            ),
+            _ => return None,
+        };
+        let popup = |t: PopupType, msg: String| Some(vec![Request::Popup(Popup::new(t, msg))]);
+        match key.code {
+            crossterm::event::KeyCode::Up | crossterm::event::KeyCode::Char('j') => {
+                if let ConfigWindowState::Profiles(ref mut l, ref mut confirm) = self.state {
+                    l.select_previous();
+                    *confirm = None;
+                }
+            }
+            crossterm::event::KeyCode::Down | crossterm::event::KeyCode::Char('k') => {
+                if let ConfigWindowState::Profiles(ref mut l, ref mut confirm) = self.state {
+                    l.select_next();
+                    *confirm = None;
+                }
+            }
+            crossterm::event::KeyCode::Enter => {
+                let name = selected?;
+                if name == self.profile {
+                    return None;
+                }
+                if self.has_unsaved_changes() && !confirming {
+                    self.confirm_profile_key(key.code);
+                    return popup(
+                        PopupType::Warning,
+                        format!(
+                            "{} has unsaved changes, press Done to save them or Enter again to \
+                             drop them",
+                            self.profile
+                        ),
+                    );
+                }
+                if let Err(e) = self.switch_profile(&name) {
+                    return popup(PopupType::Warning, e);
+                }
+                self.show_profiles(&name);
+                return Some(vec![
+                    Request::Update(StateUpdate::Profile(name.clone())),
+                    Request::Popup(Popup::new(
+                        PopupType::Info,
+                        format!("Profile {} is now active", name),
+                    )),
+                ]);
+            }
+            crossterm::event::KeyCode::Char('n') => {
+                self.state = ConfigWindowState::EditProfile(
+                    ProfileAction::Create,
+                    InputDialogue::new(
+                        "New profile".to_string(),
+                        "Name of the profile, starts from the defaults:".to_string(),
+                    ),
+                );
+            }
+            crossterm::event::KeyCode::Char('c') => {
+                let name = selected?;
+                self.state = ConfigWindowState::EditProfile(
+                    ProfileAction::Clone(name.clone()),
+                    InputDialogue::new(format!("Clone {}", name), "Name of the copy:".to_string()),
+                );
+            }
+            crossterm::event::KeyCode::Char('r') => {
+                let name = selected?;
+                if name == DEFAULT_PROFILE {
+                    return popup(
+                        PopupType::Warning,
+                        "The default profile cannot be renamed, clone it instead".to_string(),
+                    );
+                }
+                let mut dialogue =
+                    InputDialogue::new(format!("Rename {}", name), "New name:".to_string());
+                dialogue.textarea.insert_str(&name);
+                self.state = ConfigWindowState::EditProfile(ProfileAction::Rename(name), dialogue);
+            }
+            crossterm::event::KeyCode::Char('d') | crossterm::event::KeyCode::Delete => {
+                let name = selected?;
+                if name != DEFAULT_PROFILE && !confirming {
+                    self.confirm_profile_key(key.code);
+                    return popup(
+                        PopupType::Warning,
+                        format!("Press again to delete profile {} for good", name),
+                    );
+                }
+                if let Err(e) = delete_profile(&self.project, &name) {
+                    return popup(PopupType::Warning, e);
+                }
+                let mut ret = vec![Request::Popup(Popup::new(
+                    PopupType::Info,
+                    format!("Deleted profile {}", name),
+                ))];
+                // Deleting the active profile falls back to the default one
+                if name == self.profile {
+                    self.load_config_file();
+                    self.load_config_str();
+                    ret.push(Request::Update(StateUpdate::Profile(self.profile.clone())));
+                }
+                let current = self.profile.clone();
+                self.show_profiles(&current);
+                return Some(ret);
+            }
+            crossterm::event::KeyCode::Esc => {
+                self.state = ConfigWindowState::Main(ListState::default().with_selected(Some(0)))
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render_profiles(
+        &mut self,
+        f: &mut ratatui::prelude::Frame,
+        area: ratatui::prelude::Rect,
+    ) -> Option<Vec<Request>> {
+        let ret = self.render_main(f, area);
+        let centered = centered_rect::centered_rect(50, 50, area);
+        let items: Vec<ListItem> = self
+            .profiles
+            .iter()
+            .map(|name| {
+                if *name == self.profile {
+                    ListItem::new(format!("● {} (active)", name))
+                        .style(Style::default().fg(Color::Green))
+                } else {
+                    ListItem::new(format!("  {}", name))
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Config Profiles")
+                    .title_bottom(
+                        "[Enter] activate  [n] new  [c] clone  [r] rename  [d] delete  [Esc] back",
+                    )
+                    .border_type(BorderType::Rounded),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        f.render_widget(Clear, centered);
+        match self.state {
+            ConfigWindowState::Profiles(ref mut l, _) => {
+                f.render_stateful_widget(list, centered, l)
+            }
+            _ => f.render_widget(list, centered),
+        }
+        if let ConfigWindowState::EditProfile(_, ref mut input_dialogue) = self.state {
+            let input_area = centered_rect::centered_rect(80, 40, centered);
+            f.render_widget(Clear, input_area);
+            input_dialogue.render(f, input_area);
+        }
+        ret
     }
 
     pub fn load_config_str(&mut self) {
@@ -79,13 +400,170 @@ impl<'a> ConfigWindow<'a> {
         config_tx.insert_str(&config_json);
         self.config_tx = config_tx;
         self.update_config_block();
+        // Anything the form cannot show is left to the raw view
+        match validate_json(&config_json) {
+            Ok(value) => self.form.set_value(value),
+            Err(_) => self.tab = ConfigTab::Raw,
+        }
+    }
+
+    // Rewrite the raw JSON after the form changed it
+    fn sync_from_form(&mut self) {
+        let json_str = serde_json::to_string_pretty(self.form.value()).unwrap_or_default();
+        let mut config_tx = TextArea::default();
+        config_tx.insert_str(&json_str);
+        self.config_tx = config_tx;
+        self.update_config_block();
+    }
+
+    fn replace_config(&mut self, json_str: &str) {
+        let mut config_tx = TextArea::default();
+        config_tx.insert_str(json_str);
+        self.config_tx = config_tx;
+        self.update_config_block();
+        if let Ok(value) = validate_json(json_str) {
+            self.form.set_value(value);
+        }
+    }
+
+    // Load a config file and show what it changes. Scripts are asked about
+    // first and run in the background.
+    fn load_from(
+        &mut self,
+        path: &std::path::Path,
+        binary: Option<&std::path::Path>,
+    ) -> Option<Vec<Request>> {
+        if let Ok(ConfigSource::Script) = ConfigSource::of(path) {
+            self.state = ConfigWindowState::ConfirmScript(path.to_path_buf());
+            return None;
+        }
+        self.show_loaded(path, load_config_source(path, binary))
+    }
+
+    fn show_loaded(
+        &mut self,
+        path: &std::path::Path,
+        loaded: Result<serde_json::Value, String>,
+    ) -> Option<Vec<Request>> {
+        let warn = |msg: String| Some(vec![Request::Popup(Popup::new(PopupType::Warning, msg))]);
+        let value = match loaded {
+            Ok(v) => v,
+            Err(e) => return warn(e),
+        };
+        if let Err(e) = check_emu_opts(&value) {
+            return warn(match e.path {
+                Some(p) => format!(
+                    "{} is not a valid configuration, {}: {}",
+                    path.display(),
+                    p,
+                    e.message
+                ),
+                None => format!(
+                    "{} is not a valid configuration: {}",
+                    path.display(),
+                    e.message
+                ),
+            });
+        }
+        let new_config = serde_json::to_string_pretty(&value).unwrap_or_default();
+        let diff_view = DiffView::new(
+            format!("Load {}", path.display()),
+            &self.config_tx.lines().join("\n"),
+            &new_config,
+        );
+        if diff_view.is_empty() {
+            return Some(vec![Request::Popup(Popup::new(
+                PopupType::Info,
+                format!("{} matches the current configuration", path.display()),
+            ))]);
+        }
+        self.state = ConfigWindowState::PreviewConfig(new_config, diff_view);
+        None
+    }
+
+    fn switch_tab(&mut self) -> Option<Vec<Request>> {
+        match self.tab {
+            ConfigTab::Form => self.tab = ConfigTab::Raw,
+            ConfigTab::Raw => match validate_json(&self.config_tx.lines().join("\n")) {
+                Ok(value) => {
+                    self.form.set_value(value);
+                    self.tab = ConfigTab::Form;
+                }
+                Err(e) => {
+                    return Some(vec![Request::Popup(Popup::new(
+                        PopupType::Warning,
+                        format!("Fix the JSON before switching to the form: {}", e),
+                    ))]);
+                }
+            },
+        }
+        None
+    }
+
+    fn render_config(&mut self, f: &mut ratatui::prelude::Frame, area: ratatui::prelude::Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(3)])
+            .split(area);
+        let tabs = Tabs::new(vec!["Form", "Raw JSON"])
+            .select(match self.tab {
+                ConfigTab::Form => 0,
+                ConfigTab::Raw => 1,
+            })
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .divider("|");
+        let hint = Paragraph::new("[F2] switch view ").alignment(Alignment::Right);
+        f.render_widget(tabs, chunks[0]);
+        f.render_widget(hint, chunks[0]);
+        match self.tab {
+            ConfigTab::Form => self.form.render(f, chunks[1]),
+            ConfigTab::Raw => {
+                let editing = matches!(self.state, ConfigWindowState::ManualConfig);
+                self.raw_view.render(
+                    f,
+                    chunks[1],
+                    &self.config_tx,
+                    self.config_error.as_ref(),
+                    editing,
+                )
+            }
+        }
     }
 
     pub fn update_config_block(&mut self) {
         let json_str = self.config_tx.lines().join("\n");
-        let block = get_tx_block(&json_str);
+        self.config_error = syntax_error(&json_str);
+        let mut block = get_tx_block(&json_str);
+        if let Some(e) = self.config_error.as_ref() {
+            block = block.title_bottom(match e.missing_comma {
+                Some(l) => format!("[F8] go to error, missing comma on line {}?", l),
+                None => "[F8] go to error".to_string(),
+            });
+        }
         self.config_tx.set_block(block);
     }
+
+    // Put the cursor on the syntax error. Pressing it again from there goes
+    // to the end of the line that probably lacks a comma.
+    fn jump_to_error(&mut self) {
+        let Some(e) = self.config_error.as_ref() else {
+            return;
+        };
+        let (row, col) = e.position();
+        let target = match e.missing_comma {
+            Some(l) if self.config_tx.cursor() == (row, col) => {
+                let end = self.config_tx.lines()[l - 1].chars().count();
+                (l - 1, end)
+            }
+            _ => (row, col),
+        };
+        self.config_tx
+            .move_cursor(CursorMove::Jump(target.0 as u16, target.1 as u16));
+    }
     fn render_main(
         &mut self,
         f: &mut ratatui::prelude::Frame,
@@ -114,7 +592,7 @@ impl<'a> ConfigWindow<'a> {
         let list = List::new(items).block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Configuration Options")
+                .title(format!("Configuration Options, profile {}", self.profile))
                 .border_type(BorderType::Rounded),
         );
         let horizontal_chunks = Layout::default()
@@ -124,7 +602,7 @@ impl<'a> ConfigWindow<'a> {
             .split(area);
 
         f.render_widget(list, horizontal_chunks[0]);
-        f.render_widget(&self.config_tx, horizontal_chunks[1]);
+        self.render_config(f, horizontal_chunks[1]);
         None
     }
 
@@ -135,9 +613,66 @@ impl<'a> ConfigWindow<'a> {
     ) -> Option<Vec<Request>> {
         let mut ret = self.render_main(f, area);
         let centered = centered_rect::centered_rect(60, 60, area);
-        if let ConfigWindowState::FromScript(ref mut file_dialogue) = self.state {
-            f.render_widget(Clear, centered);
-            file_dialogue.render(f, centered);
+        // A finished script replaces the progress box with its result
+        if let ConfigWindowState::RunningScript(ref pending) = self.state {
+            if let Some(loaded) = pending.poll() {
+                let path = pending.path.clone();
+                self.state = ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                return self.show_loaded(&path, loaded);
+            }
+        }
+        match self.state {
+            ConfigWindowState::FromScript(ref mut file_dialogue) => {
+                f.render_widget(Clear, centered);
+                file_dialogue.render(f, centered);
+            }
+            ConfigWindowState::ConfirmScript(ref path) => {
+                let text = format!(
+                    "Run {}?\n\nThe script runs in a sandbox that can read the project and the binary but cannot change files or reach the network. It still runs code you did not write, so only run scripts you trust.",
+                    path.display()
+                );
+                let box_area = centered_rect::centered_rect(60, 30, area);
+                f.render_widget(Clear, box_area);
+                f.render_widget(
+                    Paragraph::new(text)
+                        .wrap(ratatui::widgets::Wrap { trim: true })
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .border_type(BorderType::Rounded)
+                                .border_style(Style::default().fg(Color::Yellow))
+                                .title("Run config script")
+                                .title_bottom("[y] run  [n/Esc] cancel"),
+                        ),
+                    box_area,
+                );
+            }
+            ConfigWindowState::RunningScript(ref pending) => {
+                let text = format!(
+                    "Running {} ({}s, stopped after {}s)",
+                    pending.path.display(),
+                    pending.elapsed().as_secs(),
+                    SCRIPT_TIMEOUT.as_secs()
+                );
+                let box_area = centered_rect::centered_rect(60, 20, area);
+                f.render_widget(Clear, box_area);
+                f.render_widget(
+                    Paragraph::new(text)
+                        .wrap(ratatui::widgets::Wrap { trim: true })
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .border_type(BorderType::Rounded)
+                                .title("Run config script")
+                                .title_bottom("[Esc] stop waiting"),
+                        ),
+                    box_area,
+                );
+            }
+            ConfigWindowState::PreviewConfig(_, ref mut diff_view) => {
+                diff_view.render(f, centered_rect::centered_rect(80, 80, area));
+            }
+            _ => {}
         }
         // display file dialogue
         return ret;
@@ -150,14 +685,180 @@ impl<'a> ConfigWindow<'a> {
     ) -> Option<Vec<Request>> {
         let mut ret = self.render_main(f, area);
         let centered = centered_rect::centered_rect(60, 60, area);
-        if let ConfigWindowState::SelectBinary(ref mut file_dialogue) = self.state {
-            f.render_widget(Clear, centered);
-            file_dialogue.render(f, centered);
+        match self.state {
+            ConfigWindowState::SelectBinary(ref mut file_dialogue) => {
+                f.render_widget(Clear, centered);
+                file_dialogue.render(f, centered);
+            }
+            ConfigWindowState::LoadAddress(_, ref mut input_dialogue) => {
+                f.render_widget(Clear, centered);
+                input_dialogue.render(f, centered);
+            }
+            ConfigWindowState::BinaryPreview(ref info, ref mut table_state) => {
+                render_binary_preview(
+                    f,
+                    centered_rect::centered_rect(80, 80, area),
+                    info,
+                    table_state,
+                );
+            }
+            _ => {}
         }
-        // display file dialogue
         return ret;
     }
 
+    // ELF files are shown before they are used, HEX and S-record images are
+    // checked and raw binaries asked for their load address
+    fn preview_binary(&mut self, path: PathBuf) -> Option<Vec<Request>> {
+        self.state = ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+        let data = match std::fs::read(&path) {
+            Ok(d) => d,
+            Err(e) => {
+                return Some(vec![Request::Popup(Popup::new(
+                    PopupType::Warning,
+                    format!("Cannot read {}: {}", path.display(), e),
+                ))])
+            }
+        };
+        match ImageFormat::detect(&path, &data) {
+            ImageFormat::Elf => {}
+            ImageFormat::Raw => {
+                let mut input = InputDialogue::new(
+                    format!("Load address of {}", path.display()),
+                    "Address the raw image starts at, e.g. 0x08000000:".to_string(),
+                );
+                // Usually the start of flash
+                if let Some(base) = validate_json(&self.config_tx.lines().join("\n"))
+                    .ok()
+                    .and_then(|v| flash_base(&v))
+                {
+                    input.textarea.insert_str(format!("{:#x}", base));
+                }
+                self.state = ConfigWindowState::LoadAddress(path, input);
+                return None;
+            }
+            _ => {
+                return Some(match decode(&path, &data, None) {
+                    Ok(image) => vec![
+                        Request::Update(StateUpdate::BinaryPath(path)),
+                        Request::Popup(Popup::new(PopupType::Info, image.describe())),
+                    ],
+                    Err(e) => vec![Request::Popup(Popup::new(PopupType::Warning, e))],
+                });
+            }
+        }
+        match ElfFile::parse(&data) {
+            Ok(elf) => {
+                let proposal = elf.propose_map(&data);
+                self.state = ConfigWindowState::BinaryPreview(
+                    Box::new(BinaryInfo {
+                        path,
+                        elf,
+                        proposal,
+                    }),
+                    TableState::default().with_selected(Some(0)),
+                );
+                None
+            }
+            Err(e) => Some(vec![Request::Popup(Popup::new(
+                PopupType::Warning,
+                format!("{} is not a usable ELF file: {}", path.display(), e),
+            ))]),
+        }
+    }
+
+    // Select the binary and show what mapping its segments does to the config
+    fn map_binary(&mut self, info: &BinaryInfo) -> Option<Vec<Request>> {
+        let mut ret = vec![Request::Update(StateUpdate::BinaryPath(info.path.clone()))];
+        let mut value = match self.editable_value("the memory map") {
+            Ok(v) => v,
+            Err(e) => {
+                ret.extend(e.unwrap_or_default());
+                return Some(ret);
+            }
+        };
+        let list = region_list_field(self.form.schema());
+        let mut map = match MemoryMap::from_config(&value, list.as_deref()) {
+            Ok(m) => m,
+            Err(e) => {
+                ret.push(Request::Popup(Popup::new(PopupType::Warning, e)));
+                return Some(ret);
+            }
+        };
+        let mut notes = info.proposal.notes.clone();
+        let mut changed = false;
+        for r in &info.proposal.regions {
+            match map.cover(&r.name, r.start, r.size, r.perms) {
+                Ok((_, c)) => changed |= c,
+                Err(e) => notes.push(format!(
+                    "{} {:#x}-{:#x} not mapped: {}",
+                    r.name,
+                    r.start,
+                    r.start + r.size,
+                    e
+                )),
+            }
+        }
+        if changed {
+            if let Err(e) = map.apply(&mut value) {
+                notes.push(e);
+            }
+        }
+        if let Some(entry) = info.proposal.entry {
+            self.set_entry(&mut value, entry);
+        }
+
+        let new_config = serde_json::to_string_pretty(&value).unwrap_or_default();
+        let diff_view = DiffView::new(
+            format!("Memory map for {}", info.path.display()),
+            &self.config_tx.lines().join("\n"),
+            &new_config,
+        )
+        .with_hint("[Enter] take it  [Esc] keep the current map  [PgUp/PgDn] scroll");
+        if !notes.is_empty() {
+            ret.push(Request::Popup(Popup::new(
+                PopupType::Warning,
+                notes.join("\n"),
+            )));
+        }
+        if diff_view.is_empty() {
+            ret.push(Request::Popup(Popup::new(
+                PopupType::Info,
+                format!("The configuration already maps {}", info.path.display()),
+            )));
+        } else {
+            self.state = ConfigWindowState::PreviewConfig(new_config, diff_view);
+        }
+        Some(ret)
+    }
+
+    // The entry point goes where the config already sets one, or where the
+    // emulator has a setting for it
+    fn set_entry(&self, value: &mut serde_json::Value, entry: u64) {
+        let path = entry_path(value)
+            .filter(|p| !p.contains('['))
+            .or_else(|| {
+                self.form
+                    .schema()
+                    .fields
+                    .iter()
+                    .find(|f| is_entry_key(f.key()))
+                    .map(|f| f.dotted())
+            })
+            .unwrap_or_else(|| "entry".to_string());
+        let keys: Vec<String> = path.split('.').map(|k| k.to_string()).collect();
+        let as_string = keys
+            .iter()
+            .try_fold(&*value, |v, k| v.get(k))
+            .is_some_and(|v| v.is_string());
+        let new = if as_string {
+            serde_json::Value::from(format!("{:#x}", entry))
+        } else {
+            serde_json::Value::from(entry)
+        };
+        set_path(value, &keys, new);
+    }
+
     fn render_set_port(
         &mut self,
         f: &mut ratatui::prelude::Frame,
@@ -165,19 +866,104 @@ impl<'a> ConfigWindow<'a> {
     ) -> Option<Vec<Request>> {
         let mut ret = self.render_main(f, area);
         let centered = centered_rect::centered_rect(60, 60, area);
-        if let ConfigWindowState::SetPort(ref mut input_dialogue) = self.state {
-            f.render_widget(Clear, centered);
-            input_dialogue.render(f, centered);
+        match self.state {
+            ConfigWindowState::SetPort(ref mut input_dialogue)
+            | ConfigWindowState::SetCores(ref mut input_dialogue) => {
+                f.render_widget(Clear, centered);
+                input_dialogue.render(f, centered);
+            }
+            _ => {}
         }
         // display input dialogue
         return ret;
     }
 
+    fn render_limits(
+        &mut self,
+        f: &mut ratatui::prelude::Frame,
+        area: ratatui::prelude::Rect,
+    ) -> Option<Vec<Request>> {
+        let ret = self.render_main(f, area);
+        let centered = centered_rect::centered_rect(60, 60, area);
+        let selected_idx = match self.state {
+            ConfigWindowState::Limits(ref l) => l.selected().unwrap_or(0),
+            _ => 0,
+        };
+        let items: Vec<ListItem> = LimitKind::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, kind)| {
+                let style = if i == selected_idx {
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(format!(
+                    "{:<30} {}",
+                    kind.label(),
+                    self.limits.display(*kind)
+                ))
+                .style(style)
+            })
+            .collect();
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Campaign Limits")
+                .title_bottom("[Enter] edit  [Del] clear  [Esc] back")
+                .border_type(BorderType::Rounded),
+        );
+        f.render_widget(Clear, centered);
+        f.render_widget(list, centered);
+        if let ConfigWindowState::EditLimit(_, ref mut input_dialogue) = self.state {
+            let input_area = centered_rect::centered_rect(80, 40, centered);
+            f.render_widget(Clear, input_area);
+            input_dialogue.render(f, input_area);
+        }
+        ret
+    }
+
+    // Apply one limit and write project.json straight away
+    fn update_limit(&mut self, kind: LimitKind, value: Option<u64>) -> Option<Vec<Request>> {
+        self.limits.set(kind, value);
+        match self.limits.save(&self.project) {
+            Ok(()) => None,
+            Err(e) => Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]),
+        }
+    }
+
     fn handle_input_manual(&mut self, key: crossterm::event::KeyEvent) -> Option<Vec<Request>> {
+        if self.tab == ConfigTab::Form {
+            if !self.form.is_editing() {
+                match key.code {
+                    crossterm::event::KeyCode::Esc => {
+                        self.state =
+                            ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                        return None;
+                    }
+                    crossterm::event::KeyCode::F(2) => return self.switch_tab(),
+                    _ => {}
+                }
+            }
+            return match self.form.handle_input(key) {
+                FormEvent::Changed => {
+                    self.sync_from_form();
+                    None
+                }
+                FormEvent::Rejected(e) => {
+                    Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))])
+                }
+                FormEvent::Ignored | FormEvent::Handled => None,
+            };
+        }
         match key.code {
             crossterm::event::KeyCode::Esc => {
                 self.state = ConfigWindowState::Main(ListState::default().with_selected(Some(0)))
             }
+            crossterm::event::KeyCode::F(2) => return self.switch_tab(),
+            crossterm::event::KeyCode::F(8) => self.jump_to_error(),
             _ => {
                 self.config_tx.input(key);
             }
@@ -187,6 +973,281 @@ impl<'a> ConfigWindow<'a> {
         return None;
     }
 
+    // Everything wrong with the current configuration. A syntax error hides
+    // the rest, there is nothing to check before it is fixed.
+    fn check_config(
+        &self,
+        binary: Option<&std::path::Path>,
+        load_address: Option<u64>,
+    ) -> Vec<Issue> {
+        if let Some(e) = self.config_error.as_ref() {
+            return vec![Issue::new(
+                "",
+                format!("line {} column {}: {}", e.line, e.column, e.message),
+            )];
+        }
+        let value = match validate_json(&self.config_tx.lines().join("\n")) {
+            Ok(v) => v,
+            Err(e) => return vec![Issue::new("", e.to_string())],
+        };
+        // The size that is loaded, which for HEX files and ELFs is not the
+        // size of the file
+        let binary_len = binary
+            .and_then(|b| {
+                let data = std::fs::read(b).ok()?;
+                emulator_image(b, &data, load_address, &value).ok()
+            })
+            .map(|c| c.len() as u64);
+        let mut issues = validate(&value, binary_len);
+        // A binary that has no vector table to read is not an interrupt problem
+        let vectors = binary.and_then(|b| read_vector_table(b).ok());
+        let list = interrupt_list_field(self.form.schema());
+        issues.extend(
+            InterruptList::from_config(&value, list.as_deref())
+                .check(&allowed_policies(&compiled_modes()), vectors.as_ref()),
+        );
+        issues
+    }
+
+    // Open the list of problems, returns false if there are none
+    pub fn open_issues(
+        &mut self,
+        binary: Option<&std::path::Path>,
+        load_address: Option<u64>,
+    ) -> bool {
+        let issues = self.check_config(binary, load_address);
+        if issues.is_empty() {
+            return false;
+        }
+        self.state = ConfigWindowState::Issues(ListState::default().with_selected(Some(0)), issues);
+        true
+    }
+
+    // Show an issue where it can be fixed
+    fn go_to_issue(&mut self, issue: &Issue) {
+        self.state = ConfigWindowState::ManualConfig;
+        if self.config_error.is_some() {
+            self.tab = ConfigTab::Raw;
+            self.jump_to_error();
+        } else {
+            self.tab = ConfigTab::Form;
+            self.form.select_path(&issue.path);
+        }
+    }
+
+    fn render_issues(
+        &mut self,
+        f: &mut ratatui::prelude::Frame,
+        area: ratatui::prelude::Rect,
+    ) -> Option<Vec<Request>> {
+        let ret = self.render_main(f, area);
+        let centered = centered_rect::centered_rect(80, 60, area);
+        if let ConfigWindowState::Issues(ref mut list_state, ref issues) = self.state {
+            let items: Vec<ListItem> = issues
+                .iter()
+                .map(|i| {
+                    let path = if i.path.is_empty() {
+                        "(config)"
+                    } else {
+                        i.path.as_str()
+                    };
+                    let (label, color) = if i.is_error() {
+                        ("error ", Color::Red)
+                    } else {
+                        ("warning ", Color::Yellow)
+                    };
+                    ListItem::new(ratatui::text::Line::from(vec![
+                        ratatui::text::Span::styled(label, Style::default().fg(color)),
+                        ratatui::text::Span::styled(
+                            format!("{} ", path),
+                            Style::default().fg(Color::Cyan),
+                        ),
+                        ratatui::text::Span::raw(i.message.clone()),
+                    ]))
+                })
+                .collect();
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .border_style(Style::default().fg(Color::Indexed(88)))
+                        .title(format!("{} problem(s) in the configuration", issues.len()))
+                        .title_bottom("[Enter] go to setting  [Esc] back"),
+                )
+                .highlight_style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::REVERSED),
+                );
+            f.render_widget(Clear, centered);
+            f.render_stateful_widget(list, centered, list_state);
+        }
+        ret
+    }
+
+    fn open_memory_map(&mut self) -> Option<Vec<Request>> {
+        let value = match self.editable_value("the memory map") {
+            Ok(v) => v,
+            Err(e) => return e,
+        };
+        let list = region_list_field(self.form.schema());
+        match MemoryMapWindow::new(&value, list.as_deref()) {
+            Ok(window) => {
+                self.state = ConfigWindowState::MemoryMap(window);
+                None
+            }
+            Err(e) => Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]),
+        }
+    }
+
+    fn open_interrupts(&mut self, binary: Option<&std::path::Path>) -> Option<Vec<Request>> {
+        let value = match self.editable_value("the interrupts") {
+            Ok(v) => v,
+            Err(e) => return e,
+        };
+        let list = interrupt_list_field(self.form.schema());
+        self.state =
+            ConfigWindowState::Interrupts(InterruptWindow::new(&value, list.as_deref(), binary));
+        None
+    }
+
+    fn open_access_rules(&mut self) -> Option<Vec<Request>> {
+        let value = match self.editable_value("the access rules") {
+            Ok(v) => v,
+            Err(e) => return e,
+        };
+        let list = rules_list_field(self.form.schema());
+        self.state = ConfigWindowState::AccessRules(AccessRulesWindow::new(
+            &value,
+            list.as_deref(),
+            &self.project,
+        ));
+        None
+    }
+
+    // Next to config.json whichever profile is active, where GrammarWindow
+    // and the fuzzer read it
+    fn grammar_path(&self) -> PathBuf {
+        self.project.join("grammar.json")
+    }
+
+    // Convert an ABNF or EBNF file and show what it does to grammar.json
+    fn import_grammar_from(&mut self, path: &std::path::Path) -> Option<Vec<Request>> {
+        let report = match import_grammar(path) {
+            Ok(r) => r,
+            Err(e) => return Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]),
+        };
+        let current = read_to_string(self.grammar_path()).unwrap_or_default();
+        let diff_view = DiffView::new(
+            format!("Import {} as {}", path.display(), report.syntax.name()),
+            &current,
+            &report.grammar.to_json(),
+        )
+        .with_hint("[Enter] replace grammar.json  [Esc] keep it  [PgUp/PgDn] scroll");
+        self.state = ConfigWindowState::PreviewGrammar(report, diff_view);
+        None
+    }
+
+    fn render_import_grammar(
+        &mut self,
+        f: &mut ratatui::prelude::Frame,
+        area: ratatui::prelude::Rect,
+    ) -> Option<Vec<Request>> {
+        let ret = self.render_main(f, area);
+        match self.state {
+            ConfigWindowState::ImportGrammar(ref mut file_dialogue) => {
+                let centered = centered_rect::centered_rect(60, 60, area);
+                f.render_widget(Clear, centered);
+                file_dialogue.render(f, centered);
+            }
+            ConfigWindowState::PreviewGrammar(ref report, ref mut diff_view) => {
+                // What could not be converted, then what is wrong with the result
+                let mut lines: Vec<ratatui::text::Line> = report
+                    .notes
+                    .iter()
+                    .map(|n| ratatui::text::Line::from(n.clone()).fg(Color::Yellow))
+                    .collect();
+                lines.extend(
+                    report
+                        .grammar
+                        .check()
+                        .iter()
+                        .map(|i| ratatui::text::Line::from(i.to_string()).fg(Color::Red)),
+                );
+                let count = lines.len();
+                if lines.is_empty() {
+                    lines.push(
+                        ratatui::text::Line::from("Everything was converted").fg(Color::Green),
+                    );
+                }
+                let centered = centered_rect::centered_rect(80, 80, area);
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Min(5),
+                        Constraint::Length((lines.len() as u16 + 2).min(10)),
+                    ])
+                    .split(centered);
+                diff_view.render(f, chunks[0]);
+                let notes = Paragraph::new(lines)
+                    .wrap(ratatui::widgets::Wrap { trim: false })
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_type(BorderType::Rounded)
+                            .title(format!("Not converted ({})", count))
+                            .style(Style::default().bg(Color::Black)),
+                    );
+                f.render_widget(Clear, chunks[1]);
+                f.render_widget(notes, chunks[1]);
+            }
+            _ => {}
+        }
+        ret
+    }
+
+    // The configuration for one of the editors, which need valid JSON
+    fn editable_value(&self, what: &str) -> Result<serde_json::Value, Option<Vec<Request>>> {
+        validate_json(&self.config_tx.lines().join("\n")).map_err(|e| {
+            Some(vec![Request::Popup(Popup::new(
+                PopupType::Warning,
+                format!("Fix the JSON before editing {}: {}", what, e),
+            ))])
+        })
+    }
+
+    // Take the changes of one of the editors back into the configuration
+    fn close_editor(&mut self) -> Option<Vec<Request>> {
+        let state = std::mem::replace(
+            &mut self.state,
+            ConfigWindowState::Main(ListState::default().with_selected(Some(0))),
+        );
+        let mut value = validate_json(&self.config_tx.lines().join("\n")).ok()?;
+        let (changed, what) = match state {
+            ConfigWindowState::MemoryMap(mut w) => match w.apply(&mut value) {
+                Ok(changed) => (changed, "Memory map"),
+                // Back to the editor, so the regions can be fixed
+                Err(e) => {
+                    w.reopen();
+                    self.state = ConfigWindowState::MemoryMap(w);
+                    return Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]);
+                }
+            },
+            ConfigWindowState::Interrupts(w) => (w.apply(&mut value), "Interrupts"),
+            ConfigWindowState::AccessRules(w) => (w.apply(&mut value), "Access rules"),
+            _ => return None,
+        };
+        if !changed {
+            return None;
+        }
+        self.replace_config(&serde_json::to_string_pretty(&value).unwrap_or_default());
+        Some(vec![Request::Popup(Popup::new(
+            PopupType::Info,
+            format!("{} updated, press Done to save it", what),
+        ))])
+    }
+
     fn save_config(&mut self) -> Result<String, String> {
         let json_str = self.config_tx.lines().join("\n");
         match validate_json(&json_str) {
@@ -213,38 +1274,111 @@ impl<'a> Window for ConfigWindow<'a> {
             ConfigWindowState::Main(_) => false,
             ConfigWindowState::ManualConfig => true,
             ConfigWindowState::FromScript(_) => true,
+            ConfigWindowState::ConfirmScript(_) => true,
+            ConfigWindowState::RunningScript(_) => true,
+            ConfigWindowState::PreviewConfig(_, _) => true,
             ConfigWindowState::SelectBinary(_) => true,
+            ConfigWindowState::BinaryPreview(_, _) => true,
+            ConfigWindowState::LoadAddress(_, _) => true,
             ConfigWindowState::SetPort(_) => true,
+            ConfigWindowState::SetCores(_) => true,
+            ConfigWindowState::Limits(_) => true,
+            ConfigWindowState::EditLimit(_, _) => true,
+            ConfigWindowState::Issues(_, _) => true,
+            ConfigWindowState::MemoryMap(_) => true,
+            ConfigWindowState::Interrupts(_) => true,
+            ConfigWindowState::AccessRules(_) => true,
+            ConfigWindowState::Profiles(_, _) => true,
+            ConfigWindowState::EditProfile(_, _) => true,
+            ConfigWindowState::ImportGrammar(_) => true,
+            ConfigWindowState::PreviewGrammar(_, _) => true,
         }
     }
     fn name(&self) -> &str {
         match self.state {
             ConfigWindowState::Main(_) => "Configuration Menu",
             ConfigWindowState::ManualConfig => "Configuration Menu: Manual config",
-            ConfigWindowState::FromScript(_) => "Configuration Menu: From Script",
+            ConfigWindowState::FromScript(_)
+            | ConfigWindowState::ConfirmScript(_)
+            | ConfigWindowState::RunningScript(_) => "Configuration Menu: From Script",
+            ConfigWindowState::PreviewConfig(_, _) => "Configuration Menu: Review loaded config",
             ConfigWindowState::SelectBinary(_) => "Configuration Menu: Selecting Binary",
+            ConfigWindowState::BinaryPreview(_, _) => "Configuration Menu: Review binary",
+            ConfigWindowState::LoadAddress(_, _) => "Configuration Menu: Load address",
             ConfigWindowState::SetPort(_) => "Configuration Menu: Selecting port",
+            ConfigWindowState::SetCores(_) => "Configuration Menu: Selecting cores",
+            ConfigWindowState::Limits(_) | ConfigWindowState::EditLimit(_, _) => {
+                "Configuration Menu: Campaign limits"
+            }
+            ConfigWindowState::Issues(_, _) => "Configuration Menu: Problems",
+            ConfigWindowState::MemoryMap(_) => "Configuration Menu: Memory map",
+            ConfigWindowState::Interrupts(_) => "Configuration Menu: Interrupts",
+            ConfigWindowState::AccessRules(_) => "Configuration Menu: Memory access rules",
+            ConfigWindowState::Profiles(_, _) | ConfigWindowState::EditProfile(_, _) => {
+                "Configuration Menu: Profiles"
+            }
+            ConfigWindowState::ImportGrammar(_) => "Configuration Menu: Import grammar",
+            ConfigWindowState::PreviewGrammar(_, _) => {
+                "Configuration Menu: Review imported grammar"
+            }
         }
     }
     fn render(
         &mut self,
         f: &mut ratatui::prelude::Frame,
         area: ratatui::prelude::Rect,
+        _state: &AppState,
     ) -> Option<Vec<Request>> {
         match self.state {
             ConfigWindowState::Main(_) | ConfigWindowState::ManualConfig => {
                 self.render_main(f, area)
             }
-            ConfigWindowState::FromScript(_) => self.render_from_script(f, area),
-            ConfigWindowState::SelectBinary(_) => self.render_select_binary(f, area),
-            ConfigWindowState::SetPort(_) => self.render_set_port(f, area),
+            ConfigWindowState::FromScript(_)
+            | ConfigWindowState::ConfirmScript(_)
+            | ConfigWindowState::RunningScript(_)
+            | ConfigWindowState::PreviewConfig(_, _) => self.render_from_script(f, area),
+            ConfigWindowState::SelectBinary(_)
+            | ConfigWindowState::BinaryPreview(_, _)
+            | ConfigWindowState::LoadAddress(_, _) => self.render_select_binary(f, area),
+            ConfigWindowState::SetPort(_) | ConfigWindowState::SetCores(_) => {
+                self.render_set_port(f, area)
+            }
+            ConfigWindowState::Limits(_) | ConfigWindowState::EditLimit(_, _) => {
+                self.render_limits(f, area)
+            }
+            ConfigWindowState::Issues(_, _) => self.render_issues(f, area),
+            ConfigWindowState::MemoryMap(ref mut map_window) => {
+                map_window.render(f, area);
+                None
+            }
+            ConfigWindowState::Interrupts(ref mut irq_window) => {
+                irq_window.render(f, area);
+                None
+            }
+            ConfigWindowState::AccessRules(ref mut rules_window) => {
+                rules_window.render(f, area);
+                None
+            }
+            ConfigWindowState::Profiles(_, _) | ConfigWindowState::EditProfile(_, _) => {
+                self.render_profiles(f, area)
+            }
+            ConfigWindowState::ImportGrammar(_) | ConfigWindowState::PreviewGrammar(_, _) => {
+                self.render_import_grammar(f, area)
+            }
         }
     }
 
-    fn handle_input(&mut self, key: crossterm::event::KeyEvent) -> Option<Vec<Request>> {
+    fn handle_input(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        state: &AppState,
+    ) -> Option<Vec<Request>> {
         let mut ret = None;
         match self.state {
             ConfigWindowState::Main(ref mut l) => match key.code {
+                crossterm::event::KeyCode::F(2) => {
+                    ret = self.switch_tab();
+                }
                 crossterm::event::KeyCode::Up | crossterm::event::KeyCode::Char('j') => {
                     l.select_previous();
                 }
@@ -257,6 +1391,24 @@ impl<'a> Window for ConfigWindow<'a> {
                         self.state = ConfigWindowState::ManualConfig;
                         return None;
                     }
+                    if &self.options[selected_idx] == "Memory Map" {
+                        return self.open_memory_map();
+                    }
+                    if &self.options[selected_idx] == "Interrupts" {
+                        return self.open_interrupts(state.binary_path.as_deref());
+                    }
+                    if &self.options[selected_idx] == "Access Rules" {
+                        return self.open_access_rules();
+                    }
+                    if &self.options[selected_idx] == "Profiles" {
+                        let current = self.profile.clone();
+                        self.show_profiles(&current);
+                        return None;
+                    }
+                    if &self.options[selected_idx] == "Import Grammar" {
+                        self.state = ConfigWindowState::ImportGrammar(FileDialogue::new());
+                        return None;
+                    }
                     if &self.options[selected_idx] == "From script" {
                         self.state = ConfigWindowState::FromScript(FileDialogue::new());
                         return None;
@@ -272,11 +1424,50 @@ impl<'a> Window for ConfigWindow<'a> {
                         ));
                         return None;
                     }
+                    if &self.options[selected_idx] == "Set Cores" {
+                        self.state = ConfigWindowState::SetCores(InputDialogue::new(
+                            "Set Cores".to_string(),
+                            "Cores to fuzz on, e.g. 0-3,8 / all / none:".to_string(),
+                        ));
+                        return None;
+                    }
+                    if &self.options[selected_idx] == "Campaign Limits" {
+                        self.state =
+                            ConfigWindowState::Limits(ListState::default().with_selected(Some(0)));
+                        return None;
+                    }
+                    if &self.options[selected_idx] == "Validate" {
+                        if !self.open_issues(state.binary_path.as_deref(), state.load_address) {
+                            return Some(vec![Request::Popup(Popup::new(
+                                PopupType::Success,
+                                "No problems found in the configuration",
+                            ))]);
+                        }
+                        return None;
+                    }
                     if &self.options[selected_idx] == "Done" {
+                        let issues =
+                            self.check_config(state.binary_path.as_deref(), state.load_address);
+                        if !errors(&issues).is_empty() {
+                            self.open_issues(state.binary_path.as_deref(), state.load_address);
+                            return Some(vec![Request::Popup(Popup::new(
+                                PopupType::Warning,
+                                "Not saved, fix the problems listed first",
+                            ))]);
+                        }
                         let mut ret: Option<Vec<Request>> = None;
                         let mut ret_vec = vec![];
                         match self.save_config() {
                             Ok(msg) => {
+                                let msg = match issues.first() {
+                                    Some(first) => format!(
+                                        "{}, with {} warning(s). First: {}",
+                                        msg,
+                                        issues.len(),
+                                        first
+                                    ),
+                                    None => msg,
+                                };
                                 ret_vec.push(Request::Popup(Popup::new(PopupType::Info, msg)));
                             }
                             Err(msg) => {
@@ -286,18 +1477,17 @@ impl<'a> Window for ConfigWindow<'a> {
                         }
                         #[cfg(feature = "for_fuzzer")]
                         {
-                            println!("{}", self.config_tx.lines().join("\n"));
                             let opts = EmuOpts::from_json(self.config_tx.lines().join("\n"));
-                            if let Ok(opts) = opts {
-                                ret_vec.push(Request::PushProperty(
-                                    "emu_opts".to_string(),
-                                    Box::new(opts) as Box<dyn std::any::Any>,
-                                ));
-                            } else {
-                                ret = Some(vec![Request::Popup(Popup::new(
-                                    PopupType::Warning,
-                                    "Invalid EmuOpts configuration".to_string(),
-                                ))]);
+                            match opts {
+                                Ok(opts) => {
+                                    ret_vec.push(Request::Update(StateUpdate::EmuOpts(opts)))
+                                }
+                                Err(e) => {
+                                    ret = Some(vec![Request::Popup(Popup::new(
+                                        PopupType::Warning,
+                                        format!("Invalid EmuOpts configuration: {}", e),
+                                    ))]);
+                                }
                             }
                         }
                         if ret.is_some() {
@@ -311,7 +1501,7 @@ impl<'a> Window for ConfigWindow<'a> {
                 _ => {}
             },
             ConfigWindowState::ManualConfig => {
-                self.handle_input_manual(key);
+                ret = self.handle_input_manual(key);
             }
 
             ConfigWindowState::FromScript(ref mut file_dialogue) => {
@@ -320,7 +1510,7 @@ impl<'a> Window for ConfigWindow<'a> {
                     FileDialogueResult::Select(input) => {
                         self.state =
                             ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
-                        // Go back to main after submission
+                        ret = self.load_from(&input, state.binary_path.as_deref());
                     }
                     FileDialogueResult::Cancel => {
                         self.state =
@@ -330,17 +1520,122 @@ impl<'a> Window for ConfigWindow<'a> {
                 }
             }
 
-            ConfigWindowState::SelectBinary(ref mut file_dialogue) => {
+            ConfigWindowState::ConfirmScript(ref path) => match key.code {
+                crossterm::event::KeyCode::Char('y') => {
+                    self.state = ConfigWindowState::RunningScript(PendingSource::start(
+                        path.clone(),
+                        state.binary_path.clone(),
+                    ));
+                }
+                crossterm::event::KeyCode::Char('n') | crossterm::event::KeyCode::Esc => {
+                    self.state =
+                        ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                }
+                _ => {}
+            },
+
+            // The script is left to finish or time out on its own
+            ConfigWindowState::RunningScript(_) => {
+                if key.code == crossterm::event::KeyCode::Esc {
+                    self.state =
+                        ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                }
+            }
+
+            ConfigWindowState::PreviewConfig(ref new_config, ref mut diff_view) => {
+                match diff_view.handle_input(key) {
+                    DiffViewResult::Continue => {}
+                    DiffViewResult::Accept => {
+                        let new_config = new_config.clone();
+                        self.replace_config(&new_config);
+                        self.state =
+                            ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                        ret = Some(vec![Request::Popup(Popup::new(
+                            PopupType::Info,
+                            "Configuration replaced, press Done to save it",
+                        ))]);
+                    }
+                    DiffViewResult::Reject => {
+                        self.state =
+                            ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                    }
+                }
+            }
+
+            ConfigWindowState::Profiles(_, _) => {
+                ret = self.handle_input_profiles(key);
+            }
+
+            ConfigWindowState::EditProfile(ref action, ref mut input_dialogue) => {
+                match input_dialogue.handle_input(key) {
+                    InputDialogueResult::Continue => {}
+                    InputDialogueResult::Submit(input) => {
+                        let action = action.clone();
+                        ret = self.submit_profile_name(&action, &input);
+                    }
+                    InputDialogueResult::Cancel => {
+                        let current = self.profile.clone();
+                        self.show_profiles(&current);
+                    }
+                }
+            }
+
+            ConfigWindowState::ImportGrammar(ref mut file_dialogue) => {
                 match file_dialogue.handle_input(key) {
                     FileDialogueResult::Continue => {}
                     FileDialogueResult::Select(input) => {
                         self.state =
                             ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
-                        // Go back to main after submission
-                        ret = Some(vec![Request::PushProperty(
-                            "binary_path".to_string(),
-                            Box::new(input) as Box<dyn std::any::Any>,
-                        )]);
+                        ret = self.import_grammar_from(&input);
+                    }
+                    FileDialogueResult::Cancel => {
+                        self.state =
+                            ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                    }
+                }
+            }
+
+            ConfigWindowState::PreviewGrammar(ref report, ref mut diff_view) => {
+                match diff_view.handle_input(key) {
+                    DiffViewResult::Continue => {}
+                    DiffViewResult::Accept => {
+                        let path = self.grammar_path();
+                        ret = Some(vec![match report.grammar.save(&path) {
+                            Ok(()) if report.notes.is_empty() => Request::Popup(Popup::new(
+                                PopupType::Success,
+                                format!(
+                                    "Imported {} rules into {}",
+                                    report.grammar.rules.len(),
+                                    path.display()
+                                ),
+                            )),
+                            Ok(()) => Request::Popup(Popup::new(
+                                PopupType::Info,
+                                format!(
+                                    "Imported {} rules into {}, {} constructs could not be \
+                                     converted",
+                                    report.grammar.rules.len(),
+                                    path.display(),
+                                    report.notes.len()
+                                ),
+                            )),
+                            Err(e) => Request::Popup(Popup::new(PopupType::Warning, e)),
+                        }]);
+                        self.state =
+                            ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                    }
+                    DiffViewResult::Reject => {
+                        self.state =
+                            ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                    }
+                }
+            }
+
+            ConfigWindowState::SelectBinary(ref mut file_dialogue) => {
+                match file_dialogue.handle_input(key) {
+                    FileDialogueResult::Continue => {}
+                    FileDialogueResult::Select(input) => {
+                        ret = self.preview_binary(input);
                     }
                     FileDialogueResult::Cancel => {
                         self.state =
@@ -349,16 +1644,70 @@ impl<'a> Window for ConfigWindow<'a> {
                     }
                 }
             }
+            ConfigWindowState::BinaryPreview(_, ref mut table_state) => match key.code {
+                crossterm::event::KeyCode::Up | crossterm::event::KeyCode::Char('j') => {
+                    table_state.select_previous();
+                }
+                crossterm::event::KeyCode::Down | crossterm::event::KeyCode::Char('k') => {
+                    table_state.select_next();
+                }
+                crossterm::event::KeyCode::Enter
+                | crossterm::event::KeyCode::Char('y')
+                | crossterm::event::KeyCode::Char('g') => {
+                    let preview = std::mem::replace(
+                        &mut self.state,
+                        ConfigWindowState::Main(ListState::default().with_selected(Some(0))),
+                    );
+                    if let ConfigWindowState::BinaryPreview(info, _) = preview {
+                        ret = if key.code == crossterm::event::KeyCode::Char('g') {
+                            self.map_binary(&info)
+                        } else {
+                            Some(vec![Request::Update(StateUpdate::BinaryPath(info.path))])
+                        };
+                    }
+                }
+                crossterm::event::KeyCode::Esc => {
+                    self.state = ConfigWindowState::SelectBinary(FileDialogue::new());
+                }
+                _ => {}
+            },
+            ConfigWindowState::LoadAddress(ref path, ref mut input_dialogue) => {
+                match input_dialogue.handle_input(key) {
+                    InputDialogueResult::Continue => {}
+                    InputDialogueResult::Submit(input) => match parse_int(&input) {
+                        Ok(addr) => {
+                            ret = Some(vec![
+                                Request::Update(StateUpdate::BinaryPath(path.clone())),
+                                Request::Update(StateUpdate::LoadAddress(addr)),
+                                Request::Popup(Popup::new(
+                                    PopupType::Info,
+                                    format!("{} is loaded at {:#x}", path.display(), addr),
+                                )),
+                            ]);
+                            self.state = ConfigWindowState::Main(
+                                ListState::default().with_selected(Some(0)),
+                            );
+                        }
+                        Err(e) => {
+                            ret = Some(vec![Request::Popup(Popup::new(
+                                PopupType::Warning,
+                                format!("Invalid load address {}: {}", input, e),
+                            ))]);
+                        }
+                    },
+                    InputDialogueResult::Cancel => {
+                        self.state =
+                            ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                    }
+                }
+            }
             ConfigWindowState::SetPort(ref mut input_dialogue) => {
                 match input_dialogue.handle_input(key) {
                     InputDialogueResult::Continue => {}
                     InputDialogueResult::Submit(input) => {
                         // Handle port submission
                         if let Ok(port) = input.parse::<u16>() {
-                            ret = Some(vec![Request::PushProperty(
-                                "port".to_string(),
-                                Box::new(port) as Box<dyn std::any::Any>,
-                            )]);
+                            ret = Some(vec![Request::Update(StateUpdate::Port(port))]);
                         } else {
                             ret = Some(vec![Request::Popup(Popup::new(
                                 PopupType::Warning,
@@ -374,11 +1723,138 @@ impl<'a> Window for ConfigWindow<'a> {
                     }
                 }
             }
+            ConfigWindowState::SetCores(ref mut input_dialogue) => {
+                match input_dialogue.handle_input(key) {
+                    InputDialogueResult::Continue => {}
+                    InputDialogueResult::Submit(input) => {
+                        ret = match parse_core_spec(&input) {
+                            Ok(cores) => Some(vec![
+                                Request::Popup(Popup::new(
+                                    PopupType::Info,
+                                    format!("Fuzzing on cores: {}", format_core_spec(&cores)),
+                                )),
+                                Request::Update(StateUpdate::Cores(cores)),
+                            ]),
+                            Err(e) => Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]),
+                        };
+                        self.state =
+                            ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                    }
+                    InputDialogueResult::Cancel => {
+                        self.state =
+                            ConfigWindowState::Main(ListState::default().with_selected(Some(0)));
+                    }
+                }
+            }
+            ConfigWindowState::Limits(ref mut l) => match key.code {
+                crossterm::event::KeyCode::Up | crossterm::event::KeyCode::Char('j') => {
+                    l.select_previous();
+                }
+                crossterm::event::KeyCode::Down | crossterm::event::KeyCode::Char('k') => {
+                    l.select_next();
+                }
+                crossterm::event::KeyCode::Enter => {
+                    let kind =
+                        LimitKind::ALL[l.selected().unwrap_or(0).min(LimitKind::ALL.len() - 1)];
+                    self.state = ConfigWindowState::EditLimit(
+                        kind,
+                        InputDialogue::new(kind.label().to_string(), kind.hint().to_string()),
+                    );
+                }
+                crossterm::event::KeyCode::Delete | crossterm::event::KeyCode::Backspace => {
+                    let kind =
+                        LimitKind::ALL[l.selected().unwrap_or(0).min(LimitKind::ALL.len() - 1)];
+                    ret = self.update_limit(kind, None);
+                }
+                crossterm::event::KeyCode::Esc => {
+                    self.state =
+                        ConfigWindowState::Main(ListState::default().with_selected(Some(0)))
+                }
+                _ => {}
+            },
+            ConfigWindowState::Issues(ref mut l, ref issues) => match key.code {
+                crossterm::event::KeyCode::Up | crossterm::event::KeyCode::Char('j') => {
+                    l.select_previous();
+                }
+                crossterm::event::KeyCode::Down | crossterm::event::KeyCode::Char('k') => {
+                    l.select_next();
+                }
+                crossterm::event::KeyCode::Enter => {
+                    if let Some(issue) = l.selected().and_then(|i| issues.get(i)).cloned() {
+                        self.go_to_issue(&issue);
+                    }
+                }
+                crossterm::event::KeyCode::Esc => {
+                    self.state =
+                        ConfigWindowState::Main(ListState::default().with_selected(Some(0)))
+                }
+                _ => {}
+            },
+            ConfigWindowState::MemoryMap(ref mut map_window) => {
+                ret = map_window.handle_input(key);
+                if map_window.is_closed() {
+                    ret = self.close_editor();
+                }
+            }
+            ConfigWindowState::Interrupts(ref mut irq_window) => {
+                ret = irq_window.handle_input(key);
+                if irq_window.is_closed() {
+                    ret = self.close_editor();
+                }
+            }
+            ConfigWindowState::AccessRules(ref mut rules_window) => {
+                ret = rules_window.handle_input(key);
+                if rules_window.is_closed() {
+                    ret = self.close_editor();
+                }
+            }
+            ConfigWindowState::EditLimit(kind, ref mut input_dialogue) => {
+                let idx = LimitKind::ALL.iter().position(|k| *k == kind);
+                match input_dialogue.handle_input(key) {
+                    InputDialogueResult::Continue => {}
+                    InputDialogueResult::Submit(input) => {
+                        self.state =
+                            ConfigWindowState::Limits(ListState::default().with_selected(idx));
+                        ret = match CampaignLimits::parse(kind, &input) {
+                            Ok(value) => self.update_limit(kind, value),
+                            Err(e) => Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]),
+                        };
+                    }
+                    InputDialogueResult::Cancel => {
+                        self.state =
+                            ConfigWindowState::Limits(ListState::default().with_selected(idx));
+                    }
+                }
+            }
         }
         return ret;
     }
 }
 
+// What a new profile starts with, like a new project's config.json
+#[cfg(feature = "for_fuzzer")]
+fn fresh_config() -> String {
+    serde_json::to_string_pretty(&EmuOpts::default()).unwrap_or_default()
+}
+
+#[cfg(not(feature = "for_fuzzer"))]
+fn fresh_config() -> String {
+    "{}".to_string()
+}
+
+// The form's fields come from the emulator options; without them, from
+// whatever config.json holds
+#[cfg(feature = "for_fuzzer")]
+fn config_schema() -> Schema {
+    Schema::emu_opts()
+}
+
+#[cfg(not(feature = "for_fuzzer"))]
+fn config_schema() -> Schema {
+    let current = read_to_string("config.json").unwrap_or_default();
+    Schema::from_defaults(validate_json(&current).unwrap_or_default())
+}
+
 pub fn validate_json(json_str: &str) -> Result<serde_json::Value, serde_json::Error> {
     // Simple validation for JSON format
     serde_json::from_str::<serde_json::Value>(json_str)
@@ -407,3 +1883,168 @@ pub fn get_tx_block<'a, 'b>(json_str: &'b str) -> Block<'a> {
             .style(Style::default().fg(Color::White).bg(Color::Black))
     }
 }
+
+// What an ELF file loads where, and the memory map that would hold it
+fn render_binary_preview(
+    f: &mut ratatui::prelude::Frame,
+    area: ratatui::prelude::Rect,
+    info: &BinaryInfo,
+    table_state: &mut TableState,
+) {
+    let elf = &info.elf;
+    let mut lines = vec![
+        ratatui::text::Line::from(format!("{}", info.path.display())).fg(Color::Cyan),
+        ratatui::text::Line::from(format!(
+            "{}, entry {:#010x}, {} symbol(s)",
+            elf.describe(),
+            elf.entry,
+            elf.symbols.len()
+        )),
+    ];
+    let mut proposed: Vec<String> = info
+        .proposal
+        .regions
+        .iter()
+        .map(|r| {
+            format!(
+                "{} {:#x}-{:#x} {}",
+                r.name,
+                r.start,
+                r.start + r.size,
+                r.perms
+            )
+        })
+        .collect();
+    if let Some(sp) = info.proposal.initial_sp {
+        proposed.push(format!("initial SP {:#x}", sp));
+    }
+    lines.push(ratatui::text::Line::from(format!(
+        "Proposed map: {}",
+        proposed.join(", ")
+    )));
+    lines.extend(
+        info.proposal
+            .notes
+            .iter()
+            .map(|n| ratatui::text::Line::from(n.clone()).fg(Color::Yellow)),
+    );
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(lines.len() as u16 + 2),
+            Constraint::Length((elf.segments.len() as u16 + 3).min(12)),
+            Constraint::Min(4),
+        ])
+        .split(area);
+    let block = |title: String| {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(title)
+            .style(Style::default().bg(Color::Black))
+    };
+    let header = |cells: Vec<&'static str>| {
+        Row::new(cells).style(Style::default().add_modifier(Modifier::BOLD))
+    };
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .block(block("Binary".to_string())),
+        chunks[0],
+    );
+
+    let hex = |n: u64| format!("{:#010x}", n);
+    let segments = Table::new(
+        elf.segments.iter().map(|s| {
+            Row::new(vec![
+                s.type_name(),
+                hex(s.offset),
+                hex(s.vaddr),
+                hex(s.paddr),
+                hex(s.filesz),
+                hex(s.memsz),
+                s.perms().to_string(),
+            ])
+        }),
+        [
+            Constraint::Length(12),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(4),
+        ],
+    )
+    .header(header(vec![
+        "Type", "Offset", "VirtAddr", "PhysAddr", "FileSiz", "MemSiz", "Flg",
+    ]))
+    .block(block(format!("Program headers ({})", elf.segments.len())));
+    f.render_widget(segments, chunks[1]);
+
+    let sections = Table::new(
+        elf.sections.iter().map(|s| {
+            let row = Row::new(vec![
+                s.name.clone(),
+                s.type_name(),
+                hex(s.addr),
+                hex(s.size),
+                s.flag_string(),
+            ]);
+            if s.is_alloc() {
+                row
+            } else {
+                row.style(Style::default().fg(Color::DarkGray))
+            }
+        }),
+        [
+            Constraint::Fill(1),
+            Constraint::Length(15),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(5),
+        ],
+    )
+    .header(header(vec!["Name", "Type", "Addr", "Size", "Flg"]))
+    .row_highlight_style(
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::REVERSED),
+    )
+    .block(
+        block(format!("Sections ({})", elf.sections.len()))
+            .title_bottom("[Enter] use binary  [g] use it and map its memory  [Esc] back"),
+    );
+    f.render_stateful_widget(sections, chunks[2], table_state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::profiles::PROFILES_DIR;
+    use crate::grammar::rules::Grammar;
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    #[test]
+    fn test_import_grammar_with_profile_active() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path();
+        std::fs::write(project.join("config.json"), "{}").unwrap();
+        create_profile(project, "irq", "{}").unwrap();
+        set_active_profile(project, "irq").unwrap();
+        let abnf = project.join("request.abnf");
+        std::fs::write(&abnf, "request = \"GET\" / \"POST\"\n").unwrap();
+
+        let mut window = ConfigWindow::in_project(project);
+        assert_eq!(window.profile, "irq");
+        window.import_grammar_from(&abnf);
+        window.handle_input(KeyEvent::from(KeyCode::Enter), &AppState::default());
+
+        // The profile's folder is not where the grammar is read from
+        assert!(!project.join(PROFILES_DIR).join("grammar.json").exists());
+        let grammar = Grammar::load(&project.join("grammar.json")).unwrap();
+        assert_eq!(grammar.rules.len(), 2);
+    }
+}
@@ -1,88 +1,89 @@
 #![cfg(feature = "for_fuzzer")]
-use libc;
-use ratatui::widgets::{Block, Borders, Paragraph};
-use std::{
-    any::Any,
-    collections::HashMap,
-    path::PathBuf,
-    sync::{Arc, RwLock},
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Cell, Clear, LineGauge, Paragraph, Row, Table, Wrap},
 };
+use std::{path::Path, sync::Arc};
 
 use crate::{
     app::Request,
+    campaign::{
+        cores::{format_core_spec, parse_core_spec},
+        history::HistoryZoom,
+        limits::CampaignLimits,
+        runner::{CampaignRunner, LaunchParams},
+        session::SessionStatus,
+        stale::{apply_action, check_binary, record_fingerprint, StaleAction, StaleBinary},
+        stats::{format_count, format_duration},
+    },
+    config::validate::{errors, validate},
+    firmware::{
+        fingerprint::BinaryFingerprint,
+        loader::{emulator_image, ImageFormat},
+    },
     popup::{Popup, PopupType},
+    state::{AppState, Missing, StateUpdate},
+    utils::{
+        centered_rect::centered_rect,
+        input_dialogue::{InputDialogue, InputDialogueResult},
+        log_pane::LogPane,
+        stats_chart::render_history_charts,
+    },
     window::Window,
+    windows::config::ConfigWindow,
 };
 
-use flashfuzzemu::opts::EmuOpts;
-use flashfuzzer::fuzz;
-
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent};
 
 pub struct FuzzingWindow {
-    pub context: Arc<RwLock<FuzzingWindowCtx>>,
-    pub properties: HashMap<String, Box<dyn Any>>,
+    pub runner: CampaignRunner,
     pub started: bool,
-    pub str: String,
+    pub selected_client: usize,
+    pub cores_input: Option<InputDialogue<'static>>,
+    pub log_pane: LogPane<'static>,
+    pub show_charts: bool,
+    pub zoom: HistoryZoom,
+    // Asks what to do with inputs found against an older binary
+    pub stale: Option<StaleBinary>,
+    // The user chose for the current binary, reset once a campaign starts
+    pub stale_answered: bool,
 }
 
 impl FuzzingWindow {
     pub fn new() -> Self {
         Self {
-            context: Arc::new(RwLock::new(FuzzingWindowCtx {})),
-            properties: HashMap::new(),
+            runner: CampaignRunner::new(),
             started: false,
-            str: "Nothing".into(),
+            selected_client: 0,
+            cores_input: None,
+            log_pane: LogPane::new(),
+            show_charts: false,
+            zoom: HistoryZoom::Last5Min,
+            stale: None,
+            stale_answered: false,
         }
     }
-    fn try_start(&mut self) -> Option<Vec<Request>> {
-        self.str.push_str("Trying to start...\n".into());
-        let opts = match self.properties.get("emu_opts") {
-            None => {
-                return Some(vec![Request::GetProperty("emu_opts".into())]);
-            }
-            Some(o) => match (*o).downcast_ref::<EmuOpts>() {
-                None => {
-                    return Some(vec![Request::Popup(Popup::new(
-                        PopupType::Warning,
-                        "Unexpected Type for emu_opts",
-                    ))]);
-                }
-                Some(o) => o.clone(),
-            },
-        };
-        self.str.push_str("Getting port...\n".into());
-        let port = match self.properties.get("port") {
-            None => {
-                return Some(vec![Request::GetProperty("port".into())]);
-            }
-            Some(p) => match (*p).downcast_ref::<u16>() {
-                None => {
-                    return Some(vec![Request::Popup(Popup::new(
-                        PopupType::Warning,
-                        "Unexpected Type for port",
-                    ))]);
-                }
-                Some(p) => *p,
-            },
+    // Send the user to the config window for whatever is missing
+    fn prompt_missing(missing: Missing) -> Option<Vec<Request>> {
+        Some(vec![
+            Request::PopWindow,
+            Request::PushWindow(Box::new(ConfigWindow::new())),
+            Request::Popup(Popup::new(PopupType::Info, missing.prompt())),
+        ])
+    }
+
+    fn try_start(&mut self, state: &AppState) -> Option<Vec<Request>> {
+        let opts = match state.require_emu_opts() {
+            Ok(o) => o.clone(),
+            Err(m) => return Self::prompt_missing(m),
         };
-        self.str.push_str("Getting binary...\n".into());
-        let binary = match self.properties.get("binary_path") {
-            None => {
-                return Some(vec![Request::GetProperty("binary_path".into())]);
-            }
-            Some(b) => match (*b).downcast_ref::<PathBuf>() {
-                None => {
-                    return Some(vec![Request::Popup(Popup::new(
-                        PopupType::Warning,
-                        "Unexpected Type for binary_path",
-                    ))]);
-                }
-                Some(b) => b.clone(),
-            },
+        let binary = match state.require_binary() {
+            Ok(b) => b.clone(),
+            Err(m) => return Self::prompt_missing(m),
         };
-        self.str.push_str("Done!\n".into());
-        let mut contents = match std::fs::read(&binary) {
+        let port = state.port;
+        let cores = state.cores.clone();
+        let data = match std::fs::read(&binary) {
             Ok(c) => c,
             Err(e) => {
                 return Some(vec![Request::Popup(Popup::new(
@@ -91,28 +92,435 @@ impl FuzzingWindow {
                 ))]);
             }
         };
-        self.str = format!(
-            "Try start: opts: {:?}, port: {}, binary: {:?}",
-            opts, port, binary
-        );
-        unsafe {
-            // TODO: Why fork why not thread
-            let pid = libc::fork();
-            if pid < 0 {
-                return Some(vec![Request::Popup(Popup::new(
+        if state.load_address.is_none() && ImageFormat::detect(&binary, &data) == ImageFormat::Raw {
+            return Self::prompt_missing(Missing::LoadAddress);
+        }
+        // The emulator gets the image laid out from the flash base
+        let value = serde_json::to_value(&opts).unwrap_or_default();
+        let contents = match emulator_image(&binary, &data, state.load_address, &value) {
+            Ok(c) => c,
+            Err(e) => {
+                return Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]);
+            }
+        };
+        // Refuse to fuzz with a config that is known to be wrong
+        let issues = validate(&value, Some(contents.len() as u64));
+        let problems = errors(&issues);
+        if !problems.is_empty() {
+            let mut config = ConfigWindow::new();
+            config.open_issues(Some(&binary), state.load_address);
+            return Some(vec![
+                Request::PopWindow,
+                Request::PushWindow(Box::new(config)),
+                Request::Popup(Popup::new(
                     PopupType::Warning,
-                    "Fork failed".to_string(),
-                ))]);
+                    format!(
+                        "The configuration has {} problem(s), fix them before fuzzing. First: {}",
+                        problems.len(),
+                        problems[0]
+                    ),
+                )),
+            ]);
+        }
+        let limits = match CampaignLimits::load(Path::new(".")) {
+            Ok(l) => l,
+            Err(e) => {
+                return Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]);
             }
-            if pid == 0 {
-                let _ = fuzz(&mut contents, opts, port as u64);
+        };
+        // Inputs of campaigns against another build may no longer crash or
+        // cover anything, ask before fuzzing on top of them
+        let fingerprint = BinaryFingerprint::of(&data);
+        if !self.stale_answered {
+            if let Some(stale) = check_binary(Path::new("."), &fingerprint) {
+                self.stale = Some(stale);
+                // Started for real once the prompt is answered
+                self.started = true;
+                return None;
             }
-            self.started = true;
-            return None;
         }
+        self.runner.note(format!(
+            "Fuzzing binary {} ({})",
+            binary.display(),
+            fingerprint.describe()
+        ));
+        self.selected_client = 0;
+        self.log_pane.reset();
+        let launch = LaunchParams {
+            contents,
+            opts,
+            port,
+        };
+        // Only try once, failures are reported and [r] tries again
+        self.started = true;
+        let mut ret = vec![Request::Update(StateUpdate::Campaign(Arc::downgrade(
+            &self.runner.context,
+        )))];
+        match self.runner.start(launch, &cores, limits) {
+            Ok(id) => {
+                self.stale_answered = false;
+                if let Err(e) = record_fingerprint(Path::new("."), &id, &fingerprint) {
+                    self.runner.note(e);
+                }
+            }
+            Err(e) => ret.push(Request::Popup(Popup::new(PopupType::Warning, e))),
+        }
+        Some(ret)
+    }
+
+    // Enter keeps the inputs as they are, Esc leaves the campaign stopped
+    fn answer_stale(&mut self, key: KeyEvent) -> Option<Vec<Request>> {
+        let stale = self.stale.as_ref()?;
+        let action = match key.code {
+            KeyCode::Char(c) => StaleAction::ALL.into_iter().find(|a| a.key() == c),
+            KeyCode::Enter => None,
+            KeyCode::Esc => {
+                self.stale = None;
+                self.runner
+                    .note("Campaign not started, press [r] to choose again");
+                return None;
+            }
+            _ => return None,
+        };
+        match action {
+            Some(action) => match apply_action(Path::new("."), stale, action) {
+                Ok(msg) => self.runner.note(msg),
+                Err(e) => {
+                    // Leave the prompt up to choose again
+                    return Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]);
+                }
+            },
+            None if key.code == KeyCode::Enter => self
+                .runner
+                .note("Keeping the corpus and crashes of the old binary"),
+            None => return None,
+        }
+        self.stale = None;
+        self.stale_answered = true;
+        self.started = false;
+        None
+    }
+
+    fn restart(&mut self) {
+        if !self.runner.request_restart() {
+            // Nothing running, start again on the next tick
+            self.started = false;
+        }
+    }
+
+    fn restart_selected(&mut self) -> Option<Vec<Request>> {
+        if let Err(e) = self.runner.restart_client(self.selected_client) {
+            return Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]);
+        }
+        None
+    }
+
+    // New core list, used from the next (re)start of the campaign
+    fn set_cores(&mut self, spec: &str) -> Option<Vec<Request>> {
+        match parse_core_spec(spec) {
+            Ok(cores) => {
+                self.runner.note(format!(
+                    "Cores set to {}, press [r] to restart the campaign with them",
+                    format_core_spec(&cores)
+                ));
+                Some(vec![Request::Update(StateUpdate::Cores(cores))])
+            }
+            Err(e) => Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]),
+        }
+    }
+
+    fn select_client(&mut self, forward: bool) {
+        let n = self.runner.context.read().unwrap().clients.len().max(1);
+        self.selected_client = if forward {
+            (self.selected_client + 1) % n
+        } else {
+            (self.selected_client + n - 1) % n
+        };
+    }
+
+    // Drive the runner and turn a campaign ended by its limits into a popup
+    fn poll_session(&mut self) -> Option<Vec<Request>> {
+        let summary = self.runner.poll();
+        if self.runner.take_restart() {
+            self.started = false;
+        }
+        let summary = summary?;
+        self.runner.context.read().unwrap().finish_reason.as_ref()?;
+        Some(vec![Request::Popup(Popup::new(
+            PopupType::Success,
+            format!(
+                "Campaign {} finished: {}\n{} execs, {} corpus entries, {} crashes",
+                summary.campaign_id,
+                summary.reason,
+                format_count(summary.execs),
+                summary.corpus_count,
+                summary.crashes
+            ),
+        ))])
+    }
+
+    fn render_stale(&self, f: &mut Frame, area: Rect) {
+        let Some(stale) = &self.stale else {
+            return;
+        };
+        let mut lines = vec![
+            Line::from(stale.describe()),
+            Line::from(""),
+            Line::from(format!("Old: {}", stale.previous.describe())),
+            Line::from(format!("New: {}", stale.current.describe())),
+            Line::from(""),
+        ];
+        for action in StaleAction::ALL {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("[{}] {}", action.key(), action.label()),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::raw(format!(": {}", action.hint())),
+            ]));
+        }
+        lines.push(Line::from(
+            "[Enter] keep everything and fuzz  [Esc] do not start",
+        ));
+        let para = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .title("Binary changed")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded),
+        );
+        let centered = centered_rect(70, 40, area);
+        f.render_widget(Clear, centered);
+        f.render_widget(para, centered);
+    }
+
+    // One gauge per configured limit
+    fn render_limits(&self, f: &mut Frame, area: Rect) {
+        let ctx = self.runner.context.read().unwrap();
+        let progress = ctx.limits.progress(&ctx.stats);
+        let block = Block::default()
+            .title("Limits")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(1); progress.len()])
+            .split(inner);
+        for (p, row) in progress.iter().zip(rows.iter()) {
+            let color = if p.ratio >= 1.0 {
+                Color::Red
+            } else if p.ratio >= 0.8 {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            let gauge = LineGauge::default()
+                .filled_style(Style::default().fg(color))
+                .label(format!("{:>30}: {}", p.kind.label(), p.label))
+                .ratio(p.ratio);
+            f.render_widget(gauge, *row);
+        }
+    }
+
+    fn render_clients(&self, f: &mut Frame, area: Rect) {
+        let ctx = self.runner.context.read().unwrap();
+        let status_style = |status: Option<SessionStatus>| match status {
+            Some(SessionStatus::Running) => Style::default().fg(Color::Green),
+            Some(SessionStatus::Paused) | Some(SessionStatus::Stopping) => {
+                Style::default().fg(Color::Yellow)
+            }
+            _ => Style::default().fg(Color::Red),
+        };
+        let mut rows: Vec<Row> = ctx
+            .clients
+            .iter()
+            .map(|c| {
+                let status = match c.status() {
+                    None => "Not started".to_string(),
+                    Some(_) if ctx.restart_requested => "Restarting".to_string(),
+                    Some(s) => s.describe(),
+                };
+                let uptime = c
+                    .session
+                    .as_ref()
+                    .map_or("-".to_string(), |s| format!("{}s", s.uptime().as_secs()));
+                let row = Row::new(vec![
+                    Cell::from(c.id.to_string()),
+                    Cell::from(c.core.map_or("-".to_string(), |c| c.to_string())),
+                    Cell::from(
+                        c.session
+                            .as_ref()
+                            .map_or("-".to_string(), |s| s.pid().to_string()),
+                    ),
+                    Cell::from(status).style(status_style(c.status())),
+                    Cell::from(format!("{:.1}", c.stats.execs_per_sec)),
+                    Cell::from(format_count(c.stats.executions)),
+                    Cell::from(uptime),
+                    Cell::from(c.restarts.to_string()),
+                ]);
+                if c.id == self.selected_client && ctx.clients.len() > 1 {
+                    row.style(Style::default().add_modifier(Modifier::REVERSED))
+                } else {
+                    row
+                }
+            })
+            .collect();
+        let alive = ctx.clients.iter().filter(|c| c.is_alive()).count();
+        rows.push(
+            Row::new(vec![
+                Cell::from("all"),
+                Cell::from(""),
+                Cell::from(""),
+                Cell::from(format!("{}/{} alive", alive, ctx.clients.len())),
+                Cell::from(format!("{:.1}", ctx.stats.execs_per_sec)),
+                Cell::from(format_count(ctx.stats.executions)),
+                Cell::from(""),
+                Cell::from(
+                    ctx.clients
+                        .iter()
+                        .map(|c| c.restarts)
+                        .sum::<u32>()
+                        .to_string(),
+                ),
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        );
+        let header = Row::new(vec![
+            "client", "core", "PID", "status", "execs/s", "execs", "uptime", "restarts",
+        ])
+        .style(Style::default().fg(Color::Cyan));
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Length(5),
+                Constraint::Length(8),
+                Constraint::Fill(2),
+                Constraint::Length(10),
+                Constraint::Length(9),
+                Constraint::Length(8),
+                Constraint::Length(8),
+            ],
+        )
+        .header(header)
+        .block(
+            Block::default()
+                .title("Clients")
+                .title_bottom(
+                    "[s] stop  [p] pause/resume  [r] restart  [Tab] select  [K] stop client  [R] restart client  [C] cores  [c] charts/log  [z] zoom"
+                        .dark_gray(),
+                )
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded),
+        );
+        f.render_widget(table, area);
+    }
+
+    // AFL-style status screen, redrawn from the shared context on every tick
+    fn render_stats(&self, f: &mut Frame, area: Rect) {
+        let stats = self.runner.context.read().unwrap().stats.clone();
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(5), Constraint::Length(5)])
+            .split(area);
+        let top = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(rows[0]);
+        let bottom = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(rows[1]);
+
+        let since = |d: Option<std::time::Duration>| match d {
+            Some(d) => format_duration(d),
+            None => "none seen yet".to_string(),
+        };
+        let crash_style = if stats.crashes > 0 {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+
+        let timing = vec![
+            stat_line(
+                "run time",
+                format_duration(stats.run_time()),
+                Style::default(),
+            ),
+            stat_line(
+                "last new path",
+                since(stats.since_last_new_path()),
+                Style::default(),
+            ),
+            stat_line(
+                "last crash",
+                since(stats.since_last_crash()),
+                Style::default(),
+            ),
+        ];
+        let results = vec![
+            stat_line(
+                "corpus count",
+                format_count(stats.corpus_size),
+                Style::default(),
+            ),
+            stat_line("crashes", format_count(stats.crashes), crash_style),
+            stat_line("timeouts", format_count(stats.timeouts), Style::default()),
+        ];
+        let progress = vec![
+            stat_line(
+                "total execs",
+                format_count(stats.executions),
+                Style::default(),
+            ),
+            stat_line(
+                "exec speed",
+                format!("{:.1}/sec", stats.execs_per_sec),
+                Style::default(),
+            ),
+        ];
+        #[allow(unused_mut)]
+        let mut coverage = vec![stat_line(
+            "clients",
+            stats.clients.to_string(),
+            Style::default(),
+        )];
+        #[cfg(feature = "track_unique_bb")]
+        coverage.push(stat_line(
+            "unique bbs",
+            stats.unique_bbs.map_or("-".to_string(), format_count),
+            Style::default(),
+        ));
+
+        f.render_widget(stats_para("process timing", timing), top[0]);
+        f.render_widget(stats_para("overall results", results), top[1]);
+        f.render_widget(stats_para("stage progress", progress), bottom[0]);
+        f.render_widget(stats_para("coverage", coverage), bottom[1]);
     }
 }
 
+fn stat_line(label: &str, value: String, style: Style) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            format!("{:>14} : ", label),
+            Style::default().fg(Color::Cyan),
+        ),
+        Span::styled(value, style),
+    ])
+}
+
+fn stats_para<'a>(title: &'a str, lines: Vec<Line<'a>>) -> Paragraph<'a> {
+    Paragraph::new(lines).block(
+        Block::default()
+            .title(title)
+            .title_style(Style::default().fg(Color::Yellow))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded),
+    )
+}
+
 impl Window for FuzzingWindow {
     fn name(&self) -> &str {
         "Fuzzing"
@@ -121,58 +529,98 @@ impl Window for FuzzingWindow {
         &mut self,
         f: &mut ratatui::Frame,
         area: ratatui::prelude::Rect,
+        state: &AppState,
     ) -> Option<Vec<Request>> {
-        // Render logic for the fuzzing window
-        let para = Paragraph::new(self.str.clone()).block(
-            Block::default()
-                .title("Fuzzing Window")
-                .borders(Borders::ALL),
-        );
-        f.render_widget(para, area);
+        let ret = self.poll_session();
+        // Header, one row per client, the totals row and the borders
+        let clients_height =
+            (self.runner.context.read().unwrap().clients.len().max(1) as u16 + 4).min(12);
+        // One gauge per configured limit plus the borders
+        let limits_height = match self.runner.context.read().unwrap().limits.count() {
+            0 => 0,
+            n => n as u16 + 2,
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(clients_height),
+                Constraint::Length(limits_height),
+                Constraint::Length(10),
+                Constraint::Fill(1),
+            ])
+            .split(area);
+        self.render_clients(f, chunks[0]);
+        if limits_height > 0 {
+            self.render_limits(f, chunks[1]);
+        }
+        self.render_stats(f, chunks[2]);
+        if self.show_charts {
+            let samples = self
+                .runner
+                .context
+                .read()
+                .unwrap()
+                .history
+                .samples(self.zoom);
+            render_history_charts(f, chunks[3], &samples, self.zoom);
+        } else {
+            let ctx = self.runner.context.read().unwrap();
+            self.log_pane.render(f, chunks[3], &ctx.log);
+        }
+        if let Some(input) = self.cores_input.as_mut() {
+            input.render(f, centered_rect(40, 20, area));
+        }
+        self.render_stale(f, area);
+        if ret.is_some() {
+            return ret;
+        }
         if !self.started {
-            return self.try_start();
+            return self.try_start(state);
         }
         None
     }
-    fn handle_input(&mut self, _key: KeyEvent) -> Option<Vec<crate::app::Request>> {
-        // Handle input for the fuzzing window
+    fn handle_input(&mut self, key: KeyEvent, _state: &AppState) -> Option<Vec<Request>> {
+        if self.stale.is_some() {
+            return self.answer_stale(key);
+        }
+        if let Some(input) = self.cores_input.as_mut() {
+            match input.handle_input(key) {
+                InputDialogueResult::Continue => {}
+                InputDialogueResult::Submit(spec) => {
+                    self.cores_input = None;
+                    return self.set_cores(&spec);
+                }
+                InputDialogueResult::Cancel => self.cores_input = None,
+            }
+            return None;
+        }
+        if !self.show_charts {
+            let ctx = self.runner.context.read().unwrap();
+            if self.log_pane.handle_input(key, &ctx.log) {
+                return None;
+            }
+        }
+        match key.code {
+            KeyCode::Char('s') => self.runner.stop(),
+            KeyCode::Char('p') => self.runner.toggle_pause(),
+            KeyCode::Char('r') => self.restart(),
+            KeyCode::Tab => self.select_client(true),
+            KeyCode::BackTab => self.select_client(false),
+            KeyCode::Char('K') => self.runner.stop_client(self.selected_client),
+            KeyCode::Char('R') => return self.restart_selected(),
+            KeyCode::Char('C') => {
+                self.cores_input = Some(InputDialogue::new(
+                    "Cores".to_string(),
+                    "Cores to fuzz on, e.g. 0-3,8 / all / none".to_string(),
+                ))
+            }
+            KeyCode::Char('c') => self.show_charts = !self.show_charts,
+            KeyCode::Char('z') => self.zoom = self.zoom.next(),
+            _ => {}
+        }
         None
     }
-    fn send_property(&mut self, name: String, property: &dyn Any) {
-        self.str.push_str(&format!(
-            "Property received: {} - {:?}\n",
-            name,
-            std::any::type_name_of_val(property)
-        ));
-        let boxed: Option<Box<dyn Any>> = if let Some(p) = property.downcast_ref::<PathBuf>() {
-            Some(Box::new(p.clone()))
-        } else if let Some(p) = property.downcast_ref::<u16>() {
-            self.str
-                .push_str(&format!("Property {} set successfully as u16.\n", &name));
-            Some(Box::new(*p))
-        } else if let Some(p) = property.downcast_ref::<u64>() {
-            self.str
-                .push_str(&format!("Property {} set successfully as u64.\n", &name));
-            Some(Box::new(*p))
-        } else if let Some(p) = property.downcast_ref::<String>() {
-            self.str
-                .push_str(&format!("Property {} set successfully as String.\n", &name));
-
-            Some(Box::new(p.clone()))
-        } else if let Some(p) = property.downcast_ref::<EmuOpts>() {
-            Some(Box::new(p.clone()))
-        } else {
-            self.str
-                .push_str(&format!("Unsupported property type for: {}\n", name));
-            None
-        };
-
-        if let Some(val) = boxed {
-            self.properties.insert(name.clone(), val);
-            self.str
-                .push_str(&format!("Property {} set successfully.\n", &name));
-        }
+    fn capture_all_input(&self) -> bool {
+        self.log_pane.is_searching() || self.cores_input.is_some() || self.stale.is_some()
     }
 }
-
-pub struct FuzzingWindowCtx {}
@@ -0,0 +1,618 @@
+// windows/grammar_window.rs
+use crate::app::Request;
+use crate::grammar::generate::{export_samples, generate_samples, Rng};
+use crate::grammar::import::import_grammar;
+use crate::grammar::rules::{is_valid_name, parse_rhs, Grammar, GrammarIssue, Production, Symbol};
+use crate::popup::{Popup, PopupType};
+use crate::state::AppState;
+use crate::utils::centered_rect::centered_rect;
+use crate::utils::file_dialogue::{FileDialogue, FileDialogueResult};
+use crate::utils::input_dialogue::{InputDialogue, InputDialogueResult};
+use crate::window::Window;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_SAMPLES: usize = 10;
+
+// A line of the rule tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TreeRow {
+    NonTerminal(String),
+    // Referenced but without rules
+    Undefined(String),
+    Rule(usize),
+}
+
+enum GrammarState<'a> {
+    Main,
+    // Editing a rule, None when adding one
+    Edit(Option<usize>, InputDialogue<'a>),
+    Generate(InputDialogue<'a>),
+    Samples(Vec<String>, ListState),
+    Import(FileDialogue),
+}
+
+pub struct GrammarWindow<'a> {
+    path: PathBuf,
+    grammar: Grammar,
+    // grammar.json did not parse, saving would throw it away
+    load_error: Option<String>,
+    issues: Vec<GrammarIssue>,
+    // What the last ABNF/EBNF import could not convert
+    import_notes: Vec<String>,
+    collapsed: HashSet<String>,
+    list_state: ListState,
+    state: GrammarState<'a>,
+    changed: bool,
+    // Esc was pressed once with unsaved changes
+    confirm_discard: bool,
+}
+
+impl<'a> GrammarWindow<'a> {
+    pub fn new() -> Self {
+        Self::open(Path::new("grammar.json"))
+    }
+
+    pub fn open(path: &Path) -> Self {
+        let (grammar, load_error) = match Grammar::load(path) {
+            Ok(g) => (g, None),
+            Err(e) => (Grammar::default(), Some(e)),
+        };
+        let mut ret = Self {
+            path: path.to_path_buf(),
+            grammar,
+            load_error,
+            issues: vec![],
+            import_notes: vec![],
+            collapsed: HashSet::new(),
+            list_state: ListState::default().with_selected(Some(0)),
+            state: GrammarState::Main,
+            changed: false,
+            confirm_discard: false,
+        };
+        ret.issues = ret.grammar.check();
+        ret
+    }
+
+    fn rows(&self) -> Vec<TreeRow> {
+        let mut rows = vec![];
+        let nts = self.grammar.nonterminals();
+        for nt in &nts {
+            rows.push(TreeRow::NonTerminal(nt.to_string()));
+            if !self.collapsed.contains(*nt) {
+                rows.extend(
+                    self.grammar
+                        .productions_of(nt)
+                        .into_iter()
+                        .map(TreeRow::Rule),
+                );
+            }
+        }
+        let mut undefined: Vec<String> = vec![];
+        for p in &self.grammar.rules {
+            for s in parse_rhs(&p.rhs).unwrap_or_default() {
+                if let Symbol::NonTerminal(n) = s {
+                    if !nts.contains(&n.as_str()) && !undefined.contains(&n) {
+                        undefined.push(n);
+                    }
+                }
+            }
+        }
+        rows.extend(undefined.into_iter().map(TreeRow::Undefined));
+        rows
+    }
+
+    fn toggle(&mut self, nt: String) {
+        if !self.collapsed.remove(&nt) {
+            self.collapsed.insert(nt);
+        }
+    }
+
+    fn selected_row(&self) -> Option<TreeRow> {
+        self.rows().get(self.list_state.selected()?).cloned()
+    }
+
+    fn modified(&mut self) {
+        self.changed = true;
+        self.confirm_discard = false;
+        self.issues = self.grammar.check();
+    }
+
+    fn select_rule(&mut self, idx: usize) {
+        self.collapsed.remove(&self.grammar.rules[idx].lhs);
+        let row = self.rows().iter().position(|r| *r == TreeRow::Rule(idx));
+        self.list_state.select(row);
+    }
+
+    fn open_edit(&mut self, idx: Option<usize>) {
+        let (title, prefill) = match idx {
+            Some(i) => {
+                let p = &self.grammar.rules[i];
+                (
+                    format!("Edit a rule of {}", p.lhs),
+                    format!("{} -> {}", p.lhs, p.rhs),
+                )
+            }
+            None => {
+                let nt = match self.selected_row() {
+                    Some(TreeRow::NonTerminal(n) | TreeRow::Undefined(n)) => n,
+                    Some(TreeRow::Rule(i)) => self.grammar.rules[i].lhs.clone(),
+                    None => String::new(),
+                };
+                ("Add rule".to_string(), format!("{} -> ", nt))
+            }
+        };
+        let mut dialogue =
+            InputDialogue::new(title, "NAME -> text with {OTHER} non-terminals".to_string());
+        dialogue.textarea.insert_str(prefill);
+        self.state = GrammarState::Edit(idx, dialogue);
+    }
+
+    fn apply_edit(&mut self, idx: Option<usize>, input: &str) -> Result<(), String> {
+        let (lhs, rhs) = input
+            .split_once("->")
+            .ok_or("Expected: NAME -> production")?;
+        let lhs = lhs.trim();
+        // Only one space after the arrow is syntax, the rest is the production
+        let rhs = rhs.strip_prefix(' ').unwrap_or(rhs);
+        if !is_valid_name(lhs) {
+            return Err(format!(
+                "Invalid non-terminal name {}, use letters, digits, _ and -",
+                lhs
+            ));
+        }
+        parse_rhs(rhs)?;
+        let rule = Production {
+            lhs: lhs.to_string(),
+            rhs: rhs.to_string(),
+        };
+        let idx = match idx {
+            Some(i) => {
+                self.grammar.rules[i] = rule;
+                i
+            }
+            None => {
+                // Next to the other rules of the same non-terminal
+                let at = self
+                    .grammar
+                    .productions_of(lhs)
+                    .last()
+                    .map_or(self.grammar.rules.len(), |i| i + 1);
+                self.grammar.rules.insert(at, rule);
+                at
+            }
+        };
+        self.modified();
+        self.select_rule(idx);
+        Ok(())
+    }
+
+    fn import(&mut self, path: &Path) -> Result<String, String> {
+        let report = import_grammar(path)?;
+        self.grammar = report.grammar;
+        // Whatever grammar.json held is replaced, readable or not
+        self.load_error = None;
+        self.import_notes = report.notes;
+        self.collapsed.clear();
+        self.list_state.select(Some(0));
+        self.modified();
+        Ok(format!(
+            "Imported {} rules from {}, press [s] to save them",
+            self.grammar.rules.len(),
+            path.display()
+        ))
+    }
+
+    fn save(&mut self) -> Result<String, String> {
+        if let Some(e) = self.load_error.as_ref() {
+            return Err(format!(
+                "Not saved, {} could not be read: {}",
+                self.path.display(),
+                e
+            ));
+        }
+        self.grammar.save(&self.path)?;
+        self.changed = false;
+        Ok(format!("Grammar saved to {}", self.path.display()))
+    }
+
+    fn render_tree(&mut self, f: &mut Frame, area: Rect) {
+        let problem_nts: HashSet<&str> =
+            self.issues.iter().map(|i| i.nonterminal.as_str()).collect();
+        let start = self.grammar.start();
+        let items: Vec<ListItem> = self
+            .rows()
+            .iter()
+            .map(|row| match row {
+                TreeRow::NonTerminal(nt) => {
+                    let marker = if self.collapsed.contains(nt) {
+                        "▸"
+                    } else {
+                        "▾"
+                    };
+                    let count = self.grammar.productions_of(nt).len();
+                    let mut style = Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD);
+                    if problem_nts.contains(nt.as_str()) {
+                        style = style.fg(Color::Red);
+                    }
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("{} {}", marker, nt), style),
+                        Span::styled(
+                            format!(
+                                "  {} rule(s){}",
+                                count,
+                                if start == Some(nt.as_str()) {
+                                    ", start"
+                                } else {
+                                    ""
+                                }
+                            ),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                    ]))
+                }
+                TreeRow::Undefined(nt) => ListItem::new(Line::from(vec![
+                    Span::styled(format!("  {}", nt), Style::default().fg(Color::Red)),
+                    Span::styled("  undefined", Style::default().fg(Color::DarkGray)),
+                ])),
+                TreeRow::Rule(i) => {
+                    let mut spans = vec![Span::raw("    → ")];
+                    match parse_rhs(&self.grammar.rules[*i].rhs) {
+                        Ok(symbols) if symbols.is_empty() => {
+                            spans.push(Span::styled("ε", Style::default().fg(Color::DarkGray)))
+                        }
+                        Ok(symbols) => spans.extend(symbols.into_iter().map(|s| match s {
+                            Symbol::Text(t) => {
+                                Span::styled(format!("{:?}", t), Style::default().fg(Color::Green))
+                            }
+                            Symbol::NonTerminal(n) => {
+                                Span::styled(format!(" {} ", n), Style::default().fg(Color::Cyan))
+                            }
+                        })),
+                        Err(_) => spans.push(Span::styled(
+                            self.grammar.rules[*i].rhs.clone(),
+                            Style::default().fg(Color::Red),
+                        )),
+                    }
+                    ListItem::new(Line::from(spans))
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .title(format!(
+                        "{}{}",
+                        self.path.display(),
+                        if self.changed { " (changed)" } else { "" }
+                    ))
+                    .title_bottom(
+                        "[Enter] fold/edit  [a] add  [d] delete  [g] generate  [i] import  [s] save  [Esc] back",
+                    ),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::REVERSED),
+            );
+        f.render_stateful_widget(list, area, &mut self.list_state);
+    }
+
+    fn render_issues(&self, f: &mut Frame, area: Rect) {
+        let mut lines: Vec<Line> = vec![];
+        if let Some(e) = self.load_error.as_ref() {
+            lines.push(Line::from(e.clone()).style(Style::default().fg(Color::Red)));
+        }
+        if !cfg!(feature = "grammar_rules") {
+            lines.push(
+                Line::from("Built without grammar_rules, the fuzzer ignores this grammar")
+                    .style(Style::default().fg(Color::Red)),
+            );
+        }
+        if self.grammar.rules.is_empty() && self.load_error.is_none() {
+            lines.push(Line::from("No rules yet, press [a] to add the start rule"));
+        }
+        for issue in &self.issues {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{} ", issue.nonterminal),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(issue.message.clone()),
+            ]));
+        }
+        if !self.import_notes.is_empty() {
+            lines.push(Line::from("Not converted by the import:").style(Style::default().bold()));
+            lines.extend(
+                self.import_notes
+                    .iter()
+                    .map(|n| Line::from(n.clone()).style(Style::default().fg(Color::Yellow))),
+            );
+        }
+        if lines.is_empty() {
+            lines.push(Line::from("No problems found").style(Style::default().fg(Color::Green)));
+        }
+        let para = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(format!("Problems ({})", self.issues.len())),
+        );
+        f.render_widget(para, area);
+    }
+}
+
+impl Default for GrammarWindow<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Window for GrammarWindow<'a> {
+    fn name(&self) -> &str {
+        match self.state {
+            GrammarState::Main => "Grammar",
+            GrammarState::Edit(_, _) => "Grammar: Edit rule",
+            GrammarState::Generate(_) | GrammarState::Samples(_, _) => "Grammar: Samples",
+            GrammarState::Import(_) => "Grammar: Import ABNF/EBNF",
+        }
+    }
+
+    fn capture_all_input(&self) -> bool {
+        true
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect, _state: &AppState) -> Option<Vec<Request>> {
+        f.render_widget(Clear, area);
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .horizontal_margin(1)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(area);
+        self.render_tree(f, chunks[0]);
+        self.render_issues(f, chunks[1]);
+
+        match self.state {
+            GrammarState::Edit(_, ref mut dialogue) | GrammarState::Generate(ref mut dialogue) => {
+                let centered = centered_rect(60, 20, area);
+                f.render_widget(Clear, centered);
+                dialogue.render(f, centered);
+            }
+            GrammarState::Samples(ref samples, ref mut list_state) => {
+                let centered = centered_rect(80, 70, area);
+                let items: Vec<ListItem> = samples
+                    .iter()
+                    .map(|s| ListItem::new(format!("{:?}", s)))
+                    .collect();
+                let list = List::new(items)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_type(BorderType::Rounded)
+                            .title(format!("{} samples", samples.len()))
+                            .title_bottom("[e] export to corpus/  [g] again  [Esc] back"),
+                    )
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                f.render_widget(Clear, centered);
+                f.render_stateful_widget(list, centered, list_state);
+            }
+            GrammarState::Import(ref mut file_dialogue) => {
+                let centered = centered_rect(60, 60, area);
+                f.render_widget(Clear, centered);
+                file_dialogue.render(f, centered);
+            }
+            GrammarState::Main => {}
+        }
+        None
+    }
+
+    fn handle_input(&mut self, key: KeyEvent, _state: &AppState) -> Option<Vec<Request>> {
+        let popup = |t: PopupType, msg: String| Some(vec![Request::Popup(Popup::new(t, msg))]);
+        match self.state {
+            GrammarState::Edit(idx, ref mut dialogue) => match dialogue.handle_input(key) {
+                InputDialogueResult::Continue => None,
+                InputDialogueResult::Submit(input) => {
+                    self.state = GrammarState::Main;
+                    self.apply_edit(idx, &input)
+                        .err()
+                        .and_then(|e| popup(PopupType::Warning, e))
+                }
+                InputDialogueResult::Cancel => {
+                    self.state = GrammarState::Main;
+                    None
+                }
+            },
+            GrammarState::Generate(ref mut dialogue) => match dialogue.handle_input(key) {
+                InputDialogueResult::Continue => None,
+                InputDialogueResult::Submit(input) => {
+                    self.state = GrammarState::Main;
+                    let count = if input.trim().is_empty() {
+                        DEFAULT_SAMPLES
+                    } else {
+                        match input.trim().parse::<usize>() {
+                            Ok(n) if n > 0 => n,
+                            _ => {
+                                return popup(
+                                    PopupType::Warning,
+                                    format!("Invalid number of samples: {}", input),
+                                )
+                            }
+                        }
+                    };
+                    match generate_samples(&self.grammar, count, &mut Rng::from_time()) {
+                        Ok(samples) => {
+                            self.state = GrammarState::Samples(
+                                samples,
+                                ListState::default().with_selected(Some(0)),
+                            );
+                            None
+                        }
+                        Err(e) => popup(PopupType::Warning, e),
+                    }
+                }
+                InputDialogueResult::Cancel => {
+                    self.state = GrammarState::Main;
+                    None
+                }
+            },
+            GrammarState::Samples(ref samples, ref mut list_state) => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    list_state.select_previous();
+                    None
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    list_state.select_next();
+                    None
+                }
+                KeyCode::Char('e') => {
+                    let corpus = self.path.with_file_name("corpus");
+                    match export_samples(samples, &corpus) {
+                        Ok(written) => popup(
+                            PopupType::Success,
+                            format!("Wrote {} inputs to {}", written.len(), corpus.display()),
+                        ),
+                        Err(e) => popup(PopupType::Warning, e),
+                    }
+                }
+                KeyCode::Char('g') => {
+                    let count = samples.len();
+                    match generate_samples(&self.grammar, count, &mut Rng::from_time()) {
+                        Ok(new) => {
+                            self.state = GrammarState::Samples(
+                                new,
+                                ListState::default().with_selected(Some(0)),
+                            );
+                            None
+                        }
+                        Err(e) => popup(PopupType::Warning, e),
+                    }
+                }
+                KeyCode::Esc => {
+                    self.state = GrammarState::Main;
+                    None
+                }
+                _ => None,
+            },
+            GrammarState::Import(ref mut file_dialogue) => match file_dialogue.handle_input(key) {
+                FileDialogueResult::Continue => None,
+                FileDialogueResult::Select(path) => {
+                    self.state = GrammarState::Main;
+                    match self.import(&path) {
+                        Ok(msg) => popup(PopupType::Info, msg),
+                        Err(e) => popup(PopupType::Warning, e),
+                    }
+                }
+                FileDialogueResult::Cancel => {
+                    self.state = GrammarState::Main;
+                    None
+                }
+            },
+            GrammarState::Main => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.list_state.select_previous();
+                    None
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.list_state.select_next();
+                    None
+                }
+                KeyCode::Enter | KeyCode::Char(' ') => {
+                    match self.selected_row() {
+                        Some(TreeRow::NonTerminal(nt)) => self.toggle(nt),
+                        Some(TreeRow::Rule(i)) => self.open_edit(Some(i)),
+                        Some(TreeRow::Undefined(_)) => self.open_edit(None),
+                        None => {}
+                    }
+                    None
+                }
+                KeyCode::Char('a') => {
+                    self.open_edit(None);
+                    None
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    if let Some(TreeRow::Rule(i)) = self.selected_row() {
+                        self.grammar.rules.remove(i);
+                        self.modified();
+                    }
+                    None
+                }
+                KeyCode::Char('g') => {
+                    let mut dialogue = InputDialogue::new(
+                        "Generate samples".to_string(),
+                        format!("How many? ({} by default)", DEFAULT_SAMPLES),
+                    );
+                    dialogue.textarea.insert_str(DEFAULT_SAMPLES.to_string());
+                    self.state = GrammarState::Generate(dialogue);
+                    None
+                }
+                KeyCode::Char('i') => {
+                    self.state = GrammarState::Import(FileDialogue::new());
+                    None
+                }
+                KeyCode::Char('s') => match self.save() {
+                    Ok(msg) => popup(PopupType::Success, msg),
+                    Err(e) => popup(PopupType::Warning, e),
+                },
+                KeyCode::Esc => {
+                    if self.changed && !self.confirm_discard {
+                        self.confirm_discard = true;
+                        return popup(
+                            PopupType::Warning,
+                            "Unsaved changes, press [s] to save or Esc again to discard them"
+                                .to_string(),
+                        );
+                    }
+                    Some(vec![Request::PopWindow])
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("grammar.json");
+        std::fs::write(&path, "").unwrap();
+        let mut window = GrammarWindow::open(&path);
+        window.apply_edit(None, "START -> GET {PATH}").unwrap();
+        window.apply_edit(None, "PATH -> /").unwrap();
+        window.apply_edit(None, "START -> HEAD {PATH}").unwrap();
+        assert!(window.apply_edit(None, "bad name -> x").is_err());
+        assert_eq!(
+            window.rows(),
+            vec![
+                TreeRow::NonTerminal("START".into()),
+                TreeRow::Rule(0),
+                TreeRow::Rule(1),
+                TreeRow::NonTerminal("PATH".into()),
+                TreeRow::Rule(2),
+            ]
+        );
+        assert_eq!(window.grammar.rules[1].rhs, "HEAD {PATH}");
+        assert!(window.issues.is_empty());
+        window.save().unwrap();
+        assert_eq!(Grammar::load(&path).unwrap(), window.grammar);
+
+        std::fs::write(&path, "not json").unwrap();
+        let mut broken = GrammarWindow::open(&path);
+        assert!(broken.save().is_err());
+        // An import replaces the unreadable file
+        let abnf = dir.path().join("request.abnf");
+        std::fs::write(&abnf, "start = \"GET \" <path>\n").unwrap();
+        broken.import(&abnf).unwrap();
+        assert_eq!(broken.import_notes.len(), 2);
+        broken.save().unwrap();
+    }
+}
@@ -0,0 +1,543 @@
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+use serde_json::Value;
+
+use crate::app::Request;
+use crate::config::interrupts::{
+    allowed_policies, compiled_modes, parse_interrupt_spec, FiringPolicy, InterruptList,
+    InterruptMode,
+};
+use crate::config::validate::Issue;
+use crate::firmware::vector_table::{analyze_vectors, HandlerKind, VectorReport};
+use crate::popup::{Popup, PopupType};
+use crate::utils::centered_rect;
+use crate::utils::input_dialogue::{InputDialogue, InputDialogueResult};
+
+// Edits the interrupts the emulator raises. Like the memory map it is
+// opened from the configuration window, which takes the list back on close.
+pub struct InterruptWindow<'a> {
+    list: InterruptList,
+    modes: Vec<InterruptMode>,
+    policies: Vec<FiringPolicy>,
+    vectors: Result<VectorReport, String>,
+    issues: Vec<Issue>,
+    table_state: TableState,
+    // Every entry of the vector table, open with [t]
+    vector_view: Option<TableState>,
+    // The entry being edited, None when adding one
+    input: Option<(Option<usize>, InputDialogue<'a>)>,
+    changed: bool,
+    closed: bool,
+}
+
+impl<'a> InterruptWindow<'a> {
+    pub fn new(config: &Value, default_list: Option<&str>, binary: Option<&Path>) -> Self {
+        let modes = compiled_modes();
+        let mut ret = Self {
+            list: InterruptList::from_config(config, default_list),
+            policies: allowed_policies(&modes),
+            modes,
+            vectors: match binary {
+                Some(b) => analyze_vectors(b),
+                None => Err("No binary selected, handlers are not checked".to_string()),
+            },
+            issues: vec![],
+            table_state: TableState::default().with_selected(Some(0)),
+            vector_view: None,
+            input: None,
+            changed: false,
+            closed: false,
+        };
+        ret.check();
+        ret
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    // Write the list into the config, returns false if nothing changed
+    pub fn apply(&self, config: &mut Value) -> bool {
+        if self.changed {
+            self.list.apply(config);
+        }
+        self.changed
+    }
+
+    fn check(&mut self) {
+        let table = self.vectors.as_ref().ok().map(|r| &r.table);
+        self.issues = self.list.check(&self.policies, table);
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.table_state
+            .selected()
+            .filter(|i| *i < self.list.interrupts.len())
+    }
+
+    fn open_input(&mut self, idx: Option<usize>) {
+        let prefill = idx.map_or(String::new(), |i| {
+            let irq = &self.list.interrupts[i];
+            let mut parts = vec![irq.irq.to_string()];
+            parts.extend(irq.handler.map(|h| format!("{:#x}", h)));
+            parts.extend(irq.policy.map(|p| p.name().to_string()));
+            parts.extend(irq.period.map(|p| p.to_string()));
+            parts.join(" ")
+        });
+        let title = match idx {
+            Some(i) => format!("Edit IRQ {}", self.list.interrupts[i].irq),
+            None => "Add interrupt".to_string(),
+        };
+        let mut dialogue = InputDialogue::new(
+            title,
+            format!(
+                "irq [handler] [policy] [period], policies: {}",
+                self.policy_names()
+            ),
+        );
+        dialogue.textarea.insert_str(prefill);
+        self.input = Some((idx, dialogue));
+    }
+
+    fn policy_names(&self) -> String {
+        self.policies
+            .iter()
+            .map(|p| p.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn update(&mut self, idx: Option<usize>, text: &str) -> Result<(), String> {
+        let irq = parse_interrupt_spec(text)?;
+        let idx = self.list.set(idx, irq)?;
+        self.changed = true;
+        self.table_state.select(Some(idx));
+        self.check();
+        Ok(())
+    }
+
+    // Step the selected interrupt through the policies this build has
+    fn cycle_policy(&mut self, idx: usize) -> Result<(), String> {
+        if self.policies.is_empty() {
+            return Err("This build has no interrupt support".to_string());
+        }
+        let mut irq = self.list.interrupts[idx].clone();
+        let next = match irq
+            .policy
+            .and_then(|p| self.policies.iter().position(|q| *q == p))
+        {
+            Some(i) => self.policies[(i + 1) % self.policies.len()],
+            None => self.policies[0],
+        };
+        irq.policy = Some(next);
+        self.list.set(Some(idx), irq)?;
+        self.changed = true;
+        self.check();
+        Ok(())
+    }
+
+    fn handler_from_vectors(&mut self, idx: usize) -> Result<(), String> {
+        let vt = &self.vectors.as_ref().map_err(|e| e.clone())?.table;
+        let mut irq = self.list.interrupts[idx].clone();
+        let slot = vt
+            .irq_slot(irq.irq)
+            .filter(|s| *s != 0)
+            .ok_or_else(|| format!("The vector table has no handler for IRQ {}", irq.irq))?;
+        irq.handler = Some((slot & !1) as u64);
+        self.list.set(Some(idx), irq)?;
+        self.changed = true;
+        self.check();
+        Ok(())
+    }
+
+    // Add IRQs and handlers from the vector table to the list
+    fn import_handlers(&mut self, handlers: &[(u64, u64)]) -> Result<String, String> {
+        if handlers.is_empty() {
+            return Err("The vector table has no handler to take".to_string());
+        }
+        let changed = self.list.import_handlers(handlers);
+        if changed > 0 {
+            self.changed = true;
+            self.check();
+        }
+        Ok(format!(
+            "{} of {} interrupt(s) from the vector table added or completed",
+            changed,
+            handlers.len()
+        ))
+    }
+
+    // [Enter] takes the selected interrupt, even a weak or default one,
+    // [i] all the firmware handles itself
+    fn handle_vector_view(&mut self, key: KeyEvent) -> Result<Option<String>, String> {
+        let (Some(view), Ok(report)) = (self.vector_view.as_mut(), self.vectors.as_ref()) else {
+            return Ok(None);
+        };
+        match key.code {
+            KeyCode::Up | KeyCode::Char('j') => view.select_previous(),
+            KeyCode::Down | KeyCode::Char('k') => {
+                let last = report.entries.len().saturating_sub(1);
+                let row = view.selected().map_or(0, |r| (r + 1).min(last));
+                view.select(Some(row));
+            }
+            KeyCode::Enter => {
+                let Some(entry) = view.selected().and_then(|i| report.entries.get(i)) else {
+                    return Ok(None);
+                };
+                let Some(irq) = entry.irq() else {
+                    return Err(format!(
+                        "{} is a core exception, only IRQs go in the list",
+                        entry.name()
+                    ));
+                };
+                if entry.kind == HandlerKind::Empty {
+                    return Err(format!("The vector table has no handler for IRQ {}", irq));
+                }
+                let handler = entry.addr();
+                return self.import_handlers(&[(irq, handler)]).map(Some);
+            }
+            KeyCode::Char('i') => {
+                let handlers = report.interrupt_handlers();
+                return self.import_handlers(&handlers).map(Some);
+            }
+            KeyCode::Esc => self.vector_view = None,
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    pub fn handle_input(&mut self, key: KeyEvent) -> Option<Vec<Request>> {
+        let warn = |e: String| Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]);
+        if self.vector_view.is_some() {
+            return match self.handle_vector_view(key) {
+                Ok(Some(msg)) => Some(vec![Request::Popup(Popup::new(PopupType::Info, msg))]),
+                Ok(None) => None,
+                Err(e) => warn(e),
+            };
+        }
+        if let Some((idx, ref mut dialogue)) = self.input {
+            match dialogue.handle_input(key) {
+                InputDialogueResult::Continue => {}
+                InputDialogueResult::Submit(text) => {
+                    self.input = None;
+                    if let Err(e) = self.update(idx, &text) {
+                        return warn(e);
+                    }
+                }
+                InputDialogueResult::Cancel => self.input = None,
+            }
+            return None;
+        }
+        let result = match key.code {
+            KeyCode::Up | KeyCode::Char('j') => {
+                self.table_state.select_previous();
+                Ok(())
+            }
+            KeyCode::Down | KeyCode::Char('k') => {
+                let last = self.list.interrupts.len().saturating_sub(1);
+                let row = self.table_state.selected().map_or(0, |r| (r + 1).min(last));
+                self.table_state.select(Some(row));
+                Ok(())
+            }
+            KeyCode::Char('a') => {
+                self.open_input(None);
+                Ok(())
+            }
+            KeyCode::Char('t') => match &self.vectors {
+                Ok(_) => {
+                    self.vector_view = Some(TableState::default().with_selected(Some(0)));
+                    Ok(())
+                }
+                Err(e) => Err(e.clone()),
+            },
+            KeyCode::Esc => {
+                self.closed = true;
+                Ok(())
+            }
+            code => {
+                let idx = self.selected()?;
+                match code {
+                    KeyCode::Enter => {
+                        self.open_input(Some(idx));
+                        Ok(())
+                    }
+                    KeyCode::Char('p') => self.cycle_policy(idx),
+                    KeyCode::Char('v') => self.handler_from_vectors(idx),
+                    KeyCode::Char('d') | KeyCode::Delete => {
+                        self.list.delete(idx);
+                        self.changed = true;
+                        if idx >= self.list.interrupts.len() {
+                            self.table_state.select_previous();
+                        }
+                        self.check();
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                }
+            }
+        };
+        result.err().and_then(warn)
+    }
+
+    fn header(&self) -> Vec<Line<'static>> {
+        let build = if self.modes.is_empty() {
+            Line::from("Built without interrupt support, the emulator ignores this list")
+                .style(Style::default().fg(Color::Red))
+        } else {
+            Line::from(format!(
+                "Built with {}, policies: {}",
+                self.modes
+                    .iter()
+                    .map(|m| m.feature())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.policy_names()
+            ))
+        };
+        let mut ret = vec![build];
+        match &self.vectors {
+            Ok(report) => {
+                ret.push(Line::from(format!(
+                    "Vector table: initial SP {:#x}, reset {:#x}",
+                    report.table.initial_sp(),
+                    report.table.reset(),
+                )));
+                ret.push(Line::from(format!(
+                    "{}, [t] to list them",
+                    report.summary()
+                )));
+            }
+            Err(e) => ret.push(Line::from(e.clone()).style(Style::default().fg(Color::DarkGray))),
+        }
+        if self.list.numbers_only() {
+            ret.push(
+                Line::from(format!(
+                    "{} only holds IRQ numbers, handlers and policies cannot be set",
+                    self.list.list_path
+                ))
+                .style(Style::default().fg(Color::Yellow)),
+            );
+        }
+        ret
+    }
+
+    fn render_vectors(&mut self, f: &mut Frame, area: Rect) {
+        let (Some(view), Ok(report)) = (self.vector_view.as_mut(), self.vectors.as_ref()) else {
+            return;
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(format!("Vector table: {}", report.summary()))
+            .title_bottom(
+                "[Enter] add the selected IRQ  [i] add all IRQs with their own handler  [Esc] back",
+            );
+        let rows: Vec<Row> = report
+            .entries
+            .iter()
+            .map(|e| {
+                let listed = e
+                    .irq()
+                    .is_some_and(|irq| self.list.interrupts.iter().any(|i| i.irq == irq));
+                let mut notes = vec![];
+                if !e.kind.label().is_empty() {
+                    notes.push(e.kind.label().to_string());
+                }
+                if e.shared > 0 && e.kind != HandlerKind::Empty {
+                    notes.push(format!("shared with {}", e.shared));
+                }
+                if listed {
+                    notes.push("listed".to_string());
+                }
+                let handler = if e.kind == HandlerKind::Empty {
+                    String::new()
+                } else {
+                    format!("{:#010x}", e.addr())
+                };
+                Row::new(vec![
+                    e.exception.to_string(),
+                    e.name(),
+                    handler,
+                    e.symbol.clone().unwrap_or_default(),
+                    notes.join(", "),
+                ])
+                .style(match e.kind {
+                    HandlerKind::Defined => Style::default(),
+                    HandlerKind::Weak => Style::default().fg(Color::Yellow),
+                    HandlerKind::Default | HandlerKind::Empty => {
+                        Style::default().fg(Color::DarkGray)
+                    }
+                })
+            })
+            .collect();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(4),
+                Constraint::Length(13),
+                Constraint::Length(12),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec!["#", "Exception", "Handler", "Symbol", ""])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::REVERSED),
+        );
+        f.render_stateful_widget(table, area, view);
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        f.render_widget(Clear, area);
+        if self.vector_view.is_some() {
+            self.render_vectors(f, area);
+            return;
+        }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(format!(
+                "Interrupts: {}{}",
+                self.list.list_path,
+                if self.changed { " (changed)" } else { "" }
+            ))
+            .title_bottom(
+                "[a] add  [Enter] edit  [p] policy  [v] handler from vector table  [t] vector table  [d] delete  [Esc] done",
+            );
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let header = self.header();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(header.len() as u16 + 1),
+                Constraint::Min(3),
+            ])
+            .split(inner);
+        f.render_widget(
+            Paragraph::new(header).block(Block::default().borders(Borders::BOTTOM)),
+            chunks[0],
+        );
+
+        let vectors = self.vectors.as_ref().ok().map(|r| &r.table);
+        let rows: Vec<Row> = self
+            .list
+            .interrupts
+            .iter()
+            .enumerate()
+            .map(|(i, irq)| {
+                let path = self.list.path_of(i);
+                let problem = self.issues.iter().find(|is| is.path == path);
+                let slot = match vectors.map(|vt| vt.irq_slot(irq.irq)) {
+                    Some(Some(0)) => "empty".to_string(),
+                    Some(Some(s)) => format!("{:#010x}", s & !1),
+                    Some(None) => "past end".to_string(),
+                    None => String::new(),
+                };
+                Row::new(vec![
+                    irq.irq.to_string(),
+                    irq.handler
+                        .map_or(String::new(), |h| format!("{:#010x}", h)),
+                    slot,
+                    irq.policy.map_or(String::new(), |p| p.name().to_string()),
+                    irq.period.map_or(String::new(), |p| p.to_string()),
+                    problem.map_or(String::new(), |p| p.message.clone()),
+                ])
+                .style(if problem.is_some() {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default()
+                })
+            })
+            .collect();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(5),
+                Constraint::Length(12),
+                Constraint::Length(13),
+                Constraint::Length(9),
+                Constraint::Length(10),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                "IRQ",
+                "Handler",
+                "Vector table",
+                "Policy",
+                "Period",
+                "",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::REVERSED),
+        );
+        f.render_stateful_widget(table, chunks[1], &mut self.table_state);
+
+        if let Some((_, ref mut dialogue)) = self.input {
+            let centered = centered_rect::centered_rect(60, 20, area);
+            f.render_widget(Clear, centered);
+            dialogue.render(f, centered);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_vector_handler() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("fw.bin");
+        let mut table = vec![0x2000_8000u32, 0x0800_0101];
+        table.extend([0; 14]);
+        table.extend([0x0800_0201, 0x0800_0301]);
+        std::fs::write(
+            &binary,
+            table
+                .iter()
+                .flat_map(|w| w.to_le_bytes())
+                .collect::<Vec<u8>>(),
+        )
+        .unwrap();
+
+        let config = json!({"interrupts": [{"irq": 1, "handler": 0x0800_0400u64}]});
+        let mut window = InterruptWindow::new(&config, None, Some(&binary));
+        assert_eq!(window.issues.len(), 1);
+        window.handle_input(KeyEvent::from(KeyCode::Char('v')));
+        assert!(window.issues.is_empty());
+
+        let mut value = config.clone();
+        assert!(window.apply(&mut value));
+        assert_eq!(value["interrupts"][0]["handler"], json!(0x0800_0300u64));
+
+        // IRQ 0 is not listed yet, [i] in the vector table adds it
+        window.handle_input(KeyEvent::from(KeyCode::Char('t')));
+        assert!(window.vector_view.is_some());
+        window.handle_input(KeyEvent::from(KeyCode::Char('i')));
+        window.handle_input(KeyEvent::from(KeyCode::Esc));
+        assert!(window.vector_view.is_none());
+        let irqs: Vec<u64> = window.list.interrupts.iter().map(|i| i.irq).collect();
+        assert_eq!(irqs, vec![1, 0]);
+        assert_eq!(window.list.interrupts[1].handler, Some(0x0800_0200));
+    }
+}
@@ -1,7 +1,10 @@
 // windows/main_window.rs
 use crate::app::Request;
+use crate::state::AppState;
 use crate::window::Window; // Removed WindowTransition
+use crate::windows::campaign_history_window::CampaignHistoryWindow;
 use crate::windows::config::ConfigWindow;
+use crate::windows::grammar_window::GrammarWindow;
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{prelude::*, widgets::*};
 
@@ -20,6 +23,8 @@ impl MainWindow {
                 "Static analysis".into(),
                 "Fuzz !".into(),
                 "Config".into(),
+                "Grammar".into(),
+                "Campaign History".into(),
                 "Quit".into(),
             ],
             list_state: ListState::default().with_selected(Some(0)),
@@ -32,7 +37,7 @@ impl Window for MainWindow {
         "Main Menu"
     }
 
-    fn render(&mut self, f: &mut Frame, area: Rect) -> Option<Vec<Request>> {
+    fn render(&mut self, f: &mut Frame, area: Rect, _state: &AppState) -> Option<Vec<Request>> {
         let ret = None;
         let vertical_chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -86,7 +91,7 @@ impl Window for MainWindow {
         return ret;
     }
 
-    fn handle_input(&mut self, key: KeyEvent) -> Option<Vec<Request>> {
+    fn handle_input(&mut self, key: KeyEvent, _state: &AppState) -> Option<Vec<Request>> {
         let mut ret = None;
         match key.code {
             KeyCode::Up | KeyCode::Char('k') => {
@@ -101,6 +106,14 @@ impl Window for MainWindow {
                     // Config
                     ret = Some(vec![Request::PushWindow(Box::new(ConfigWindow::new()))]);
                 }
+                if &self.options[selected] == "Grammar" {
+                    ret = Some(vec![Request::PushWindow(Box::new(GrammarWindow::new()))]);
+                }
+                if &self.options[selected] == "Campaign History" {
+                    ret = Some(vec![Request::PushWindow(Box::new(
+                        CampaignHistoryWindow::new(),
+                    ))]);
+                }
                 if &self.options[selected] == "Fuzz !" {
                     #[cfg(feature = "for_fuzzer")]
                     {
@@ -0,0 +1,524 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table, TableState,
+};
+use ratatui::Frame;
+use serde_json::Value;
+
+use crate::app::Request;
+use crate::config::memory_map::{parse_region_spec, MapRegion, MemoryMap};
+use crate::config::schema::{format_value, parse_int, parse_size, FieldKind};
+use crate::popup::{Popup, PopupType};
+use crate::utils::centered_rect;
+use crate::utils::input_dialogue::{InputDialogue, InputDialogueResult};
+
+// Columns a gap between regions takes in the address bar. Gaps are usually
+// far larger than the regions, drawn to scale they would hide them.
+const GAP_WIDTH: usize = 3;
+const REGION_COLORS: [Color; 6] = [
+    Color::Blue,
+    Color::Green,
+    Color::Cyan,
+    Color::Magenta,
+    Color::Yellow,
+    Color::LightBlue,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Segment {
+    Gap,
+    Region(usize),
+    Overlap,
+}
+
+enum MapInput {
+    Add,
+    Edit(usize),
+    Split(usize),
+    Resize(usize),
+}
+
+// Edits the memory regions of a config. It is opened from the
+// configuration window, which takes the changed regions back on close.
+pub struct MemoryMapWindow<'a> {
+    map: MemoryMap,
+    // Over the regions ordered by address
+    table_state: TableState,
+    input: Option<(MapInput, InputDialogue<'a>)>,
+    changed: bool,
+    closed: bool,
+}
+
+impl<'a> MemoryMapWindow<'a> {
+    pub fn new(config: &Value, default_list: Option<&str>) -> Result<Self, String> {
+        Ok(Self::of_map(MemoryMap::from_config(config, default_list)?))
+    }
+
+    pub fn of_map(map: MemoryMap) -> Self {
+        Self {
+            map,
+            table_state: TableState::default().with_selected(Some(0)),
+            input: None,
+            changed: false,
+            closed: false,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn reopen(&mut self) {
+        self.closed = false;
+    }
+
+    // Write the regions into the config, returns false if nothing changed
+    pub fn apply(&self, config: &mut Value) -> Result<bool, String> {
+        if self.changed {
+            self.map.apply(config)?;
+        }
+        Ok(self.changed)
+    }
+
+    fn selected(&self) -> Option<usize> {
+        let order = self.map.by_address();
+        self.table_state
+            .selected()
+            .and_then(|i| order.get(i).copied())
+    }
+
+    fn select(&mut self, idx: usize) {
+        let row = self.map.by_address().iter().position(|i| *i == idx);
+        self.table_state.select(row);
+    }
+
+    fn open_input(&mut self, input: MapInput) {
+        let (title, prompt, prefill) = match input {
+            MapInput::Add => (
+                "Add region".to_string(),
+                "name start size [perms], e.g. sram 0x20000000 64k rw-".to_string(),
+                String::new(),
+            ),
+            MapInput::Edit(i) => {
+                let r = &self.map.regions[i];
+                (
+                    format!("Edit {}", r.name),
+                    "name start size [perms]".to_string(),
+                    format!(
+                        "{} {:#x} {:#x} {}",
+                        r.name,
+                        r.start,
+                        r.size,
+                        r.perms.map(|p| p.to_string()).unwrap_or_default()
+                    )
+                    .trim_end()
+                    .to_string(),
+                )
+            }
+            MapInput::Split(i) => {
+                let r = &self.map.regions[i];
+                (
+                    format!("Split {} ({:#x}-{:#x})", r.name, r.start, r.end()),
+                    "Address to split at, or +offset from the start".to_string(),
+                    String::new(),
+                )
+            }
+            MapInput::Resize(i) => {
+                let r = &self.map.regions[i];
+                (
+                    format!("Resize {}", r.name),
+                    "size, or start size".to_string(),
+                    format!("{:#x}", r.size),
+                )
+            }
+        };
+        let mut dialogue = InputDialogue::new(title, prompt);
+        dialogue.textarea.insert_str(prefill);
+        self.input = Some((input, dialogue));
+    }
+
+    fn submit(&mut self, input: MapInput, text: &str) -> Result<(), String> {
+        let idx = match input {
+            MapInput::Add => {
+                let (name, start, size, perms) = parse_region_spec(text)?;
+                self.map.add(&name, start, size, perms)
+            }
+            MapInput::Edit(i) => {
+                let (name, start, size, perms) = parse_region_spec(text)?;
+                self.map.edit(i, &name, start, size, perms)?;
+                i
+            }
+            MapInput::Split(i) => {
+                let text = text.trim();
+                let at = match text.strip_prefix('+') {
+                    Some(offset) => self.map.regions[i]
+                        .start
+                        .checked_add(parse_size(offset)?)
+                        .ok_or("Offset out of range")?,
+                    None => parse_int(text)?,
+                };
+                self.map.split(i, at)?
+            }
+            MapInput::Resize(i) => {
+                let parts: Vec<&str> = text.split_whitespace().collect();
+                let (start, size) = match parts.as_slice() {
+                    [size] => (self.map.regions[i].start, parse_size(size)?),
+                    [start, size] => (parse_int(start)?, parse_size(size)?),
+                    _ => return Err("Expected: size, or start size".to_string()),
+                };
+                self.map.resize(i, start, size)?;
+                i
+            }
+        };
+        self.changed = true;
+        self.select(idx);
+        Ok(())
+    }
+
+    // Merge the selected region with the next one up
+    fn merge_next(&mut self) -> Result<(), String> {
+        let order = self.map.by_address();
+        let row = self.table_state.selected().ok_or("No region selected")?;
+        let (Some(&idx), Some(&next)) = (order.get(row), order.get(row + 1)) else {
+            return Err("There is no region above this one to merge with".to_string());
+        };
+        self.map.merge(idx, next)?;
+        self.changed = true;
+        self.select(if next < idx { idx - 1 } else { idx });
+        Ok(())
+    }
+
+    pub fn handle_input(&mut self, key: KeyEvent) -> Option<Vec<Request>> {
+        let warn = |e: String| Some(vec![Request::Popup(Popup::new(PopupType::Warning, e))]);
+        if let Some((_, ref mut dialogue)) = self.input {
+            match dialogue.handle_input(key) {
+                InputDialogueResult::Continue => {}
+                InputDialogueResult::Submit(text) => {
+                    let (input, _) = self.input.take().unwrap();
+                    if let Err(e) = self.submit(input, &text) {
+                        return warn(e);
+                    }
+                }
+                InputDialogueResult::Cancel => self.input = None,
+            }
+            return None;
+        }
+        let selected = self.selected();
+        match key.code {
+            KeyCode::Up | KeyCode::Char('j') => self.table_state.select_previous(),
+            KeyCode::Down | KeyCode::Char('k') => {
+                let last = self.map.regions.len().saturating_sub(1);
+                let row = self.table_state.selected().map_or(0, |r| (r + 1).min(last));
+                self.table_state.select(Some(row));
+            }
+            KeyCode::Char('a') => self.open_input(MapInput::Add),
+            KeyCode::Esc => self.closed = true,
+            _ => {
+                let idx = selected?;
+                // Check up front so the dialogue does not open for nothing
+                if self.map.regions[idx].is_fixed()
+                    && matches!(
+                        key.code,
+                        KeyCode::Enter | KeyCode::Char('s' | 'r' | 'd') | KeyCode::Delete
+                    )
+                {
+                    let r = &self.map.regions[idx];
+                    return warn(format!(
+                        "'{}' is set up at {}, change it in the configuration form",
+                        r.name, r.path
+                    ));
+                }
+                match key.code {
+                    KeyCode::Enter => self.open_input(MapInput::Edit(idx)),
+                    KeyCode::Char('s') => self.open_input(MapInput::Split(idx)),
+                    KeyCode::Char('r') => self.open_input(MapInput::Resize(idx)),
+                    KeyCode::Char('m') => {
+                        if let Err(e) = self.merge_next() {
+                            return warn(e);
+                        }
+                    }
+                    KeyCode::Char('d') | KeyCode::Delete => {
+                        if let Err(e) = self.map.delete(idx) {
+                            return warn(e);
+                        }
+                        self.changed = true;
+                        if self.table_state.selected() >= Some(self.map.regions.len()) {
+                            self.table_state.select_previous();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        f.render_widget(Clear, area);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(format!(
+                "Memory map: {}{}",
+                self.map.list_path,
+                if self.changed { " (changed)" } else { "" }
+            ))
+            .title_bottom(
+                "[a] add  [Enter] edit  [s] split  [m] merge with next  [r] resize  [d] delete  [Esc] done",
+            );
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(4), Constraint::Min(3)])
+            .split(inner);
+
+        let selected = self.selected();
+        f.render_widget(
+            Paragraph::new(address_bar(
+                &self.map.regions,
+                inner.width as usize,
+                selected,
+            ))
+            .block(Block::default().borders(Borders::BOTTOM)),
+            chunks[0],
+        );
+
+        let rows: Vec<Row> = self
+            .map
+            .by_address()
+            .into_iter()
+            .map(|i| {
+                let r = &self.map.regions[i];
+                let (note, style) = if self.map.overlaps_any(i) {
+                    ("overlaps".to_string(), Style::default().fg(Color::Red))
+                } else if r.is_fixed() {
+                    (
+                        format!("set at {}", r.path),
+                        Style::default().fg(Color::DarkGray),
+                    )
+                } else if !r.is_aligned() {
+                    (
+                        "not page aligned".to_string(),
+                        Style::default().fg(Color::Yellow),
+                    )
+                } else {
+                    (String::new(), Style::default())
+                };
+                let cells: Vec<Cell> = vec![
+                    Span::styled(
+                        "■ ",
+                        Style::default().fg(REGION_COLORS[i % REGION_COLORS.len()]),
+                    )
+                    .into(),
+                    r.name.clone().into(),
+                    format!("{:#010x}", r.start).into(),
+                    format!("{:#010x}", r.end()).into(),
+                    format_value(&FieldKind::Size, &Value::from(r.size)).into(),
+                    r.perms.map(|p| p.to_string()).unwrap_or_default().into(),
+                    note.into(),
+                ];
+                Row::new(cells).style(style)
+            })
+            .collect();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(2),
+                Constraint::Fill(1),
+                Constraint::Length(12),
+                Constraint::Length(12),
+                Constraint::Length(20),
+                Constraint::Length(6),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec!["", "Name", "Start", "End", "Size", "Perms", ""])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::REVERSED),
+        );
+        f.render_stateful_widget(table, chunks[1], &mut self.table_state);
+
+        if let Some((_, ref mut dialogue)) = self.input {
+            let centered = centered_rect::centered_rect(60, 20, area);
+            f.render_widget(Clear, centered);
+            dialogue.render(f, centered);
+        }
+    }
+}
+
+// Cut the address space at every region boundary and give each piece a
+// width: regions in proportion to their size, at least one column each,
+// gaps a fixed width
+fn bar_segments(regions: &[MapRegion], width: usize) -> Vec<(Segment, usize)> {
+    let mut bounds: Vec<u64> = regions
+        .iter()
+        .filter(|r| r.size > 0)
+        .flat_map(|r| [r.start, r.end()])
+        .collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    let mut pieces: Vec<(Segment, u64)> = vec![];
+    for w in bounds.windows(2) {
+        let covering: Vec<usize> = (0..regions.len())
+            .filter(|i| regions[*i].start <= w[0] && w[0] < regions[*i].end())
+            .collect();
+        let seg = match covering.as_slice() {
+            [] => Segment::Gap,
+            [i] => Segment::Region(*i),
+            _ => Segment::Overlap,
+        };
+        match pieces.last_mut() {
+            Some((last, len)) if *last == seg => *len += w[1] - w[0],
+            _ => pieces.push((seg, w[1] - w[0])),
+        }
+    }
+
+    let gaps = pieces.iter().filter(|(s, _)| *s == Segment::Gap).count();
+    let gap_width = if gaps * GAP_WIDTH * 2 <= width {
+        GAP_WIDTH
+    } else {
+        1
+    };
+    let room = width.saturating_sub(gaps * gap_width) as u128;
+    let mapped: u128 = pieces
+        .iter()
+        .filter(|(s, _)| *s != Segment::Gap)
+        .map(|(_, len)| *len as u128)
+        .sum();
+    let mut ret: Vec<(Segment, usize)> = pieces
+        .iter()
+        .map(|(s, len)| match s {
+            Segment::Gap => (*s, gap_width),
+            _ => (*s, ((*len as u128 * room / mapped.max(1)) as usize).max(1)),
+        })
+        .collect();
+    // Rounding leaves the total a little off, the widest piece evens it out
+    let total: usize = ret.iter().map(|(_, w)| w).sum();
+    if let Some(widest) = ret.iter_mut().max_by_key(|(_, w)| *w) {
+        widest.1 = (widest.1 + width).saturating_sub(total).max(1);
+    }
+    ret
+}
+
+fn address_bar(regions: &[MapRegion], width: usize, selected: Option<usize>) -> Vec<Line<'static>> {
+    if regions.iter().all(|r| r.size == 0) {
+        return vec![Line::from("No memory regions, press [a] to add one")
+            .style(Style::default().fg(Color::DarkGray))];
+    }
+    let mut bar = vec![];
+    let mut marks = vec![];
+    for (seg, w) in bar_segments(regions, width) {
+        let (text, style) = match seg {
+            Segment::Gap => ("┄".repeat(w), Style::default().fg(Color::DarkGray)),
+            Segment::Overlap => (
+                "!".repeat(w),
+                Style::default().fg(Color::White).bg(Color::Red),
+            ),
+            Segment::Region(i) => {
+                let name: String = regions[i].name.chars().take(w).collect();
+                let mut style = Style::default()
+                    .fg(Color::Black)
+                    .bg(REGION_COLORS[i % REGION_COLORS.len()]);
+                if selected == Some(i) {
+                    style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+                }
+                (format!("{:<w$}", name, w = w), style)
+            }
+        };
+        let mark = if selected.is_some() && seg == Segment::Region(selected.unwrap()) {
+            "^"
+        } else {
+            " "
+        };
+        marks.push(Span::raw(mark.repeat(w)));
+        bar.push(Span::styled(text, style));
+    }
+    let low = regions.iter().map(|r| r.start).min().unwrap_or(0);
+    let high = regions.iter().map(|r| r.end()).max().unwrap_or(0);
+    let low = format!("{:#010x}", low);
+    let high = format!("{:#010x}", high);
+    vec![
+        Line::from(bar),
+        Line::from(marks),
+        Line::from(format!(
+            "{}{:>w$}",
+            low,
+            high,
+            w = width.saturating_sub(low.len())
+        ))
+        .style(Style::default().fg(Color::DarkGray)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_bar_segments() {
+        let map = MemoryMap::with_emulator(
+            &json!({"regions": [
+                {"name": "flash", "base": 0x0800_0000u64, "size": 0x20000},
+                {"name": "sram", "base": 0x2000_0000u64, "size": 0x8000},
+                {"name": "sram2", "base": 0x2000_4000u64, "size": 0x8000},
+            ]}),
+            None,
+            None,
+        )
+        .unwrap();
+        let segs = bar_segments(&map.regions, 40);
+        let kinds: Vec<Segment> = segs.iter().map(|(s, _)| *s).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Segment::Region(0),
+                Segment::Gap,
+                Segment::Region(1),
+                Segment::Overlap,
+                Segment::Region(2),
+            ]
+        );
+        assert_eq!(segs.iter().map(|(_, w)| w).sum::<usize>(), 40);
+        assert_eq!(segs[1].1, GAP_WIDTH);
+        // Drawn to scale, flash is bigger than both srams together
+        assert!(segs[0].1 > segs[2].1 + segs[3].1 + segs[4].1);
+    }
+
+    #[test]
+    fn test_edit_keys() {
+        let config = json!({"regions": [
+            {"name": "flash", "base": 0x0800_0000u64, "size": 0x20000, "perms": "r-x"},
+        ]});
+        let mut window =
+            MemoryMapWindow::of_map(MemoryMap::with_emulator(&config, None, None).unwrap());
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+        window.handle_input(key('a'));
+        for c in "ram 0x20000000 64k rw-".chars() {
+            window.handle_input(key(c));
+        }
+        window.handle_input(KeyEvent::from(KeyCode::Enter));
+        window.handle_input(key('s'));
+        for c in "+16k".chars() {
+            window.handle_input(key(c));
+        }
+        window.handle_input(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(window.map.regions.len(), 3);
+        assert_eq!(window.selected(), Some(2));
+
+        let mut config = config;
+        assert_eq!(window.apply(&mut config), Ok(true));
+        assert_eq!(config["regions"][2]["base"], json!(0x2000_4000u64));
+        assert_eq!(config["regions"][2]["perms"], json!("rw-"));
+    }
+}
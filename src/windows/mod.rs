@@ -1,4 +1,9 @@
+pub mod access_rules_window;
+pub mod campaign_history_window;
 pub mod config;
 pub mod fuzzing_window;
+pub mod grammar_window;
+pub mod interrupt_window;
 pub mod main_window;
+pub mod memory_map_window;
 pub mod project_window;
@@ -1,3 +1,7 @@
+use crate::campaign::persist::STATS_DIR;
+use crate::campaign::LOGS_DIR;
+use crate::config::manifest::{ProjectManifest, MANIFEST_FILE};
+use crate::state::{AppState, StateUpdate};
 use crate::utils::centered_rect::{self, centered_rect};
 use crate::utils::file_dialogue::{FileDialogue, FileDialogueResult};
 use crate::utils::input_dialogue::InputDialogue; // Removed ExplorerInput
@@ -9,7 +13,6 @@ use crossterm::event::KeyEvent;
 
 use ratatui::{prelude::*, widgets::*};
 
-use std::any::Any;
 use std::fs;
 use std::path::Path;
 
@@ -45,19 +48,39 @@ impl<'a> ProjectWindow<'a> {
     fn set_current_dir(&mut self, path: &Path) -> Option<Vec<Request>> {
         match std::env::set_current_dir(&path) {
             Ok(_) => {
-                return Some(vec![
-                    Request::PushProperty(
-                        "project_name".into(),
-                        Box::new(String::from(path.file_name().unwrap().to_str().unwrap()))
-                            as Box<dyn Any>,
-                    ),
+                let project = std::env::current_dir().unwrap_or_else(|_| path.to_path_buf());
+                // The binary and port the project was last used with
+                let (manifest, problem) = match ProjectManifest::load(&project) {
+                    Ok(m) => {
+                        let gone = m.binary_path(&project).filter(|b| !b.exists());
+                        let problem = gone.map(|b| {
+                            format!(
+                                "The binary in {} is gone: {}, select it again",
+                                MANIFEST_FILE,
+                                b.display()
+                            )
+                        });
+                        (m, problem)
+                    }
+                    Err(e) => (ProjectManifest::default(), Some(e)),
+                };
+                let mut ret = vec![
+                    Request::Update(StateUpdate::Project(project)),
+                    Request::Update(StateUpdate::Manifest(manifest)),
                     Request::PopWindow,
                     Request::PushWindow(Box::new(MainWindow::new())),
                     Request::Popup(crate::popup::Popup::new(
                         crate::popup::PopupType::Success,
                         format!("Project opened successfully at: {}", path.display()),
                     )),
-                ]);
+                ];
+                if let Some(problem) = problem {
+                    ret.push(Request::Popup(crate::popup::Popup::new(
+                        crate::popup::PopupType::Warning,
+                        problem,
+                    )));
+                }
+                return Some(ret);
             }
             Err(e) => {
                 return Some(vec![Request::Popup(crate::popup::Popup::new(
@@ -156,7 +179,7 @@ impl<'a> Window for ProjectWindow<'a> {
         }
     }
 
-    fn render(&mut self, f: &mut Frame, area: Rect) -> Option<Vec<Request>> {
+    fn render(&mut self, f: &mut Frame, area: Rect, _state: &AppState) -> Option<Vec<Request>> {
         self.render_main(f, area);
         match self.state {
             ProjectWindowState::SelectingAction(_) => {
@@ -177,7 +200,7 @@ impl<'a> Window for ProjectWindow<'a> {
         None
     }
 
-    fn handle_input(&mut self, key: KeyEvent) -> Option<Vec<Request>> {
+    fn handle_input(&mut self, key: KeyEvent, _state: &AppState) -> Option<Vec<Request>> {
         // key is crossterm::event::KeyEvent
         match self.state {
             ProjectWindowState::SelectingAction(ref mut l) => match key.code {
@@ -302,6 +325,8 @@ impl<'a> Window for ProjectWindow<'a> {
 pub fn create_project_structure(project_path: &Path) -> Result<(), String> {
     let corpus_path = project_path.join("corpus");
     let crashes_path = project_path.join("crashes");
+    let stats_path = project_path.join(STATS_DIR);
+    let logs_path = project_path.join(LOGS_DIR);
     let config_file = project_path.join("config.json");
     let grammar_file = project_path.join("grammar.json");
 
@@ -309,6 +334,10 @@ pub fn create_project_structure(project_path: &Path) -> Result<(), String> {
         .map_err(|e| format!("Failed to create corpus directory: {}", e))?;
     fs::create_dir_all(&crashes_path)
         .map_err(|e| format!("Failed to create crashes directory: {}", e))?;
+    fs::create_dir_all(&stats_path)
+        .map_err(|e| format!("Failed to create stats directory: {}", e))?;
+    fs::create_dir_all(&logs_path)
+        .map_err(|e| format!("Failed to create logs directory: {}", e))?;
     fs::File::create(&config_file).map_err(|e| format!("Failed to create config.json: {}", e))?;
     fs::File::create(&grammar_file).map_err(|e| format!("Failed to create grammar.json: {}", e))?;
     #[cfg(feature = "for_fuzzer")]
@@ -358,6 +387,8 @@ mod tests {
         assert!(result.is_ok(), "Should succeed: {:?}", result.err());
         assert!(project_path.join("corpus").is_dir());
         assert!(project_path.join("crashes").is_dir());
+        assert!(project_path.join(STATS_DIR).is_dir());
+        assert!(project_path.join(LOGS_DIR).is_dir());
         assert!(project_path.join("config.json").is_file());
         assert!(project_path.join("grammar.json").is_file());
     }